@@ -0,0 +1,109 @@
+//! Size and speed of the session cache codecs.
+//!
+//! Run with `cargo bench -p scrybe-cache --bench codec`. Encoded sizes are
+//! printed before the timings.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use scrybe_cache::{BinaryCodec, JsonCodec, SessionCodec};
+use scrybe_core::test_util;
+use scrybe_core::types::{
+    BehavioralSignals, BrowserSignals, ClickEvent, Header, MouseButton, MouseEvent, MouseEventType,
+    NetworkSignals, ScrollEvent, Session, TimingMetrics,
+};
+use std::hint::black_box;
+
+/// A session at the event limits (1000 mouse, 100 scroll, 100 click events)
+/// following a smooth, slightly jittered path.
+fn full_session() -> Session {
+    let mouse_events = (0..1000u64)
+        .map(|i| {
+            let t = i as f64 / 40.0;
+            MouseEvent {
+                timestamp_ms: 500 + i * 16 + i % 3,
+                x: (640.0 + 300.0 * t.sin()) as i32 + (i % 5) as i32,
+                y: (360.0 + 200.0 * (t * 0.7).cos()) as i32 - (i % 3) as i32,
+                event_type: MouseEventType::Move,
+            }
+        })
+        .collect();
+    let scroll_events = (0..100u64)
+        .map(|i| ScrollEvent {
+            timestamp_ms: 1_000 + i * 150,
+            x: 0,
+            y: (i * 120) as i32,
+            delta_x: 0,
+            delta_y: 120,
+        })
+        .collect();
+    let click_events = (0..100u64)
+        .map(|i| ClickEvent {
+            timestamp_ms: 2_000 + i * 160,
+            x: 200 + (i * 37 % 800) as i32,
+            y: 150 + (i * 53 % 500) as i32,
+            button: MouseButton::Left,
+        })
+        .collect();
+
+    Session::new(
+        NetworkSignals {
+            ja3: Some("771,4865-4866-4867,0-23-65281,29-23-24,0".to_string()),
+            ja4: Some("t13d1516h2_8daaf6152771_b186095e22b6".to_string()),
+            headers: vec![
+                Header::new(
+                    "User-Agent",
+                    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/120.0",
+                ),
+                Header::new("Accept-Language", "en-US,en;q=0.9"),
+            ],
+            ..test_util::network_signals()
+        },
+        BrowserSignals {
+            canvas_hash: Some("a3f5c8d2e1b4f6a8".to_string()),
+            canvas_hash_repeat: Some("a3f5c8d2e1b4f6a8".to_string()),
+            webgl_hash: Some("b4c6d8e0f2a4b6c8".to_string()),
+            audio_hash: Some("c5d7e9f1a3b5c7d9".to_string()),
+            fonts: ["Arial", "Calibri", "Cambria", "Consolas", "Segoe UI"]
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+            plugins: vec!["PDF Viewer".to_string()],
+            timezone: "America/New_York".to_string(),
+            language: "en-US".to_string(),
+            user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) Chrome/120.0".to_string(),
+            ..test_util::browser_signals()
+        },
+        BehavioralSignals {
+            mouse_events,
+            scroll_events,
+            click_events,
+            timing: TimingMetrics::default(),
+        },
+    )
+}
+
+fn bench_codecs(c: &mut Criterion) {
+    let session = full_session();
+    let codecs: [(&str, &dyn SessionCodec); 3] = [
+        ("json", &JsonCodec),
+        ("binary-zstd3", &BinaryCodec::new(None)),
+        ("binary-zstd9", &BinaryCodec::new(Some(9))),
+    ];
+
+    let mut group = c.benchmark_group("session_codec");
+    for (label, codec) in codecs {
+        let encoded = codec.encode(&session).expect("encodes");
+        println!("{}: {} bytes", label, encoded.len());
+
+        group.throughput(Throughput::Bytes(encoded.len() as u64));
+        group.bench_with_input(BenchmarkId::new("encode", label), &session, |b, s| {
+            b.iter(|| codec.encode(black_box(s)))
+        });
+        group.bench_with_input(BenchmarkId::new("decode", label), &encoded, |b, e| {
+            b.iter(|| codec.decode(black_box(e)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_codecs);
+criterion_main!(benches);
@@ -0,0 +1,285 @@
+//! Real-time anomaly feed (RFC-0006).
+//!
+//! High-scoring sessions are written to two sorted sets holding the same
+//! members, session IDs: one scored by detection time (Unix ms) for "what
+//! is new", one scored by bot probability for "what is worst". The details
+//! of each anomaly live in a hash keyed by session ID. A re-scored session
+//! replaces its entry rather than adding another, and the oldest sessions
+//! are evicted from all three keys once the feed is full.
+//!
+//! Keys:
+//!
+//! ```text
+//! anomaly:{feed}                     # Session IDs by detection time (Sorted Set)
+//! anomaly:{feed}:by_score            # Session IDs by bot probability (Sorted Set)
+//! anomaly:{feed}:details             # Anomaly JSON by session ID (Hash)
+//! ```
+//!
+//! `{feed}` is a literal hash tag, so the keys share a Cluster slot and are
+//! updated by one script.
+
+use crate::client::RedisClient;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use scrybe_core::{
+    types::{EnrichedSession, SessionId},
+    ScrybeError,
+};
+use serde::{Deserialize, Serialize};
+
+/// Default feed name.
+pub const DEFAULT_FEED: &str = "high_bot_probability";
+
+/// Default minimum bot probability recorded in the feed.
+const DEFAULT_THRESHOLD: f64 = 0.8;
+
+/// Default number of anomalies kept.
+const DEFAULT_MAX_LEN: usize = 1_000;
+
+/// Maximum anomalies returned by a single query (DoS protection).
+pub const MAX_PAGE_SIZE: usize = 1_000;
+
+/// Records a session's anomaly, replacing any earlier one, and evicts the
+/// oldest sessions beyond the feed size.
+///
+/// KEYS[1] by-time set, KEYS[2] by-score set, KEYS[3] details hash;
+/// ARGV[1] session ID, ARGV[2] detection time (Unix ms), ARGV[3] score,
+/// ARGV[4] anomaly JSON, ARGV[5] feed size.
+const RECORD_SCRIPT: &str = r"
+redis.call('ZADD', KEYS[1], ARGV[2], ARGV[1])
+redis.call('ZADD', KEYS[2], ARGV[3], ARGV[1])
+redis.call('HSET', KEYS[3], ARGV[1], ARGV[4])
+local excess = redis.call('ZCARD', KEYS[1]) - tonumber(ARGV[5])
+if excess > 0 then
+    local evicted = redis.call('ZRANGE', KEYS[1], 0, excess - 1)
+    redis.call('ZREM', KEYS[1], unpack(evicted))
+    redis.call('ZREM', KEYS[2], unpack(evicted))
+    redis.call('HDEL', KEYS[3], unpack(evicted))
+end
+return 1
+";
+
+/// A high-scoring session in the anomaly feed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Anomaly {
+    /// Session that scored high
+    pub session_id: SessionId,
+    /// Fingerprint hash of the session, if computed
+    pub fingerprint_hash: Option<String>,
+    /// Bot probability (0.0 - 1.0)
+    pub score: f64,
+    /// Names of the rules that fired
+    pub rules: Vec<String>,
+    /// When the anomaly was detected
+    pub detected_at: DateTime<Utc>,
+}
+
+impl Anomaly {
+    /// Build an anomaly for an enriched session, detected now.
+    pub fn from_enriched(enriched: &EnrichedSession) -> Self {
+        Self {
+            session_id: enriched.session.id,
+            fingerprint_hash: enriched.fingerprint_hash().map(str::to_string),
+            score: enriched.bot_probability(),
+            rules: enriched
+                .evidence
+                .findings
+                .iter()
+                .map(|f| f.name.clone())
+                .collect(),
+            detected_at: Utc::now(),
+        }
+    }
+}
+
+/// Redis sorted-set feed of anomalous sessions.
+#[derive(Clone)]
+pub struct AnomalyFeed {
+    client: RedisClient,
+    by_time_key: String,
+    by_score_key: String,
+    details_key: String,
+    threshold: f64,
+    max_len: usize,
+}
+
+impl AnomalyFeed {
+    /// Create a new anomaly feed.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Redis client instance
+    /// * `feed` - Feed name (default: `high_bot_probability`)
+    /// * `threshold` - Minimum bot probability recorded (default: 0.8)
+    /// * `max_len` - Anomalies kept (default: 1000)
+    pub fn new(
+        client: RedisClient,
+        feed: Option<&str>,
+        threshold: Option<f64>,
+        max_len: Option<usize>,
+    ) -> Self {
+        let by_time_key = client.key(&format!("anomaly:{{{}}}", feed.unwrap_or(DEFAULT_FEED)));
+        Self {
+            client,
+            by_score_key: format!("{}:by_score", by_time_key),
+            details_key: format!("{}:details", by_time_key),
+            by_time_key,
+            threshold: threshold.unwrap_or(DEFAULT_THRESHOLD),
+            max_len: max_len.unwrap_or(DEFAULT_MAX_LEN).max(1),
+        }
+    }
+
+    /// Minimum bot probability recorded in the feed.
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// Record an anomaly if its score reaches the threshold.
+    ///
+    /// An earlier anomaly of the same session is replaced, so a session
+    /// re-scored after each page load or event batch keeps a single entry.
+    /// Returns `true` if the anomaly was recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn record(&self, anomaly: &Anomaly) -> Result<bool, ScrybeError> {
+        if anomaly.score < self.threshold {
+            return Ok(false);
+        }
+
+        let details = serde_json::to_string(anomaly).map_err(|e| {
+            ScrybeError::cache_error("anomaly", format!("Serialization failed: {}", e))
+        })?;
+
+        let mut conn = self.client.get_connection().await?;
+
+        redis::Script::new(RECORD_SCRIPT)
+            .key(&self.by_time_key)
+            .key(&self.by_score_key)
+            .key(&self.details_key)
+            .arg(anomaly.session_id.to_string())
+            .arg(anomaly.detected_at.timestamp_millis())
+            .arg(anomaly.score)
+            .arg(details)
+            .arg(self.max_len)
+            .invoke_async::<_, ()>(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("anomaly", format!("EVALSHA failed: {}", e)))?;
+
+        Ok(true)
+    }
+
+    /// Most recent anomalies, newest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn recent(&self, limit: usize) -> Result<Vec<Anomaly>, ScrybeError> {
+        self.range_rev(&self.by_time_key, limit).await
+    }
+
+    /// Highest-scoring anomalies still in the feed, worst first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn top(&self, limit: usize) -> Result<Vec<Anomaly>, ScrybeError> {
+        self.range_rev(&self.by_score_key, limit).await
+    }
+
+    /// Anomalies detected at or after `since`, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn since(
+        &self,
+        since: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<Anomaly>, ScrybeError> {
+        let mut conn = self.client.get_connection().await?;
+
+        let session_ids: Vec<String> = conn
+            .zrangebyscore_limit(
+                &self.by_time_key,
+                since.timestamp_millis(),
+                "+inf",
+                0,
+                limit.clamp(1, MAX_PAGE_SIZE) as isize,
+            )
+            .await
+            .map_err(|e| {
+                ScrybeError::cache_error("anomaly", format!("ZRANGEBYSCORE failed: {}", e))
+            })?;
+
+        self.details(&mut conn, &session_ids).await
+    }
+
+    /// Read the top `limit` members of a sorted set, highest score first.
+    async fn range_rev(&self, key: &str, limit: usize) -> Result<Vec<Anomaly>, ScrybeError> {
+        let stop = limit.clamp(1, MAX_PAGE_SIZE) as isize - 1;
+
+        let mut conn = self.client.get_connection().await?;
+
+        let session_ids: Vec<String> = conn
+            .zrevrange(key, 0, stop)
+            .await
+            .map_err(|e| ScrybeError::cache_error("anomaly", format!("ZRANGE failed: {}", e)))?;
+
+        self.details(&mut conn, &session_ids).await
+    }
+
+    /// Look up the anomalies of sessions, in order.
+    async fn details<C: redis::aio::ConnectionLike>(
+        &self,
+        conn: &mut C,
+        session_ids: &[String],
+    ) -> Result<Vec<Anomaly>, ScrybeError> {
+        if session_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let details: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(&self.details_key)
+            .arg(session_ids)
+            .query_async(conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("anomaly", format!("HMGET failed: {}", e)))?;
+
+        Ok(decode(details))
+    }
+}
+
+/// Decode anomaly details, skipping sessions evicted since they were listed
+/// and malformed entries.
+fn decode(details: Vec<Option<String>>) -> Vec<Anomaly> {
+    details
+        .iter()
+        .flatten()
+        .filter_map(|detail| serde_json::from_str(detail).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_skips_evicted_and_malformed_details() {
+        let anomaly = Anomaly {
+            session_id: SessionId::new(),
+            fingerprint_hash: None,
+            score: 0.9,
+            rules: vec!["teleport_click".to_string()],
+            detected_at: Utc::now(),
+        };
+        let details = vec![
+            Some(serde_json::to_string(&anomaly).unwrap()),
+            None,
+            Some("not json".to_string()),
+        ];
+
+        assert_eq!(decode(details), vec![anomaly]);
+    }
+}
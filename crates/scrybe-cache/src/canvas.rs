@@ -0,0 +1,207 @@
+//! Canvas hash populations.
+//!
+//! Sessions that match on every fingerprint signal but canvas, audio and
+//! network form a group. Each group counts its sessions, the sessions that
+//! rendered each canvas hash, and the canvas hashes rendered by exactly one
+//! session, updated atomically by a script so every worker shares one view
+//! of the population. Every session is counted at most once, so re-scoring
+//! a session or stitching a page load does not count its canvas again.
+//!
+//! Keys:
+//!
+//! ```text
+//! canvas:{group}:counts                    # Session and canvas hash counts (Hash)
+//! canvas:{group}:seen:{session}            # Session already counted (String)
+//! ```
+//!
+//! The braces are a literal hash tag, so the keys of a group share a
+//! Cluster slot and are updated by one script. Groups expire when no
+//! session joins them for the retention period. Keys are prefixed by the
+//! client's key space.
+
+use crate::client::RedisClient;
+use crate::keyspace::KeySpace;
+use scrybe_core::ScrybeError;
+
+/// Default retention of an idle group (7 days).
+const DEFAULT_TTL_SECONDS: u64 = 7 * 24 * 3_600;
+
+/// How long a counted session is remembered (1 day).
+const SEEN_TTL_SECONDS: u64 = 24 * 3_600;
+
+/// Maximum number of distinct canvas hashes counted per group (DoS protection).
+pub const MAX_CANVAS_HASHES_PER_GROUP: usize = 1_000;
+
+/// Maximum length of a group key or canvas hash (DoS protection).
+pub const MAX_KEY_LENGTH: usize = 128;
+
+/// Counts a session's canvas hash in its group, once per session, and
+/// returns the group's counts.
+///
+/// KEYS[1] counts hash, KEYS[2] seen marker; ARGV[1] group TTL, ARGV[2]
+/// seen TTL, ARGV[3] canvas hash, ARGV[4] maximum canvas hashes.
+const OBSERVE_SCRIPT: &str = r"
+local field = 'hash:' .. ARGV[3]
+if redis.call('SET', KEYS[2], 1, 'NX', 'EX', ARGV[2]) then
+    redis.call('HINCRBY', KEYS[1], 'sessions', 1)
+    local known = redis.call('HEXISTS', KEYS[1], field) == 1
+    if known or tonumber(redis.call('HGET', KEYS[1], 'hashes') or '0') < tonumber(ARGV[4]) then
+        local count = redis.call('HINCRBY', KEYS[1], field, 1)
+        if count == 1 then
+            redis.call('HINCRBY', KEYS[1], 'hashes', 1)
+            redis.call('HINCRBY', KEYS[1], 'singletons', 1)
+        elseif count == 2 then
+            redis.call('HINCRBY', KEYS[1], 'singletons', -1)
+        end
+    end
+end
+redis.call('EXPIRE', KEYS[1], ARGV[1])
+local counts = redis.call('HMGET', KEYS[1], 'sessions', field, 'singletons')
+return {tonumber(counts[1] or '0'), tonumber(counts[2] or '0'), tonumber(counts[3] or '0')}
+";
+
+/// Counts of a canvas population group, as seen by one session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanvasGroupStats {
+    /// Sessions counted in the group
+    pub sessions: u64,
+    /// Sessions that rendered the observed canvas hash (0 if the group
+    /// tracks too many hashes to count it)
+    pub canvas_sessions: u64,
+    /// Canvas hashes rendered by exactly one session
+    pub singletons: u64,
+}
+
+impl CanvasGroupStats {
+    /// Share of sessions whose canvas hash no other session rendered.
+    pub fn singleton_ratio(&self) -> f64 {
+        if self.sessions == 0 {
+            return 0.0;
+        }
+        self.singletons as f64 / self.sessions as f64
+    }
+}
+
+/// Redis-backed canvas hash counts per fingerprint group.
+#[derive(Clone)]
+pub struct CanvasPopulations {
+    client: RedisClient,
+    ttl_seconds: u64,
+}
+
+impl CanvasPopulations {
+    /// Create canvas populations.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Redis client instance
+    /// * `ttl_seconds` - Retention of an idle group (default: 604800 = 7 days)
+    pub fn new(client: RedisClient, ttl_seconds: Option<u64>) -> Self {
+        Self {
+            client,
+            ttl_seconds: ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS).max(1),
+        }
+    }
+
+    /// Count a session's canvas hash in its group and return the group's
+    /// counts.
+    ///
+    /// A session already counted leaves the counts unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ValidationError` if the group key or canvas
+    /// hash is longer than [`MAX_KEY_LENGTH`], or `ScrybeError::CacheError`
+    /// if the operation fails.
+    pub async fn observe(
+        &self,
+        group: &str,
+        session: &str,
+        canvas: &str,
+    ) -> Result<CanvasGroupStats, ScrybeError> {
+        validate_length("group", group)?;
+        validate_length("canvas_hash", canvas)?;
+
+        let mut conn = self.client.get_connection().await?;
+
+        let (sessions, canvas_sessions, singletons): (u64, u64, u64) =
+            redis::Script::new(OBSERVE_SCRIPT)
+                .key(counts_key(self.client.key_space(), group))
+                .key(seen_key(self.client.key_space(), group, session))
+                .arg(self.ttl_seconds)
+                .arg(SEEN_TTL_SECONDS)
+                .arg(canvas)
+                .arg(MAX_CANVAS_HASHES_PER_GROUP)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(|e| {
+                    ScrybeError::cache_error("canvas", format!("EVALSHA failed: {}", e))
+                })?;
+
+        Ok(CanvasGroupStats {
+            sessions,
+            canvas_sessions,
+            singletons,
+        })
+    }
+}
+
+/// Reject values that would make oversized keys or hash fields.
+fn validate_length(field: &str, value: &str) -> Result<(), ScrybeError> {
+    if value.len() > MAX_KEY_LENGTH {
+        return Err(ScrybeError::validation_error(
+            field,
+            format!("<= {} bytes", MAX_KEY_LENGTH),
+            value.len().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Key of a group's counts.
+fn counts_key(keys: &KeySpace, group: &str) -> String {
+    keys.key(&format!("canvas:{{{}}}:counts", group))
+}
+
+/// Key marking a session as counted in a group.
+fn seen_key(keys: &KeySpace, group: &str, session: &str) -> String {
+    keys.key(&format!("canvas:{{{}}}:seen:{}", group, session))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys_share_group_slot() {
+        let keys = KeySpace::new("production", "acme").unwrap();
+        assert_eq!(
+            counts_key(&keys, "abc123"),
+            keys.key("canvas:{abc123}:counts")
+        );
+        assert_eq!(
+            seen_key(&keys, "abc123", "session-1"),
+            keys.key("canvas:{abc123}:seen:session-1")
+        );
+    }
+
+    #[test]
+    fn test_singleton_ratio() {
+        let stats = CanvasGroupStats {
+            sessions: 20,
+            canvas_sessions: 1,
+            singletons: 15,
+        };
+        assert_eq!(stats.singleton_ratio(), 0.75);
+        assert_eq!(CanvasGroupStats::default().singleton_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_validate_length() {
+        assert!(validate_length("canvas_hash", &"a".repeat(MAX_KEY_LENGTH)).is_ok());
+        assert!(matches!(
+            validate_length("canvas_hash", &"a".repeat(MAX_KEY_LENGTH + 1)),
+            Err(ScrybeError::ValidationError { .. })
+        ));
+    }
+}
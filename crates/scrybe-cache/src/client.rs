@@ -1,18 +1,280 @@
 //! Redis client with connection pooling.
-use deadpool_redis::{Config, Pool, Runtime};
+//!
+//! Connects to a single server, to a master discovered through Sentinel,
+//! or to a Cluster (see [`RedisTopology`]). Keys that are read or written
+//! together carry a hash tag, e.g. `session:{id}` and `session:{id}:events`,
+//! so a Cluster keeps them in one slot. Every key is also prefixed by the
+//! client's [`KeySpace`].
+
+use crate::keyspace::KeySpace;
+use async_trait::async_trait;
+use deadpool::managed::{self, Hook, HookError, Metrics, RecycleError, RecycleResult};
+use deadpool::managed::{PoolError, TimeoutType};
+use deadpool_redis::Runtime;
+use redis::aio::ConnectionLike;
+use redis::cluster_routing::{Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr};
+use redis::sentinel::{Sentinel, SentinelNodeConnectionInfo};
+use redis::{Cmd, IntoConnectionInfo, Pipeline, RedisError, RedisFuture, Value};
 use scrybe_core::ScrybeError;
+use serde::Serialize;
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// Largest accepted pool size (DoS protection against a misconfigured
+/// service exhausting Redis `maxclients`).
+const MAX_POOL_SIZE: usize = 1024;
+
+/// Connection pool settings.
+///
+/// Every checkout is bounded by `wait_timeout`, so a saturated pool fails
+/// with `ScrybeError::PoolExhausted` instead of queueing callers forever.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RedisPoolConfig {
+    /// Maximum pooled connections
+    pub max_size: usize,
+    /// How long a caller waits for a free connection
+    pub wait_timeout: Duration,
+    /// How long opening a new connection may take
+    pub connect_timeout: Duration,
+    /// How long checking a returned connection may take before it is
+    /// replaced
+    pub recycle_timeout: Duration,
+    /// Age after which a connection is closed rather than reused (`None`
+    /// keeps connections for the life of the process)
+    pub max_lifetime: Option<Duration>,
+}
+
+impl Default for RedisPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 20,
+            wait_timeout: Duration::from_secs(1),
+            connect_timeout: Duration::from_secs(5),
+            recycle_timeout: Duration::from_secs(1),
+            max_lifetime: Some(Duration::from_secs(30 * 60)),
+        }
+    }
+}
+
+impl RedisPoolConfig {
+    /// Load pool settings from environment variables.
+    ///
+    /// - `SCRYBE_REDIS_POOL_SIZE` (default: 20)
+    /// - `SCRYBE_REDIS_WAIT_TIMEOUT_MS` (default: 1000)
+    /// - `SCRYBE_REDIS_CONNECT_TIMEOUT_MS` (default: 5000)
+    /// - `SCRYBE_REDIS_RECYCLE_TIMEOUT_MS` (default: 1000)
+    /// - `SCRYBE_REDIS_MAX_LIFETIME_SECS` (default: 1800, 0 disables)
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a variable is not a number or
+    /// the pool size is out of range.
+    pub fn from_env() -> Result<Self, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load pool settings from a variable lookup.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a variable is not a number or
+    /// the pool size is out of range.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ScrybeError> {
+        let defaults = Self::default();
+        let millis = |key: &str, default: Duration| {
+            parse(&var, key, default.as_millis() as u64).map(Duration::from_millis)
+        };
+        let max_lifetime_secs = parse(
+            &var,
+            "SCRYBE_REDIS_MAX_LIFETIME_SECS",
+            defaults.max_lifetime.map_or(0, |d| d.as_secs()),
+        )?;
+
+        let config = Self {
+            max_size: parse(&var, "SCRYBE_REDIS_POOL_SIZE", defaults.max_size)?,
+            wait_timeout: millis("SCRYBE_REDIS_WAIT_TIMEOUT_MS", defaults.wait_timeout)?,
+            connect_timeout: millis("SCRYBE_REDIS_CONNECT_TIMEOUT_MS", defaults.connect_timeout)?,
+            recycle_timeout: millis("SCRYBE_REDIS_RECYCLE_TIMEOUT_MS", defaults.recycle_timeout)?,
+            max_lifetime: (max_lifetime_secs > 0).then(|| Duration::from_secs(max_lifetime_secs)),
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ScrybeError> {
+        if self.max_size == 0 || self.max_size > MAX_POOL_SIZE {
+            return Err(ScrybeError::config_error(format!(
+                "Redis pool size must be between 1 and {}, got {}",
+                MAX_POOL_SIZE, self.max_size
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Parse an optional variable, falling back to a default.
+fn parse<T>(var: impl Fn(&str) -> Option<String>, key: &str, default: T) -> Result<T, ScrybeError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match var(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|e| ScrybeError::config_error(format!("Invalid {}: {}", key, e))),
+        None => Ok(default),
+    }
+}
+
+/// Where Redis runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RedisTopology {
+    /// A single server
+    Standalone {
+        /// Server URL (e.g., `redis://localhost:6379`)
+        url: String,
+    },
+    /// A master discovered through Sentinel and rediscovered after failover
+    Sentinel {
+        /// Sentinel URLs; their credentials and database are also used
+        /// for the master
+        urls: Vec<String>,
+        /// Name of the monitored master
+        master: String,
+    },
+    /// A Redis Cluster
+    Cluster {
+        /// URLs of the nodes to discover the cluster from
+        urls: Vec<String>,
+    },
+}
+
+impl RedisTopology {
+    /// Load the topology from environment variables.
+    ///
+    /// Exactly one of these selects the topology:
+    ///
+    /// - `SCRYBE_REDIS_URL`: a single server
+    /// - `SCRYBE_REDIS_SENTINEL_URLS` (comma-separated): Sentinel, with the
+    ///   master named by `SCRYBE_REDIS_SENTINEL_MASTER`
+    /// - `SCRYBE_REDIS_CLUSTER_URLS` (comma-separated): Cluster
+    ///
+    /// Returns `None` if none is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if more than one is set, a URL
+    /// list is empty, or the Sentinel master name is missing.
+    pub fn from_env() -> Result<Option<Self>, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load the topology from a variable lookup.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if more than one topology is
+    /// set, a URL list is empty, or the Sentinel master name is missing.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, ScrybeError> {
+        let url_list = |key: &str| {
+            var(key)
+                .map(|value| {
+                    let urls: Vec<String> = value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|url| !url.is_empty())
+                        .map(String::from)
+                        .collect();
+                    if urls.is_empty() {
+                        Err(ScrybeError::config_error(format!("Empty {}", key)))
+                    } else {
+                        Ok(urls)
+                    }
+                })
+                .transpose()
+        };
+
+        let url = var("SCRYBE_REDIS_URL");
+        let sentinel_urls = url_list("SCRYBE_REDIS_SENTINEL_URLS")?;
+        let cluster_urls = url_list("SCRYBE_REDIS_CLUSTER_URLS")?;
+
+        let set = [
+            url.is_some(),
+            sentinel_urls.is_some(),
+            cluster_urls.is_some(),
+        ];
+        if set.iter().filter(|set| **set).count() > 1 {
+            return Err(ScrybeError::config_error(
+                "Set only one of SCRYBE_REDIS_URL, SCRYBE_REDIS_SENTINEL_URLS and \
+                 SCRYBE_REDIS_CLUSTER_URLS",
+            ));
+        }
+
+        if let Some(urls) = cluster_urls {
+            return Ok(Some(Self::Cluster { urls }));
+        }
+        if let Some(urls) = sentinel_urls {
+            let master = var("SCRYBE_REDIS_SENTINEL_MASTER")
+                .ok_or_else(|| ScrybeError::config_error("Missing SCRYBE_REDIS_SENTINEL_MASTER"))?;
+            return Ok(Some(Self::Sentinel { urls, master }));
+        }
+        Ok(url.map(|url| Self::Standalone { url }))
+    }
+}
+
+/// Point-in-time connection pool usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PoolMetrics {
+    /// Maximum pooled connections
+    pub max_size: usize,
+    /// Connections checked out by callers
+    pub in_use: usize,
+    /// Open connections waiting in the pool
+    pub idle: usize,
+    /// Callers waiting for a connection
+    pub waiters: usize,
+}
+
+impl From<deadpool::Status> for PoolMetrics {
+    fn from(status: deadpool::Status) -> Self {
+        Self {
+            max_size: status.max_size,
+            in_use: status.size.saturating_sub(status.available),
+            idle: status.available,
+            waiters: status.waiting,
+        }
+    }
+}
+
+/// Pool of the configured topology.
+#[derive(Clone)]
+enum Backend {
+    Standalone(deadpool_redis::Pool),
+    Sentinel(managed::Pool<SentinelManager>),
+    Cluster(deadpool_redis::cluster::Pool),
+}
 
 /// Redis client with connection pool.
 ///
-/// Uses `deadpool-redis` for connection pooling with configurable pool size.
+/// Uses `deadpool-redis` for connection pooling. Pool size, timeouts and
+/// connection lifetime come from [`RedisPoolConfig`]. Keys are prefixed
+/// by the client's [`KeySpace`], the default one unless set with
+/// [`RedisClient::with_key_space`].
 #[derive(Clone)]
 pub struct RedisClient {
-    pool: Pool,
+    backend: Backend,
+    wait_timeout: Duration,
+    key_space: KeySpace,
 }
 
 impl RedisClient {
     /// Create a new Redis client with connection pool.
     ///
+    /// Uses the default timeouts and connection lifetime of
+    /// [`RedisPoolConfig`].
+    ///
     /// # Arguments
     ///
     /// * `redis_url` - Redis connection URL (e.g., `redis://localhost:6379`)
@@ -31,37 +293,132 @@ impl RedisClient {
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn new(redis_url: &str, _pool_size: usize) -> Result<Self, ScrybeError> {
-        let cfg = Config::from_url(redis_url);
+    pub async fn new(redis_url: &str, pool_size: usize) -> Result<Self, ScrybeError> {
+        let config = RedisPoolConfig {
+            max_size: pool_size,
+            ..RedisPoolConfig::default()
+        };
+        Self::with_config(redis_url, config).await
+    }
 
-        let pool = cfg.create_pool(Some(Runtime::Tokio1)).map_err(|e| {
-            ScrybeError::cache_error("redis", format!("Pool creation failed: {}", e))
-        })?;
+    /// Create a new client for a single server with explicit pool
+    /// settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the settings are invalid and
+    /// `ScrybeError::CacheError` if connection fails.
+    pub async fn with_config(
+        redis_url: &str,
+        config: RedisPoolConfig,
+    ) -> Result<Self, ScrybeError> {
+        let topology = RedisTopology::Standalone {
+            url: redis_url.to_string(),
+        };
+        Self::connect(&topology, config).await
+    }
+
+    /// Create a new client for any topology.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the settings are invalid and
+    /// `ScrybeError::CacheError` if connection fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use scrybe_cache::{RedisClient, RedisPoolConfig, RedisTopology};
+    /// # async fn example() -> Result<(), scrybe_core::ScrybeError> {
+    /// let topology = RedisTopology::Sentinel {
+    ///     urls: vec!["redis://localhost:26379".to_string()],
+    ///     master: "scrybe".to_string(),
+    /// };
+    /// let client = RedisClient::connect(&topology, RedisPoolConfig::default()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect(
+        topology: &RedisTopology,
+        config: RedisPoolConfig,
+    ) -> Result<Self, ScrybeError> {
+        config.validate()?;
+
+        let invalid =
+            |e: RedisError| ScrybeError::cache_error("redis", format!("Invalid URL: {}", e));
+        let backend = match topology {
+            RedisTopology::Standalone { url } => Backend::Standalone(build_pool(
+                deadpool_redis::Manager::new(url.as_str()).map_err(invalid)?,
+                &config,
+            )?),
+            RedisTopology::Sentinel { urls, master } => Backend::Sentinel(build_pool(
+                SentinelManager::new(urls, master).map_err(invalid)?,
+                &config,
+            )?),
+            RedisTopology::Cluster { urls } => Backend::Cluster(build_pool(
+                deadpool_redis::cluster::Manager::new(urls.clone()).map_err(invalid)?,
+                &config,
+            )?),
+        };
+
+        let client = Self {
+            backend,
+            wait_timeout: config.wait_timeout,
+            key_space: KeySpace::default(),
+        };
 
         // Test connection
-        let mut conn = pool
-            .get()
-            .await
-            .map_err(|e| ScrybeError::cache_error("redis", format!("Connection failed: {}", e)))?;
+        client.health_check().await?;
 
-        redis::cmd("PING")
-            .query_async::<_, String>(&mut conn)
-            .await
-            .map_err(|e| ScrybeError::cache_error("redis", format!("PING failed: {}", e)))?;
+        Ok(client)
+    }
+
+    /// Use `key_space` for every key written through this client.
+    pub fn with_key_space(mut self, key_space: KeySpace) -> Self {
+        self.key_space = key_space;
+        self
+    }
+
+    /// Key space prefixing every key.
+    pub fn key_space(&self) -> &KeySpace {
+        &self.key_space
+    }
 
-        Ok(Self { pool })
+    /// Full Redis key for `name` in this client's key space.
+    pub fn key(&self, name: &str) -> String {
+        self.key_space.key(name)
     }
 
     /// Get a connection from the pool.
     ///
     /// # Errors
     ///
-    /// Returns `ScrybeError::CacheError` if no connection available.
-    pub async fn get_connection(&self) -> Result<deadpool_redis::Connection, ScrybeError> {
-        self.pool
-            .get()
-            .await
-            .map_err(|e| ScrybeError::cache_error("redis", format!("No connection: {}", e)))
+    /// Returns `ScrybeError::PoolExhausted` if no connection frees up
+    /// within the wait timeout and `ScrybeError::CacheError` if a new
+    /// connection cannot be opened.
+    pub async fn get_connection(&self) -> Result<PooledConnection, ScrybeError> {
+        let wait_timeout = self.wait_timeout;
+        let connection = match &self.backend {
+            Backend::Standalone(pool) => {
+                Connection::Standalone(pool.get().await.map_err(|e| pool_error(e, wait_timeout))?)
+            }
+            Backend::Sentinel(pool) => {
+                Connection::Sentinel(pool.get().await.map_err(|e| pool_error(e, wait_timeout))?)
+            }
+            Backend::Cluster(pool) => {
+                Connection::Cluster(pool.get().await.map_err(|e| pool_error(e, wait_timeout))?)
+            }
+        };
+        Ok(PooledConnection(connection))
+    }
+
+    /// Current pool usage.
+    pub fn pool_metrics(&self) -> PoolMetrics {
+        match &self.backend {
+            Backend::Standalone(pool) => pool.status().into(),
+            Backend::Sentinel(pool) => pool.status().into(),
+            Backend::Cluster(pool) => pool.status().into(),
+        }
     }
 
     /// Check if Redis is healthy.
@@ -82,3 +439,359 @@ impl RedisClient {
         Ok(())
     }
 }
+
+/// Build a pool with the configured size, timeouts and lifetime.
+fn build_pool<M, W>(
+    manager: M,
+    config: &RedisPoolConfig,
+) -> Result<managed::Pool<M, W>, ScrybeError>
+where
+    M: managed::Manager,
+    W: From<managed::Object<M>>,
+{
+    let mut builder = managed::Pool::builder(manager)
+        .max_size(config.max_size)
+        .wait_timeout(Some(config.wait_timeout))
+        .create_timeout(Some(config.connect_timeout))
+        .recycle_timeout(Some(config.recycle_timeout))
+        .runtime(Runtime::Tokio1);
+
+    // Connections past their lifetime fail the hook and are dropped,
+    // and the pool opens a fresh one in their place.
+    if let Some(max_lifetime) = config.max_lifetime {
+        builder = builder.pre_recycle(Hook::sync_fn(move |_, metrics| {
+            if metrics.age() > max_lifetime {
+                Err(HookError::StaticMessage("connection exceeded max lifetime"))
+            } else {
+                Ok(())
+            }
+        }));
+    }
+
+    builder
+        .build()
+        .map_err(|e| ScrybeError::cache_error("redis", format!("Pool creation failed: {}", e)))
+}
+
+/// Map a checkout failure, singling out a wait timeout as exhaustion.
+fn pool_error<E: std::fmt::Display>(error: PoolError<E>, wait_timeout: Duration) -> ScrybeError {
+    match error {
+        PoolError::Timeout(TimeoutType::Wait) => {
+            ScrybeError::pool_exhausted("redis", wait_timeout.as_millis() as u64)
+        }
+        e => ScrybeError::cache_error("redis", format!("No connection: {}", e)),
+    }
+}
+
+/// Connection checked out of a [`RedisClient`] pool.
+///
+/// Returned to the pool when dropped.
+pub struct PooledConnection(Connection);
+
+enum Connection {
+    Standalone(deadpool_redis::Connection),
+    Sentinel(managed::Object<SentinelManager>),
+    Cluster(deadpool_redis::cluster::Connection),
+}
+
+impl PooledConnection {
+    /// Send non-atomic pipelines whose commands each stay within one slot,
+    /// discarding the replies.
+    ///
+    /// A Cluster routes a whole pipeline to a single slot, so there each
+    /// pipeline is sent on its own; elsewhere they go out as one.
+    pub(crate) async fn query_pipelines(&mut self, pipes: &[Pipeline]) -> redis::RedisResult<()> {
+        if let Connection::Cluster(_) = self.0 {
+            for pipe in pipes {
+                pipe.query_async::<_, ()>(self).await?;
+            }
+            return Ok(());
+        }
+
+        let mut merged = redis::pipe();
+        for cmd in pipes.iter().flat_map(Pipeline::cmd_iter) {
+            merged.add_command(cmd.clone()).ignore();
+        }
+        merged.query_async(self).await
+    }
+
+    /// Nodes that must each be scanned to see every key.
+    ///
+    /// A single server or Sentinel master holds all keys; a Cluster is
+    /// asked for its primaries, each addressed by its first slot.
+    pub(crate) async fn scan_nodes(&mut self) -> redis::RedisResult<Vec<ScanNode>> {
+        let Connection::Cluster(conn) = &mut self.0 else {
+            return Ok(vec![ScanNode(None)]);
+        };
+
+        let slots: Vec<Vec<Value>> = redis::cmd("CLUSTER")
+            .arg("SLOTS")
+            .query_async(&mut **conn)
+            .await?;
+
+        let mut primaries: Vec<(String, u16)> = Vec::new();
+        let mut nodes = Vec::new();
+        for range in &slots {
+            // [start, end, [host, port, ...], replicas...]
+            let (Some(start), Some(Value::Bulk(primary))) = (range.first(), range.get(2)) else {
+                continue;
+            };
+            let (Some(host), Some(port)) = (primary.first(), primary.get(1)) else {
+                continue;
+            };
+            let address: (String, u16) = (
+                redis::from_redis_value(host)?,
+                redis::from_redis_value(port)?,
+            );
+            if !primaries.contains(&address) {
+                primaries.push(address);
+                nodes.push(ScanNode(Some(redis::from_redis_value(start)?)));
+            }
+        }
+        Ok(nodes)
+    }
+
+    /// One `SCAN` step on `node`, returning the next cursor (0 when done)
+    /// and the matching keys.
+    pub(crate) async fn scan(
+        &mut self,
+        node: &ScanNode,
+        cursor: u64,
+        pattern: &str,
+        count: usize,
+    ) -> redis::RedisResult<(u64, Vec<String>)> {
+        let mut cmd = redis::cmd("SCAN");
+        cmd.arg(cursor)
+            .arg("MATCH")
+            .arg(pattern)
+            .arg("COUNT")
+            .arg(count);
+
+        match (&mut self.0, node.0) {
+            (Connection::Cluster(conn), Some(slot)) => {
+                let routing = RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(
+                    Route::new(slot, SlotAddr::Master),
+                ));
+                let reply = conn.route_command(&cmd, routing).await?;
+                redis::from_redis_value(&reply)
+            }
+            _ => cmd.query_async(self).await,
+        }
+    }
+}
+
+/// Node to run a `SCAN` on: the slot of a Cluster primary, or `None` for
+/// the only server.
+pub(crate) struct ScanNode(Option<u16>);
+
+impl ConnectionLike for PooledConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match &mut self.0 {
+            Connection::Standalone(conn) => conn.req_packed_command(cmd),
+            Connection::Sentinel(conn) => conn.req_packed_command(cmd),
+            Connection::Cluster(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match &mut self.0 {
+            Connection::Standalone(conn) => conn.req_packed_commands(cmd, offset, count),
+            Connection::Sentinel(conn) => conn.req_packed_commands(cmd, offset, count),
+            Connection::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match &self.0 {
+            Connection::Standalone(conn) => conn.get_db(),
+            Connection::Sentinel(conn) => conn.get_db(),
+            Connection::Cluster(conn) => conn.get_db(),
+        }
+    }
+}
+
+/// Opens connections to the master the Sentinels currently report.
+///
+/// Each connection is dedicated (not multiplexed), so `WATCH` applies to
+/// its holder alone.
+struct SentinelManager {
+    sentinel: Mutex<Sentinel>,
+    master: String,
+    node: SentinelNodeConnectionInfo,
+}
+
+impl SentinelManager {
+    fn new(urls: &[String], master: &str) -> redis::RedisResult<Self> {
+        let node = urls
+            .first()
+            .map(|url| url.as_str().into_connection_info())
+            .transpose()?
+            .map(|info| info.redis);
+
+        Ok(Self {
+            sentinel: Mutex::new(Sentinel::build(urls.to_vec())?),
+            master: master.to_string(),
+            node: SentinelNodeConnectionInfo {
+                tls_mode: None,
+                redis_connection_info: node,
+            },
+        })
+    }
+}
+
+#[async_trait]
+impl managed::Manager for SentinelManager {
+    type Type = redis::aio::Connection;
+    type Error = RedisError;
+
+    async fn create(&self) -> Result<Self::Type, Self::Error> {
+        let client = self
+            .sentinel
+            .lock()
+            .await
+            .async_master_for(&self.master, Some(&self.node))
+            .await?;
+        client.get_async_connection().await
+    }
+
+    async fn recycle(&self, conn: &mut Self::Type, _: &Metrics) -> RecycleResult<Self::Error> {
+        // A master demoted by failover still answers, so check its role
+        let (role,): (Vec<Value>,) = redis::pipe()
+            .cmd("UNWATCH")
+            .ignore()
+            .cmd("ROLE")
+            .query_async(conn)
+            .await?;
+
+        match role.first().map(redis::from_redis_value::<String>) {
+            Some(Ok(role)) if role == "master" => Ok(()),
+            _ => Err(RecycleError::StaticMessage(
+                "connection is not to the master",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(vars: &[(&str, &str)]) -> Result<RedisPoolConfig, ScrybeError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        RedisPoolConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_pool_config_defaults() {
+        assert_eq!(load(&[]).unwrap(), RedisPoolConfig::default());
+    }
+
+    #[test]
+    fn test_pool_config_from_vars() {
+        let config = load(&[
+            ("SCRYBE_REDIS_POOL_SIZE", "64"),
+            ("SCRYBE_REDIS_WAIT_TIMEOUT_MS", "250"),
+            ("SCRYBE_REDIS_MAX_LIFETIME_SECS", "0"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.max_size, 64);
+        assert_eq!(config.wait_timeout, Duration::from_millis(250));
+        assert_eq!(config.connect_timeout, Duration::from_secs(5));
+        assert_eq!(config.max_lifetime, None);
+    }
+
+    #[test]
+    fn test_pool_config_rejects_bad_size() {
+        for size in ["0", "100000", "many"] {
+            let result = load(&[("SCRYBE_REDIS_POOL_SIZE", size)]);
+            assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+        }
+    }
+
+    #[test]
+    fn test_wait_timeout_is_pool_exhausted() {
+        let error = pool_error::<RedisError>(
+            PoolError::Timeout(TimeoutType::Wait),
+            Duration::from_millis(250),
+        );
+        assert!(matches!(
+            error,
+            ScrybeError::PoolExhausted { waited_ms: 250, .. }
+        ));
+
+        let error =
+            pool_error::<RedisError>(PoolError::Timeout(TimeoutType::Create), Duration::ZERO);
+        assert!(matches!(error, ScrybeError::CacheError { .. }));
+    }
+
+    fn topology(vars: &[(&str, &str)]) -> Result<Option<RedisTopology>, ScrybeError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        RedisTopology::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_topology_from_vars() {
+        assert_eq!(topology(&[]).unwrap(), None);
+        assert_eq!(
+            topology(&[("SCRYBE_REDIS_URL", "redis://localhost:6379")]).unwrap(),
+            Some(RedisTopology::Standalone {
+                url: "redis://localhost:6379".to_string()
+            })
+        );
+        assert_eq!(
+            topology(&[
+                (
+                    "SCRYBE_REDIS_SENTINEL_URLS",
+                    "redis://s1:26379, redis://s2:26379"
+                ),
+                ("SCRYBE_REDIS_SENTINEL_MASTER", "scrybe"),
+            ])
+            .unwrap(),
+            Some(RedisTopology::Sentinel {
+                urls: vec![
+                    "redis://s1:26379".to_string(),
+                    "redis://s2:26379".to_string()
+                ],
+                master: "scrybe".to_string(),
+            })
+        );
+        assert_eq!(
+            topology(&[(
+                "SCRYBE_REDIS_CLUSTER_URLS",
+                "redis://n1:7000,redis://n2:7001,"
+            )])
+            .unwrap(),
+            Some(RedisTopology::Cluster {
+                urls: vec!["redis://n1:7000".to_string(), "redis://n2:7001".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_topology_rejects_ambiguous_or_incomplete() {
+        let both = topology(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_REDIS_CLUSTER_URLS", "redis://n1:7000"),
+        ]);
+        assert!(matches!(both, Err(ScrybeError::ConfigError(_))));
+
+        let no_master = topology(&[("SCRYBE_REDIS_SENTINEL_URLS", "redis://s1:26379")]);
+        assert!(matches!(no_master, Err(ScrybeError::ConfigError(_))));
+
+        let empty = topology(&[("SCRYBE_REDIS_CLUSTER_URLS", " , ")]);
+        assert!(matches!(empty, Err(ScrybeError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,602 @@
+//! Encodings for sessions stored in the session cache.
+//!
+//! Behavioral events dominate the size of a cached session (up to 1000
+//! mouse events), and consecutive events differ by a few milliseconds and
+//! pixels. [`BinaryCodec`] stores each event collection as columns of
+//! deltas from the previous event, serializes sessions and event batches
+//! as MessagePack and compresses them with zstd. [`JsonCodec`] stores
+//! plain JSON.
+//!
+//! Binary values start with a format version byte. JSON values start with
+//! `{`, which is never a valid version, so both codecs decode values
+//! written by either, including sessions cached before binary encoding
+//! existed.
+
+use chrono::{DateTime, Utc};
+use scrybe_core::{
+    types::{
+        BehavioralSignals, BrowserSignals, ClickEvent, EventBatch, MouseButton, MouseEvent,
+        MouseEventType, NetworkSignals, ScrollEvent, Session, SessionId, TimingMetrics,
+    },
+    ScrybeError,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Version byte of the current binary format.
+pub const BINARY_FORMAT_V1: u8 = 1;
+
+/// First byte of a JSON-encoded session.
+const JSON_PREFIX: u8 = b'{';
+
+/// Default zstd compression level.
+const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
+
+/// Maximum decompressed size of a session (DoS protection).
+pub const MAX_DECODED_BYTES: usize = 16 * 1024 * 1024;
+
+/// Encodes sessions for storage in the session cache.
+pub trait SessionCodec: Send + Sync {
+    /// Short name for logs and metrics (e.g., "json").
+    fn name(&self) -> &'static str;
+
+    /// Encode a session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if encoding fails.
+    fn encode(&self, session: &Session) -> Result<Vec<u8>, ScrybeError>;
+
+    /// Decode a session written by any codec.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the value is malformed or uses
+    /// an unknown format version.
+    fn decode(&self, bytes: &[u8]) -> Result<Session, ScrybeError> {
+        decode(bytes)
+    }
+
+    /// Encode a session's event collections.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if encoding fails.
+    fn encode_events(&self, events: &EventBatch) -> Result<Vec<u8>, ScrybeError>;
+
+    /// Decode event collections written by any codec.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the value is malformed or uses
+    /// an unknown format version.
+    fn decode_events(&self, bytes: &[u8]) -> Result<EventBatch, ScrybeError> {
+        decode_events(bytes)
+    }
+}
+
+/// Plain JSON encoding.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl SessionCodec for JsonCodec {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn encode(&self, session: &Session) -> Result<Vec<u8>, ScrybeError> {
+        to_json(session)
+    }
+
+    fn encode_events(&self, events: &EventBatch) -> Result<Vec<u8>, ScrybeError> {
+        to_json(events)
+    }
+}
+
+/// Delta-encoded MessagePack compressed with zstd.
+#[derive(Debug, Clone, Copy)]
+pub struct BinaryCodec {
+    level: i32,
+}
+
+impl BinaryCodec {
+    /// Create a binary codec.
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - zstd compression level (default: 3)
+    pub fn new(level: Option<i32>) -> Self {
+        Self {
+            level: level.unwrap_or(DEFAULT_COMPRESSION_LEVEL),
+        }
+    }
+}
+
+impl Default for BinaryCodec {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl SessionCodec for BinaryCodec {
+    fn name(&self) -> &'static str {
+        "binary"
+    }
+
+    fn encode(&self, session: &Session) -> Result<Vec<u8>, ScrybeError> {
+        self.pack(&CompactSession::from(session))
+    }
+
+    fn encode_events(&self, events: &EventBatch) -> Result<Vec<u8>, ScrybeError> {
+        self.pack(&CompactEvents::new(
+            &events.mouse_events,
+            &events.scroll_events,
+            &events.click_events,
+        ))
+    }
+}
+
+impl BinaryCodec {
+    /// Serialize as MessagePack, compress, and prefix the format version.
+    fn pack(&self, value: &impl Serialize) -> Result<Vec<u8>, ScrybeError> {
+        let packed = rmp_serde::to_vec_named(value).map_err(|e| {
+            ScrybeError::cache_error("codec", format!("MessagePack serialization failed: {}", e))
+        })?;
+        let compressed = zstd::bulk::compress(&packed, self.level)
+            .map_err(|e| ScrybeError::cache_error("codec", format!("Compression failed: {}", e)))?;
+
+        let mut bytes = Vec::with_capacity(compressed.len() + 1);
+        bytes.push(BINARY_FORMAT_V1);
+        bytes.extend_from_slice(&compressed);
+        Ok(bytes)
+    }
+}
+
+/// Serialize as JSON.
+fn to_json(value: &impl Serialize) -> Result<Vec<u8>, ScrybeError> {
+    serde_json::to_vec(value)
+        .map_err(|e| ScrybeError::cache_error("codec", format!("JSON serialization failed: {}", e)))
+}
+
+/// Decode a session in any supported format.
+///
+/// # Errors
+///
+/// Returns `ScrybeError::CacheError` if the value is malformed or uses an
+/// unknown format version.
+pub fn decode(bytes: &[u8]) -> Result<Session, ScrybeError> {
+    unpack::<CompactSession, Session>(bytes)?.into_value()
+}
+
+/// Decode event collections in any supported format.
+///
+/// # Errors
+///
+/// Returns `ScrybeError::CacheError` if the value is malformed or uses an
+/// unknown format version.
+pub fn decode_events(bytes: &[u8]) -> Result<EventBatch, ScrybeError> {
+    unpack::<CompactEvents, EventBatch>(bytes)?.into_value()
+}
+
+/// A value decoded from either format.
+enum Unpacked<C, J> {
+    /// Binary format, still column-encoded
+    Compact(C),
+    /// JSON
+    Json(J),
+}
+
+impl<C, J> Unpacked<C, J>
+where
+    J: TryFrom<C, Error = ScrybeError>,
+{
+    /// The decoded value, converting the binary form.
+    fn into_value(self) -> Result<J, ScrybeError> {
+        match self {
+            Self::Compact(compact) => compact.try_into(),
+            Self::Json(value) => Ok(value),
+        }
+    }
+}
+
+/// Decode a value written as JSON (`J`) or in the binary format (`C`).
+fn unpack<C, J>(bytes: &[u8]) -> Result<Unpacked<C, J>, ScrybeError>
+where
+    C: DeserializeOwned,
+    J: DeserializeOwned,
+{
+    match bytes.first() {
+        Some(&JSON_PREFIX) => serde_json::from_slice(bytes)
+            .map(Unpacked::Json)
+            .map_err(|e| {
+                ScrybeError::cache_error("codec", format!("JSON deserialization failed: {}", e))
+            }),
+        Some(&BINARY_FORMAT_V1) => {
+            let packed = zstd::bulk::decompress(&bytes[1..], MAX_DECODED_BYTES).map_err(|e| {
+                ScrybeError::cache_error("codec", format!("Decompression failed: {}", e))
+            })?;
+            rmp_serde::from_slice(&packed)
+                .map(Unpacked::Compact)
+                .map_err(|e| {
+                    ScrybeError::cache_error(
+                        "codec",
+                        format!("MessagePack deserialization failed: {}", e),
+                    )
+                })
+        }
+        Some(version) => Err(ScrybeError::cache_error(
+            "codec",
+            format!("Unsupported format version {}", version),
+        )),
+        None => Err(ScrybeError::cache_error("codec", "Empty value")),
+    }
+}
+
+/// Session with event collections stored as delta-encoded columns.
+#[derive(Serialize, Deserialize)]
+struct CompactSession {
+    id: SessionId,
+    timestamp: DateTime<Utc>,
+    network: NetworkSignals,
+    browser: BrowserSignals,
+    timing: TimingMetrics,
+    mouse: MouseColumns,
+    scroll: ScrollColumns,
+    click: ClickColumns,
+    page_loads: u32,
+    page_offset_ms: u64,
+}
+
+/// Event collections stored as delta-encoded columns.
+#[derive(Serialize, Deserialize)]
+struct CompactEvents {
+    mouse: MouseColumns,
+    scroll: ScrollColumns,
+    click: ClickColumns,
+}
+
+/// Mouse events: timestamp and position deltas, and event types.
+#[derive(Serialize, Deserialize)]
+struct MouseColumns {
+    t: Vec<i64>,
+    x: Vec<i32>,
+    y: Vec<i32>,
+    kind: Vec<MouseEventType>,
+}
+
+/// Scroll events: timestamp and position deltas, and scroll deltas.
+#[derive(Serialize, Deserialize)]
+struct ScrollColumns {
+    t: Vec<i64>,
+    x: Vec<i32>,
+    y: Vec<i32>,
+    delta_x: Vec<i32>,
+    delta_y: Vec<i32>,
+}
+
+/// Click events: timestamp and position deltas, and buttons.
+#[derive(Serialize, Deserialize)]
+struct ClickColumns {
+    t: Vec<i64>,
+    x: Vec<i32>,
+    y: Vec<i32>,
+    button: Vec<MouseButton>,
+}
+
+impl CompactEvents {
+    /// Encode event collections as columns.
+    fn new(mouse: &[MouseEvent], scroll: &[ScrollEvent], click: &[ClickEvent]) -> Self {
+        Self {
+            mouse: MouseColumns {
+                t: delta_u64(mouse.iter().map(|e| e.timestamp_ms)),
+                x: delta_i32(mouse.iter().map(|e| e.x)),
+                y: delta_i32(mouse.iter().map(|e| e.y)),
+                kind: mouse.iter().map(|e| e.event_type).collect(),
+            },
+            scroll: ScrollColumns {
+                t: delta_u64(scroll.iter().map(|e| e.timestamp_ms)),
+                x: delta_i32(scroll.iter().map(|e| e.x)),
+                y: delta_i32(scroll.iter().map(|e| e.y)),
+                delta_x: scroll.iter().map(|e| e.delta_x).collect(),
+                delta_y: scroll.iter().map(|e| e.delta_y).collect(),
+            },
+            click: ClickColumns {
+                t: delta_u64(click.iter().map(|e| e.timestamp_ms)),
+                x: delta_i32(click.iter().map(|e| e.x)),
+                y: delta_i32(click.iter().map(|e| e.y)),
+                button: click.iter().map(|e| e.button).collect(),
+            },
+        }
+    }
+}
+
+impl From<&Session> for CompactSession {
+    fn from(session: &Session) -> Self {
+        let behavioral = &session.behavioral;
+        let CompactEvents {
+            mouse,
+            scroll,
+            click,
+        } = CompactEvents::new(
+            &behavioral.mouse_events,
+            &behavioral.scroll_events,
+            &behavioral.click_events,
+        );
+
+        Self {
+            id: session.id,
+            timestamp: session.timestamp,
+            network: session.network.clone(),
+            browser: session.browser.clone(),
+            timing: behavioral.timing.clone(),
+            mouse,
+            scroll,
+            click,
+            page_loads: session.page_loads,
+            page_offset_ms: session.page_offset_ms,
+        }
+    }
+}
+
+impl TryFrom<CompactEvents> for EventBatch {
+    type Error = ScrybeError;
+
+    fn try_from(compact: CompactEvents) -> Result<Self, Self::Error> {
+        let CompactEvents {
+            mouse,
+            scroll,
+            click,
+        } = compact;
+
+        check_columns(
+            "mouse",
+            mouse.t.len(),
+            &[mouse.x.len(), mouse.y.len(), mouse.kind.len()],
+        )?;
+        check_columns(
+            "scroll",
+            scroll.t.len(),
+            &[
+                scroll.x.len(),
+                scroll.y.len(),
+                scroll.delta_x.len(),
+                scroll.delta_y.len(),
+            ],
+        )?;
+        check_columns(
+            "click",
+            click.t.len(),
+            &[click.x.len(), click.y.len(), click.button.len()],
+        )?;
+
+        let mouse_events = undelta_u64(&mouse.t)
+            .zip(undelta_i32(&mouse.x))
+            .zip(undelta_i32(&mouse.y))
+            .zip(mouse.kind)
+            .map(|(((timestamp_ms, x), y), event_type)| MouseEvent {
+                timestamp_ms,
+                x,
+                y,
+                event_type,
+            })
+            .collect();
+        let scroll_events = undelta_u64(&scroll.t)
+            .zip(undelta_i32(&scroll.x))
+            .zip(undelta_i32(&scroll.y))
+            .zip(scroll.delta_x.into_iter().zip(scroll.delta_y))
+            .map(|(((timestamp_ms, x), y), (delta_x, delta_y))| ScrollEvent {
+                timestamp_ms,
+                x,
+                y,
+                delta_x,
+                delta_y,
+            })
+            .collect();
+        let click_events = undelta_u64(&click.t)
+            .zip(undelta_i32(&click.x))
+            .zip(undelta_i32(&click.y))
+            .zip(click.button)
+            .map(|(((timestamp_ms, x), y), button)| ClickEvent {
+                timestamp_ms,
+                x,
+                y,
+                button,
+            })
+            .collect();
+
+        Ok(EventBatch {
+            mouse_events,
+            scroll_events,
+            click_events,
+        })
+    }
+}
+
+impl TryFrom<CompactSession> for Session {
+    type Error = ScrybeError;
+
+    fn try_from(compact: CompactSession) -> Result<Self, Self::Error> {
+        let events = EventBatch::try_from(CompactEvents {
+            mouse: compact.mouse,
+            scroll: compact.scroll,
+            click: compact.click,
+        })?;
+
+        let mut behavioral = BehavioralSignals {
+            mouse_events: Vec::new(),
+            scroll_events: Vec::new(),
+            click_events: Vec::new(),
+            timing: compact.timing,
+        };
+        behavioral.set_events(events);
+
+        Ok(Session {
+            id: compact.id,
+            timestamp: compact.timestamp,
+            network: compact.network,
+            browser: compact.browser,
+            behavioral,
+            page_loads: compact.page_loads,
+            page_offset_ms: compact.page_offset_ms,
+        })
+    }
+}
+
+/// Check that every column of an event collection has `len` entries.
+fn check_columns(collection: &str, len: usize, others: &[usize]) -> Result<(), ScrybeError> {
+    if others.iter().all(|&other| other == len) {
+        Ok(())
+    } else {
+        Err(ScrybeError::cache_error(
+            "codec",
+            format!("Mismatched {} event columns", collection),
+        ))
+    }
+}
+
+/// Differences between consecutive values, starting from zero.
+///
+/// Arithmetic wraps, so any sequence round-trips, including timestamps
+/// that go backwards.
+fn delta_u64(values: impl Iterator<Item = u64>) -> Vec<i64> {
+    let mut previous = 0u64;
+    values
+        .map(|value| {
+            let delta = value.wrapping_sub(previous) as i64;
+            previous = value;
+            delta
+        })
+        .collect()
+}
+
+/// Inverse of [`delta_u64`].
+fn undelta_u64(deltas: &[i64]) -> impl Iterator<Item = u64> + '_ {
+    deltas.iter().scan(0u64, |previous, &delta| {
+        *previous = previous.wrapping_add(delta as u64);
+        Some(*previous)
+    })
+}
+
+/// Differences between consecutive values, starting from zero.
+fn delta_i32(values: impl Iterator<Item = i32>) -> Vec<i32> {
+    let mut previous = 0i32;
+    values
+        .map(|value| {
+            let delta = value.wrapping_sub(previous);
+            previous = value;
+            delta
+        })
+        .collect()
+}
+
+/// Inverse of [`delta_i32`].
+fn undelta_i32(deltas: &[i32]) -> impl Iterator<Item = i32> + '_ {
+    deltas.iter().scan(0i32, |previous, &delta| {
+        *previous = previous.wrapping_add(delta);
+        Some(*previous)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+    use scrybe_core::types::Header;
+
+    fn session() -> Session {
+        let mouse_events = (0..500)
+            .map(|i| MouseEvent {
+                timestamp_ms: 1_000 + i * 16,
+                x: 400 + (i as i32 % 50) * 3,
+                y: 300 - (i as i32 % 30) * 2,
+                event_type: MouseEventType::Move,
+            })
+            .collect();
+
+        let mut session = test_util::session();
+        session.network.ja4 = Some("t13d1516h2_8daaf6152771_b186095e22b6".to_string());
+        session.network.headers = vec![Header::new("User-Agent", "Mozilla/5.0")];
+        session.browser.canvas_hash = Some("canvas".to_string());
+        session.browser.fonts = vec!["Arial".to_string()];
+        session.browser.timezone = "Europe/Berlin".to_string();
+        session.browser.language = "de-DE".to_string();
+        session.browser.user_agent = "Mozilla/5.0".to_string();
+        session.behavioral = BehavioralSignals {
+            mouse_events,
+            scroll_events: vec![ScrollEvent {
+                timestamp_ms: 2_000,
+                x: 0,
+                y: 120,
+                delta_x: 0,
+                delta_y: 120,
+            }],
+            click_events: vec![ClickEvent {
+                timestamp_ms: 9_000,
+                x: -5,
+                y: 7,
+                button: MouseButton::Other(4),
+            }],
+            timing: TimingMetrics::default(),
+        };
+        session.page_loads = 2;
+        session.page_offset_ms = 5_000;
+        session
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let session = session();
+        let codec = BinaryCodec::default();
+
+        let bytes = codec.encode(&session).unwrap();
+        assert_eq!(bytes[0], BINARY_FORMAT_V1);
+        assert_eq!(codec.decode(&bytes).unwrap(), session);
+    }
+
+    #[test]
+    fn test_binary_is_smaller_than_json() {
+        let session = session();
+        let json = JsonCodec.encode(&session).unwrap();
+        let binary = BinaryCodec::default().encode(&session).unwrap();
+
+        assert!(binary.len() * 4 < json.len());
+    }
+
+    #[test]
+    fn test_codecs_decode_each_other() {
+        let session = session();
+
+        let json = JsonCodec.encode(&session).unwrap();
+        assert_eq!(BinaryCodec::default().decode(&json).unwrap(), session);
+
+        let binary = BinaryCodec::default().encode(&session).unwrap();
+        assert_eq!(JsonCodec.decode(&binary).unwrap(), session);
+    }
+
+    #[test]
+    fn test_events_round_trip() {
+        let mut session = session();
+        let events = session.behavioral.take_events();
+
+        for codec in [&JsonCodec as &dyn SessionCodec, &BinaryCodec::default()] {
+            let bytes = codec.encode_events(&events).unwrap();
+            assert_eq!(decode_events(&bytes).unwrap(), events);
+        }
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        assert!(decode(&[9, 1, 2, 3]).is_err());
+        assert!(decode(&[]).is_err());
+    }
+
+    #[test]
+    fn test_deltas_round_trip_extremes() {
+        let timestamps = [u64::MAX, 0, 5, 3, u64::MAX / 2];
+        let deltas = delta_u64(timestamps.iter().copied());
+        assert_eq!(undelta_u64(&deltas).collect::<Vec<_>>(), timestamps);
+
+        let coordinates = [i32::MIN, i32::MAX, 0, -1, 1];
+        let deltas = delta_i32(coordinates.iter().copied());
+        assert_eq!(undelta_i32(&deltas).collect::<Vec<_>>(), coordinates);
+    }
+}
@@ -0,0 +1,435 @@
+//! Fingerprint and IP correlation index (RFC-0006).
+//!
+//! Maintains, per fingerprint, the sessions and (hashed) IPs it was seen
+//! with and when it was first and last seen, and per IP hash, the
+//! fingerprints seen from it. Memberships are sorted sets scored by the
+//! time they were last seen (Unix ms), so windowed questions such as "how
+//! many distinct IPs has this fingerprint used in the last hour" are a
+//! single `ZCOUNT`.
+//!
+//! Keys:
+//!
+//! ```text
+//! fingerprint:{hash}                 # Sessions (Sorted Set)
+//! fingerprint:{hash}:ips             # IP hashes (Sorted Set)
+//! fingerprint:{hash}:seen            # first_seen, last_seen (Hash)
+//! ip:{ip_hash}                       # first_seen, last_seen, total_sessions (Hash)
+//! ip:{ip_hash}:fingerprints          # Fingerprint hashes (Sorted Set)
+//! ```
+//!
+//! The braces are literal hash tags: the keys of one fingerprint share a
+//! Cluster slot, as do the keys of one IP hash. `total_sessions` counts
+//! sessions first recorded from the IP, so recording a re-scored session
+//! again does not inflate it. All keys are prefixed by the client's key
+//! space.
+
+use crate::client::RedisClient;
+use crate::keyspace::KeySpace;
+use chrono::{DateTime, TimeZone, Utc};
+use redis::AsyncCommands;
+use scrybe_core::{types::SessionId, ScrybeError};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Default fingerprint key TTL (24 hours).
+const DEFAULT_FINGERPRINT_TTL_SECONDS: usize = 86_400;
+
+/// Default IP key TTL (1 hour).
+const DEFAULT_IP_TTL_SECONDS: usize = 3_600;
+
+/// Maximum members kept per sorted set; the least recently seen are
+/// trimmed first (DoS protection).
+pub const MAX_TRACKED_MEMBERS: usize = 10_000;
+
+/// When a fingerprint was first and last seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FingerprintSeen {
+    /// First time the fingerprint was recorded
+    pub first_seen: DateTime<Utc>,
+    /// Most recent time the fingerprint was recorded
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Redis correlation index between fingerprints, sessions and IPs.
+///
+/// IPs are only ever stored hashed; callers pass the salted hash (see
+/// `scrybe_core::privacy::hash_ip`).
+#[derive(Clone)]
+pub struct CorrelationIndex {
+    client: RedisClient,
+    fingerprint_ttl_seconds: usize,
+    ip_ttl_seconds: usize,
+}
+
+impl CorrelationIndex {
+    /// Create a new correlation index.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Redis client instance
+    /// * `fingerprint_ttl_seconds` - TTL of fingerprint keys (default: 86400 = 24 hours)
+    /// * `ip_ttl_seconds` - TTL of IP keys (default: 3600 = 1 hour)
+    pub fn new(
+        client: RedisClient,
+        fingerprint_ttl_seconds: Option<usize>,
+        ip_ttl_seconds: Option<usize>,
+    ) -> Self {
+        Self {
+            client,
+            fingerprint_ttl_seconds: fingerprint_ttl_seconds
+                .unwrap_or(DEFAULT_FINGERPRINT_TTL_SECONDS),
+            ip_ttl_seconds: ip_ttl_seconds.unwrap_or(DEFAULT_IP_TTL_SECONDS),
+        }
+    }
+
+    /// Record that a session with `fingerprint_hash` was seen from
+    /// `ip_hash` at `seen_at`.
+    ///
+    /// The fingerprint's indexes and the IP's indexes are each updated in
+    /// one transaction and their TTLs refreshed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn record(
+        &self,
+        fingerprint_hash: &str,
+        ip_hash: &str,
+        session_id: &SessionId,
+        seen_at: DateTime<Utc>,
+    ) -> Result<(), ScrybeError> {
+        let keys = self.client.key_space();
+        let score = seen_at.timestamp_millis();
+
+        let mut conn = self.client.get_connection().await?;
+
+        // The fingerprint and the IP live in different slots, so each gets
+        // its own transaction
+        let (new_session, _): (bool, bool) = fingerprint_pipe(
+            keys,
+            fingerprint_hash,
+            ip_hash,
+            session_id,
+            score,
+            self.fingerprint_ttl_seconds as i64,
+        )
+        .query_async(&mut conn)
+        .await
+        .map_err(|e| ScrybeError::cache_error("correlation", format!("EXEC failed: {}", e)))?;
+
+        ip_pipe(
+            keys,
+            ip_hash,
+            fingerprint_hash,
+            score,
+            self.ip_ttl_seconds as i64,
+            new_session,
+        )
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .map_err(|e| ScrybeError::cache_error("correlation", format!("EXEC failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Number of distinct IPs the fingerprint was seen from within `window`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn distinct_ips(
+        &self,
+        fingerprint_hash: &str,
+        window: Duration,
+    ) -> Result<usize, ScrybeError> {
+        self.count_since(
+            &fingerprint_key(self.client.key_space(), fingerprint_hash, ":ips"),
+            window,
+        )
+        .await
+    }
+
+    /// Number of distinct sessions with the fingerprint within `window`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn session_count(
+        &self,
+        fingerprint_hash: &str,
+        window: Duration,
+    ) -> Result<usize, ScrybeError> {
+        self.count_since(
+            &fingerprint_key(self.client.key_space(), fingerprint_hash, ""),
+            window,
+        )
+        .await
+    }
+
+    /// Number of distinct fingerprints seen from the IP within `window`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn distinct_fingerprints(
+        &self,
+        ip_hash: &str,
+        window: Duration,
+    ) -> Result<usize, ScrybeError> {
+        self.count_since(
+            &ip_key(self.client.key_space(), ip_hash, ":fingerprints"),
+            window,
+        )
+        .await
+    }
+
+    /// Sessions seen with the fingerprint, most recent first, at most
+    /// `limit`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn sessions(
+        &self,
+        fingerprint_hash: &str,
+        limit: usize,
+    ) -> Result<Vec<SessionId>, ScrybeError> {
+        let key = fingerprint_key(self.client.key_space(), fingerprint_hash, "");
+        let stop = limit.clamp(1, MAX_TRACKED_MEMBERS) as isize - 1;
+
+        let mut conn = self.client.get_connection().await?;
+
+        let ids: Vec<String> = conn.zrevrange(&key, 0, stop).await.map_err(|e| {
+            ScrybeError::cache_error("correlation", format!("ZRANGE failed: {}", e))
+        })?;
+
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
+    }
+
+    /// When the fingerprint was first and last seen, if it is still
+    /// indexed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn seen(
+        &self,
+        fingerprint_hash: &str,
+    ) -> Result<Option<FingerprintSeen>, ScrybeError> {
+        let key = fingerprint_key(self.client.key_space(), fingerprint_hash, ":seen");
+
+        let mut conn = self.client.get_connection().await?;
+
+        let fields: HashMap<String, i64> = conn.hgetall(&key).await.map_err(|e| {
+            ScrybeError::cache_error("correlation", format!("HGETALL failed: {}", e))
+        })?;
+
+        let timestamp = |field: &str| {
+            fields
+                .get(field)
+                .and_then(|ms| Utc.timestamp_millis_opt(*ms).single())
+        };
+
+        Ok(match (timestamp("first_seen"), timestamp("last_seen")) {
+            (Some(first_seen), Some(last_seen)) => Some(FingerprintSeen {
+                first_seen,
+                last_seen,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Count members of a sorted set seen within `window`.
+    async fn count_since(&self, key: &str, window: Duration) -> Result<usize, ScrybeError> {
+        let window_ms = i64::try_from(window.as_millis()).unwrap_or(i64::MAX);
+        let since = Utc::now().timestamp_millis().saturating_sub(window_ms);
+
+        let mut conn = self.client.get_connection().await?;
+
+        conn.zcount(key, since, "+inf")
+            .await
+            .map_err(|e| ScrybeError::cache_error("correlation", format!("ZCOUNT failed: {}", e)))
+    }
+}
+
+/// Key of a fingerprint's index, e.g. `suffix` ":ips".
+fn fingerprint_key(keys: &KeySpace, fingerprint_hash: &str, suffix: &str) -> String {
+    keys.key(&format!("fingerprint:{{{}}}{}", fingerprint_hash, suffix))
+}
+
+/// Key of an IP hash's index, e.g. `suffix` ":fingerprints".
+fn ip_key(keys: &KeySpace, ip_hash: &str, suffix: &str) -> String {
+    keys.key(&format!("ip:{{{}}}{}", ip_hash, suffix))
+}
+
+/// Transaction recording a session and IP for a fingerprint.
+///
+/// Replies whether the session and the IP were new to the fingerprint.
+fn fingerprint_pipe(
+    keys: &KeySpace,
+    fingerprint_hash: &str,
+    ip_hash: &str,
+    session_id: &SessionId,
+    score: i64,
+    ttl: i64,
+) -> redis::Pipeline {
+    let seen_key = fingerprint_key(keys, fingerprint_hash, ":seen");
+
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    add_bounded(
+        &mut pipe,
+        &fingerprint_key(keys, fingerprint_hash, ""),
+        &session_id.to_string(),
+        score,
+        ttl,
+    );
+    add_bounded(
+        &mut pipe,
+        &fingerprint_key(keys, fingerprint_hash, ":ips"),
+        ip_hash,
+        score,
+        ttl,
+    );
+    pipe.hset_nx(&seen_key, "first_seen", score)
+        .ignore()
+        .hset(&seen_key, "last_seen", score)
+        .ignore()
+        .expire(&seen_key, ttl)
+        .ignore();
+    pipe
+}
+
+/// Transaction recording a fingerprint for an IP hash, counting the
+/// session if it is new.
+fn ip_pipe(
+    keys: &KeySpace,
+    ip_hash: &str,
+    fingerprint_hash: &str,
+    score: i64,
+    ttl: i64,
+    new_session: bool,
+) -> redis::Pipeline {
+    let key = ip_key(keys, ip_hash, "");
+
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    add_bounded(
+        &mut pipe,
+        &ip_key(keys, ip_hash, ":fingerprints"),
+        fingerprint_hash,
+        score,
+        ttl,
+    );
+    pipe.hset_nx(&key, "first_seen", score)
+        .ignore()
+        .hset(&key, "last_seen", score)
+        .ignore();
+    if new_session {
+        pipe.hincr(&key, "total_sessions", 1).ignore();
+    }
+    pipe.expire(&key, ttl).ignore();
+    pipe
+}
+
+/// Queue commands adding a member to a bounded sorted set with a TTL.
+///
+/// Replies whether the member was new. Scores are last-seen times, so
+/// trimming by rank drops the least recently seen members.
+fn add_bounded(pipe: &mut redis::Pipeline, key: &str, member: &str, score: i64, ttl: i64) {
+    pipe.zadd(key, member, score)
+        .zremrangebyrank(key, 0, -(MAX_TRACKED_MEMBERS as isize) - 1)
+        .ignore()
+        .expire(key, ttl)
+        .ignore();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use redis::cluster_routing::get_slot;
+
+    /// Commands of a pipeline with their arguments, as strings.
+    fn commands(pipe: &redis::Pipeline) -> Vec<Vec<String>> {
+        pipe.cmd_iter()
+            .map(|cmd| {
+                cmd.args_iter()
+                    .map(|arg| match arg {
+                        redis::Arg::Simple(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                        redis::Arg::Cursor => "<cursor>".to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn find<'a>(commands: &'a [Vec<String>], name: &str) -> Vec<&'a Vec<String>> {
+        commands.iter().filter(|cmd| cmd[0] == name).collect()
+    }
+
+    #[test]
+    fn test_keys_share_slots() {
+        let keys = KeySpace::new("production", "acme").unwrap();
+        let fingerprint = ["", ":ips", ":seen"].map(|suffix| fingerprint_key(&keys, "fp1", suffix));
+        let ip = ["", ":fingerprints"].map(|suffix| ip_key(&keys, "iphash", suffix));
+
+        assert_eq!(fingerprint[1], keys.key("fingerprint:{fp1}:ips"));
+        assert_eq!(ip[1], keys.key("ip:{iphash}:fingerprints"));
+        for group in [&fingerprint[..], &ip[..]] {
+            let slot = get_slot(group[0].as_bytes());
+            assert!(group.iter().all(|key| get_slot(key.as_bytes()) == slot));
+        }
+    }
+
+    #[test]
+    fn test_members_scored_by_last_seen_and_trimmed_oldest_first() {
+        let keys = KeySpace::default();
+        let session_id = SessionId::new();
+        let pipe = fingerprint_pipe(&keys, "fp1", "iphash", &session_id, 1_700_000_000_000, 60);
+        let commands = commands(&pipe);
+
+        let zadds = find(&commands, "ZADD");
+        assert_eq!(zadds.len(), 2);
+        assert_eq!(
+            zadds[0][1..],
+            [
+                fingerprint_key(&keys, "fp1", ""),
+                "1700000000000".to_string(),
+                session_id.to_string()
+            ]
+        );
+
+        // Ranks ascend by score, so the lowest ranks are the least
+        // recently seen; everything above the newest MAX_TRACKED_MEMBERS
+        // goes
+        let trims = find(&commands, "ZREMRANGEBYRANK");
+        assert_eq!(trims.len(), 2);
+        for trim in trims {
+            assert_eq!(trim[2..], ["0".to_string(), "-10001".to_string()]);
+        }
+
+        let first_seen = find(&commands, "HSETNX");
+        assert_eq!(first_seen[0][2..], ["first_seen", "1700000000000"]);
+    }
+
+    #[test]
+    fn test_total_sessions_counts_new_sessions_only() {
+        let keys = KeySpace::default();
+        let increments = |new_session: bool| {
+            find(
+                &commands(&ip_pipe(&keys, "iphash", "fp1", 1, 60, new_session)),
+                "HINCRBY",
+            )
+            .len()
+        };
+
+        assert_eq!(increments(true), 1);
+        assert_eq!(increments(false), 0);
+    }
+
+    #[test]
+    fn test_correlation_index_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<CorrelationIndex>();
+    }
+}
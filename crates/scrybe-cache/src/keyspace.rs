@@ -0,0 +1,248 @@
+//! Key namespacing per environment and tenant.
+//!
+//! Every key is prefixed with `<environment>:<tenant>:`, so several sites,
+//! or staging and production, can share one Redis deployment. The prefix
+//! carries no hash tag, so the `{...}` tags of the keys themselves still
+//! decide their Cluster slot.
+
+use crate::client::RedisClient;
+use scrybe_core::ScrybeError;
+use std::env;
+
+/// Environment used when `SCRYBE_ENVIRONMENT` is not set.
+pub const DEFAULT_ENVIRONMENT: &str = "default";
+
+/// Tenant used when `SCRYBE_TENANT` is not set.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Longest accepted environment or tenant name (DoS protection against
+/// oversized keys).
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// Keys requested per `SCAN` call when deleting a key space.
+const SCAN_COUNT: usize = 500;
+
+/// Prefix applied to every Redis key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeySpace {
+    environment: String,
+    tenant: String,
+    prefix: String,
+}
+
+impl KeySpace {
+    /// Create a key space.
+    ///
+    /// # Arguments
+    ///
+    /// * `environment` - Deployment environment (e.g., `production`)
+    /// * `tenant` - Tenant or site identifier
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a name is empty, longer than
+    /// [`MAX_NAME_LENGTH`], or contains characters other than ASCII
+    /// letters, digits, `-` and `_`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use scrybe_cache::KeySpace;
+    /// let keys = KeySpace::new("staging", "acme").unwrap();
+    /// assert_eq!(keys.key("nonce:abc"), "staging:acme:nonce:abc");
+    /// ```
+    pub fn new(environment: &str, tenant: &str) -> Result<Self, ScrybeError> {
+        validate("environment", environment)?;
+        validate("tenant", tenant)?;
+
+        Ok(Self {
+            environment: environment.to_string(),
+            tenant: tenant.to_string(),
+            prefix: format!("{}:{}:", environment, tenant),
+        })
+    }
+
+    /// Load the key space from `SCRYBE_ENVIRONMENT` and `SCRYBE_TENANT`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a name is invalid.
+    pub fn from_env() -> Result<Self, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load the key space from a variable lookup.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a name is invalid.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ScrybeError> {
+        let environment =
+            var("SCRYBE_ENVIRONMENT").unwrap_or_else(|| DEFAULT_ENVIRONMENT.to_string());
+        let tenant = var("SCRYBE_TENANT").unwrap_or_else(|| DEFAULT_TENANT.to_string());
+        Self::new(&environment, &tenant)
+    }
+
+    /// Deployment environment.
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
+    /// Tenant identifier.
+    pub fn tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    /// Full Redis key for `name`.
+    pub fn key(&self, name: &str) -> String {
+        format!("{}{}", self.prefix, name)
+    }
+
+    /// `SCAN` pattern matching every key in this key space.
+    ///
+    /// Names are restricted to characters without glob meaning, so the
+    /// prefix needs no escaping.
+    pub fn pattern(&self) -> String {
+        format!("{}*", self.prefix)
+    }
+
+    /// Delete every key in this key space, e.g. to offboard a tenant.
+    ///
+    /// Walks the keys with `SCAN` (on every primary of a Cluster) and
+    /// removes each batch with `UNLINK`, so Redis is never blocked by a
+    /// single large command. Keys written while the scan runs may survive.
+    ///
+    /// Returns the number of keys deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if a Redis operation fails.
+    pub async fn delete_all(&self, client: &RedisClient) -> Result<u64, ScrybeError> {
+        let pattern = self.pattern();
+        let mut conn = client.get_connection().await?;
+
+        let nodes = conn.scan_nodes().await.map_err(|e| {
+            ScrybeError::cache_error("keyspace", format!("CLUSTER SLOTS failed: {}", e))
+        })?;
+
+        let mut deleted = 0;
+        for node in &nodes {
+            let mut cursor = 0;
+            loop {
+                let (next, keys) = conn
+                    .scan(node, cursor, &pattern, SCAN_COUNT)
+                    .await
+                    .map_err(|e| {
+                        ScrybeError::cache_error("keyspace", format!("SCAN failed: {}", e))
+                    })?;
+
+                if !keys.is_empty() {
+                    let removed: u64 = redis::cmd("UNLINK")
+                        .arg(&keys)
+                        .query_async(&mut conn)
+                        .await
+                        .map_err(|e| {
+                            ScrybeError::cache_error("keyspace", format!("UNLINK failed: {}", e))
+                        })?;
+                    deleted += removed;
+                }
+
+                if next == 0 {
+                    break;
+                }
+                cursor = next;
+            }
+        }
+
+        Ok(deleted)
+    }
+}
+
+/// Check an environment or tenant name.
+fn validate(field: &str, name: &str) -> Result<(), ScrybeError> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(ScrybeError::config_error(format!(
+            "Key space {} must be 1 to {} characters",
+            field, MAX_NAME_LENGTH
+        )));
+    }
+    if !name
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        return Err(ScrybeError::config_error(format!(
+            "Key space {} may only contain ASCII letters, digits, '-' and '_'",
+            field
+        )));
+    }
+    Ok(())
+}
+
+impl Default for KeySpace {
+    fn default() -> Self {
+        Self {
+            environment: DEFAULT_ENVIRONMENT.to_string(),
+            tenant: DEFAULT_TENANT.to_string(),
+            prefix: format!("{}:{}:", DEFAULT_ENVIRONMENT, DEFAULT_TENANT),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_key_prefix() {
+        let keys = KeySpace::new("production", "site-a").unwrap();
+        assert_eq!(keys.key("session:{abc}"), "production:site-a:session:{abc}");
+        assert_eq!(keys.pattern(), "production:site-a:*");
+    }
+
+    #[test]
+    fn test_tenants_do_not_collide() {
+        let a = KeySpace::new("production", "site-a").unwrap();
+        let b = KeySpace::new("production", "site-b").unwrap();
+        let staging = KeySpace::new("staging", "site-a").unwrap();
+
+        assert_ne!(a.key("nonce:n"), b.key("nonce:n"));
+        assert_ne!(a.key("nonce:n"), staging.key("nonce:n"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_names() {
+        for (environment, tenant) in [
+            ("", "site"),
+            ("prod", ""),
+            ("prod", "a:b"),
+            ("prod", "{tag}"),
+            ("prod", "site*"),
+            ("prod uction", "site"),
+        ] {
+            assert!(
+                matches!(
+                    KeySpace::new(environment, tenant),
+                    Err(ScrybeError::ConfigError(_))
+                ),
+                "{:?}/{:?} should be rejected",
+                environment,
+                tenant
+            );
+        }
+
+        let long = "a".repeat(MAX_NAME_LENGTH + 1);
+        assert!(KeySpace::new("prod", &long).is_err());
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let vars: HashMap<&str, &str> =
+            [("SCRYBE_ENVIRONMENT", "staging"), ("SCRYBE_TENANT", "acme")].into();
+        let keys = KeySpace::from_vars(|key| vars.get(key).map(|v| v.to_string())).unwrap();
+        assert_eq!(keys.environment(), "staging");
+        assert_eq!(keys.tenant(), "acme");
+
+        assert_eq!(KeySpace::from_vars(|_| None).unwrap(), KeySpace::default());
+    }
+}
@@ -4,10 +4,19 @@
 //!
 //! ## Features
 //!
-//! - Session storage with TTL
+//! - Session storage with TTL and compact binary encoding
+//! - Session metadata hash with partial reads and updates
+//! - Enrichment queue on Redis Streams
+//! - Visitor linking by fingerprint
 //! - Fingerprint correlation
+//! - Real-time anomaly feed
 //! - Nonce validation
 //! - Rate limiting
+//! - Sliding-window velocity counters
+//! - Per-site page timing baselines
+//! - Canvas hash counts per fingerprint group
+//! - Standalone, Sentinel and Cluster deployments
+//! - Key namespacing per environment and tenant
 //!
 //! ## TigerStyle Compliance
 //!
@@ -19,17 +28,44 @@
 #![warn(rust_2018_idioms)]
 #![deny(unsafe_code)]
 
+/// Real-time anomaly feed.
+pub mod anomaly;
+/// Canvas hash counts per fingerprint group.
+pub mod canvas;
 /// Redis client with connection pooling.
 pub mod client;
+/// Session encodings for the session cache.
+pub mod codec;
+/// Fingerprint and IP correlation index.
+pub mod correlation;
+/// Key namespacing per environment and tenant.
+pub mod keyspace;
 /// Nonce validation for replay attack prevention.
 pub mod nonce;
+/// Enrichment queue on Redis Streams.
+pub mod queue;
 /// Rate limiting with token bucket algorithm.
 pub mod rate_limit;
 /// Session cache management.
 pub mod session;
+/// Per-site page timing baselines.
+pub mod timing;
+/// Sliding-window velocity counters.
+pub mod velocity;
+/// Visitor linking by device fingerprint.
+pub mod visitor;
 
 // Re-export main types
-pub use client::RedisClient;
+pub use anomaly::{Anomaly, AnomalyFeed};
+pub use canvas::{CanvasGroupStats, CanvasPopulations};
+pub use client::{PoolMetrics, PooledConnection, RedisClient, RedisPoolConfig, RedisTopology};
+pub use codec::{BinaryCodec, JsonCodec, SessionCodec};
+pub use correlation::{CorrelationIndex, FingerprintSeen};
+pub use keyspace::KeySpace;
 pub use nonce::NonceValidator;
+pub use queue::{QueuedSession, SessionQueue};
 pub use rate_limit::RateLimiter;
-pub use session::SessionCache;
+pub use session::{SessionCache, SessionMetadata};
+pub use timing::{MetricSummary, TimingBaselines};
+pub use velocity::{Entity, EntityKind, VelocityCounters, VelocityUpdate};
+pub use visitor::VisitorIndex;
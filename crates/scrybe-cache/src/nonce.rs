@@ -6,7 +6,8 @@ use scrybe_core::ScrybeError;
 
 /// Nonce validator for replay attack prevention.
 ///
-/// Stores nonces in Redis with 5-minute TTL. Each nonce can only be used once.
+/// Stores nonces in Redis with 5-minute TTL under the client's key space.
+/// Each nonce can only be used once per tenant.
 pub struct NonceValidator {
     client: RedisClient,
     ttl_seconds: usize,
@@ -55,24 +56,23 @@ impl NonceValidator {
     /// # }
     /// ```
     pub async fn validate_nonce(&self, nonce: &str) -> Result<bool, ScrybeError> {
-        let key = format!("nonce:{}", nonce);
+        let key = self.client.key(&format!("nonce:{}", nonce));
         let mut conn = self.client.get_connection().await?;
 
-        // Try to set the key with NX (only if not exists) and EX (expiry)
-        let result: Option<String> = conn
-            .set_nx(&key, "1")
+        // Set the key only if it does not exist, with its expiry in the same
+        // command so a nonce can never be stored without a TTL
+        let result: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg("1")
+            .arg("NX")
+            .arg("EX")
+            .arg(self.ttl_seconds)
+            .query_async(&mut conn)
             .await
             .map_err(|e| ScrybeError::cache_error("nonce", format!("SET NX failed: {}", e)))?;
 
-        // If SET NX succeeded, set the TTL
-        if result.is_some() {
-            conn.expire::<_, ()>(&key, self.ttl_seconds as i64)
-                .await
-                .map_err(|e| ScrybeError::cache_error("nonce", format!("EXPIRE failed: {}", e)))?;
-            Ok(true) // Nonce is valid (new)
-        } else {
-            Ok(false) // Nonce already exists (replay attack)
-        }
+        // Nonce is valid (new) if it was set, a replay attack otherwise
+        Ok(result.is_some())
     }
 
     /// Check if a nonce exists (without marking as used).
@@ -85,7 +85,7 @@ impl NonceValidator {
     ///
     /// Returns `ScrybeError::CacheError` if Redis operation fails.
     pub async fn exists(&self, nonce: &str) -> Result<bool, ScrybeError> {
-        let key = format!("nonce:{}", nonce);
+        let key = self.client.key(&format!("nonce:{}", nonce));
         let mut conn = self.client.get_connection().await?;
 
         let exists: bool = conn
@@ -102,6 +102,7 @@ mod tests {
     #[tokio::test]
     async fn test_nonce_validator_compiles() {
         // Placeholder - requires Redis for full testing
-        assert!(true);
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<super::NonceValidator>();
     }
 }
@@ -0,0 +1,296 @@
+//! Enrichment queue backed by a Redis Stream.
+//!
+//! The gateway appends accepted sessions with `XADD`; workers consume them
+//! through a consumer group so each entry is delivered to one worker and
+//! stays pending until acknowledged. Entries left pending by a worker that
+//! died are taken over by others with `XAUTOCLAIM`.
+
+use crate::client::RedisClient;
+use redis::streams::{StreamMaxlen, StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use scrybe_core::{types::Session, ScrybeError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Default stream key.
+pub const DEFAULT_STREAM: &str = "scrybe:enrichment";
+
+/// Default consumer group.
+pub const DEFAULT_GROUP: &str = "enrichment";
+
+/// Default approximate stream length cap.
+const DEFAULT_MAX_LEN: usize = 1_000_000;
+
+/// Stream entry field holding the JSON session.
+const SESSION_FIELD: &str = "session";
+
+/// Maximum entries returned by a single read or claim (DoS protection).
+pub const MAX_BATCH_SIZE: usize = 1_000;
+
+/// `XAUTOCLAIM` cursor at the start of the pending entries list.
+const CLAIM_START: &str = "0-0";
+
+/// Entry in an `XAUTOCLAIM` reply: ID and fields, or nil for entries
+/// deleted while pending (Redis < 7).
+type ClaimedEntry = Option<(String, Option<HashMap<String, String>>)>;
+
+/// A session read from the queue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedSession {
+    /// Stream entry ID, used to acknowledge the entry
+    pub id: String,
+    /// Decoded session, `None` if the payload is missing or malformed
+    pub session: Option<Session>,
+}
+
+impl QueuedSession {
+    /// Decode a stream entry payload.
+    fn decode(id: String, payload: Option<&str>) -> Self {
+        let session = payload.and_then(|json| serde_json::from_str(json).ok());
+        Self { id, session }
+    }
+}
+
+/// Redis Stream queue of sessions awaiting enrichment.
+///
+/// The stream is capped at roughly `max_len` entries; the oldest entries
+/// are trimmed first. Clones share the reclaim cursor.
+#[derive(Clone)]
+pub struct SessionQueue {
+    client: RedisClient,
+    stream: String,
+    group: String,
+    max_len: usize,
+    /// Where the next `XAUTOCLAIM` resumes scanning pending entries
+    claim_cursor: Arc<Mutex<String>>,
+}
+
+impl SessionQueue {
+    /// Create a new session queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Redis client instance
+    /// * `stream` - Stream key within the client's key space (default:
+    ///   `scrybe:enrichment`)
+    /// * `group` - Consumer group (default: `enrichment`)
+    /// * `max_len` - Approximate stream length cap (default: 1,000,000)
+    pub fn new(
+        client: RedisClient,
+        stream: Option<&str>,
+        group: Option<&str>,
+        max_len: Option<usize>,
+    ) -> Self {
+        Self {
+            stream: client.key(stream.unwrap_or(DEFAULT_STREAM)),
+            client,
+            group: group.unwrap_or(DEFAULT_GROUP).to_string(),
+            max_len: max_len.unwrap_or(DEFAULT_MAX_LEN),
+            claim_cursor: Arc::new(Mutex::new(CLAIM_START.to_string())),
+        }
+    }
+
+    /// Append a session to the stream.
+    ///
+    /// Returns the ID of the new entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn enqueue(&self, session: &Session) -> Result<String, ScrybeError> {
+        let payload = serde_json::to_string(session).map_err(|e| {
+            ScrybeError::cache_error("redis", format!("Serialization failed: {}", e))
+        })?;
+
+        let mut conn = self.client.get_connection().await?;
+
+        conn.xadd_maxlen(
+            &self.stream,
+            StreamMaxlen::Approx(self.max_len),
+            "*",
+            &[(SESSION_FIELD, payload)],
+        )
+        .await
+        .map_err(|e| ScrybeError::cache_error("redis", format!("XADD failed: {}", e)))
+    }
+
+    /// Create the consumer group (and the stream) if missing.
+    ///
+    /// A new group starts at the end of the stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn ensure_group(&self) -> Result<(), ScrybeError> {
+        let mut conn = self.client.get_connection().await?;
+
+        let result: redis::RedisResult<()> = conn
+            .xgroup_create_mkstream(&self.stream, &self.group, "$")
+            .await;
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+            Err(e) => Err(ScrybeError::cache_error(
+                "redis",
+                format!("XGROUP CREATE failed: {}", e),
+            )),
+        }
+    }
+
+    /// Read new entries for a consumer, blocking up to `block_ms` if none
+    /// are available.
+    ///
+    /// Entries stay pending until acknowledged with [`Self::ack`].
+    /// `count` is capped at [`MAX_BATCH_SIZE`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn read(
+        &self,
+        consumer: &str,
+        count: usize,
+        block_ms: usize,
+    ) -> Result<Vec<QueuedSession>, ScrybeError> {
+        let options = StreamReadOptions::default()
+            .group(&self.group, consumer)
+            .count(count.clamp(1, MAX_BATCH_SIZE))
+            .block(block_ms);
+
+        let mut conn = self.client.get_connection().await?;
+
+        let reply: Option<StreamReadReply> = conn
+            .xread_options(&[&self.stream], &[">"], &options)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("XREADGROUP failed: {}", e)))?;
+
+        Ok(reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .map(|entry| {
+                let payload: Option<String> = entry.get(SESSION_FIELD);
+                QueuedSession::decode(entry.id, payload.as_deref())
+            })
+            .collect())
+    }
+
+    /// Acknowledge processed entries.
+    ///
+    /// Returns the number of entries that were pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn ack(&self, ids: &[String]) -> Result<usize, ScrybeError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.client.get_connection().await?;
+
+        conn.xack(&self.stream, &self.group, ids)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("XACK failed: {}", e)))
+    }
+
+    /// Take over entries pending for at least `min_idle_ms` on any consumer.
+    ///
+    /// Used to recover entries from consumers that died before
+    /// acknowledging them. `count` is capped at [`MAX_BATCH_SIZE`]. Each
+    /// call continues scanning where the previous one stopped and wraps
+    /// around at the end, so a long pending list is covered in turns
+    /// instead of rescanning its head.
+    /// Entries deleted from the stream while pending are returned with no
+    /// session so they can be acknowledged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn reclaim(
+        &self,
+        consumer: &str,
+        min_idle_ms: u64,
+        count: usize,
+    ) -> Result<Vec<QueuedSession>, ScrybeError> {
+        let mut conn = self.client.get_connection().await?;
+        let cursor = self.claim_cursor().clone();
+
+        // Reply: [next cursor, [[id, [field, value, ...]] | nil, ...], (deleted ids)]
+        let reply: Vec<redis::Value> = redis::cmd("XAUTOCLAIM")
+            .arg(&self.stream)
+            .arg(&self.group)
+            .arg(consumer)
+            .arg(min_idle_ms)
+            .arg(&cursor)
+            .arg("COUNT")
+            .arg(count.clamp(1, MAX_BATCH_SIZE))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("XAUTOCLAIM failed: {}", e)))?;
+
+        let invalid = |e: redis::RedisError| {
+            ScrybeError::cache_error("redis", format!("Invalid XAUTOCLAIM reply: {}", e))
+        };
+        // "0-0" once the scan reached the end, starting it over next time
+        let next: String = match reply.first() {
+            Some(value) => redis::from_redis_value(value).map_err(invalid)?,
+            None => CLAIM_START.to_string(),
+        };
+        let entries: Vec<ClaimedEntry> = match reply.get(1) {
+            Some(value) => redis::from_redis_value(value).map_err(invalid)?,
+            None => Vec::new(),
+        };
+        *self.claim_cursor() = next;
+
+        Ok(entries
+            .into_iter()
+            .flatten()
+            .map(|(id, fields)| {
+                let payload = fields.as_ref().and_then(|f| f.get(SESSION_FIELD));
+                QueuedSession::decode(id, payload.map(String::as_str))
+            })
+            .collect())
+    }
+
+    /// Lock the reclaim cursor.
+    fn claim_cursor(&self) -> std::sync::MutexGuard<'_, String> {
+        // A panic cannot leave the cursor half-written
+        self.claim_cursor
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+
+    #[test]
+    fn test_decode_round_trip() {
+        let session = test_util::session();
+        let payload = serde_json::to_string(&session).unwrap();
+
+        let queued = QueuedSession::decode("1-0".to_string(), Some(&payload));
+        assert_eq!(queued.id, "1-0");
+        assert_eq!(queued.session, Some(session));
+    }
+
+    #[test]
+    fn test_decode_malformed_payload() {
+        assert!(QueuedSession::decode("1-0".to_string(), Some("{not json"))
+            .session
+            .is_none());
+        assert!(QueuedSession::decode("1-0".to_string(), None)
+            .session
+            .is_none());
+    }
+
+    #[test]
+    fn test_session_queue_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SessionQueue>();
+    }
+}
@@ -4,7 +4,21 @@ use crate::client::RedisClient;
 use redis::AsyncCommands;
 use scrybe_core::ScrybeError;
 
+/// Counts a request and starts the window on the first one, atomically so
+/// a counter is never left without a TTL. The key is passed in `KEYS` so a
+/// Cluster routes the script to its slot.
+const INCREMENT_SCRIPT: &str = r"
+local count = redis.call('INCR', KEYS[1])
+if count == 1 then
+    redis.call('EXPIRE', KEYS[1], ARGV[1])
+end
+return count
+";
+
 /// Redis-backed rate limiter using token bucket algorithm.
+///
+/// Counters live in the client's key space, so tenants have separate
+/// limits.
 pub struct RateLimiter {
     client: RedisClient,
     max_requests: usize,
@@ -50,22 +64,16 @@ impl RateLimiter {
     ///
     /// Returns `ScrybeError::CacheError` if Redis operation fails.
     pub async fn check(&self, identifier: &str) -> Result<bool, ScrybeError> {
-        let key = format!("ratelimit:{}", identifier);
+        let key = self.client.key(&format!("ratelimit:{}", identifier));
 
         let mut conn = self.client.get_connection().await?;
 
-        // Increment counter
-        let count: usize = conn
-            .incr(&key, 1)
+        let count: usize = redis::Script::new(INCREMENT_SCRIPT)
+            .key(&key)
+            .arg(self.window_seconds)
+            .invoke_async(&mut conn)
             .await
-            .map_err(|e| ScrybeError::cache_error("redis", format!("INCR failed: {}", e)))?;
-
-        // Set expiration on first request
-        if count == 1 {
-            conn.expire::<_, ()>(&key, self.window_seconds as i64)
-                .await
-                .map_err(|e| ScrybeError::cache_error("redis", format!("EXPIRE failed: {}", e)))?;
-        }
+            .map_err(|e| ScrybeError::cache_error("redis", format!("EVALSHA failed: {}", e)))?;
 
         Ok(count <= self.max_requests)
     }
@@ -76,7 +84,7 @@ impl RateLimiter {
     ///
     /// Returns `ScrybeError::CacheError` if Redis operation fails.
     pub async fn get_count(&self, identifier: &str) -> Result<usize, ScrybeError> {
-        let key = format!("ratelimit:{}", identifier);
+        let key = self.client.key(&format!("ratelimit:{}", identifier));
 
         let mut conn = self.client.get_connection().await?;
 
@@ -94,7 +102,7 @@ impl RateLimiter {
     ///
     /// Returns `ScrybeError::CacheError` if Redis operation fails.
     pub async fn reset(&self, identifier: &str) -> Result<(), ScrybeError> {
-        let key = format!("ratelimit:{}", identifier);
+        let key = self.client.key(&format!("ratelimit:{}", identifier));
 
         let mut conn = self.client.get_connection().await?;
 
@@ -111,6 +119,7 @@ mod tests {
     #[tokio::test]
     async fn test_rate_limiter_compiles() {
         // Placeholder test
-        assert!(true);
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<super::RateLimiter>();
     }
 }
@@ -1,18 +1,131 @@
 //! Session cache management with Redis.
+//!
+//! Each session is split across two keys so callers read and write only
+//! what they need:
+//!
+//! ```text
+//! session:{id}                       # Metadata and signals (Hash)
+//! session:{id}:events                # Behavioral event collections (String)
+//! ```
+//!
+//! The braces are literal: `{id}` is a hash tag, so both keys of a session
+//! share a Cluster slot and can be watched and written in one transaction.
+//! All keys, including the `sessions:dirty` set, are prefixed by the
+//! client's key space.
+//!
+//! The hash holds small, independently updated fields (`first_seen`,
+//! `last_seen`, `page_loads`, `page_offset_ms`, `request_count`,
+//! `event_count`, `fingerprint_hash`, `bot_probability`) next to
+//! `signals`, the encoded session without its events. The up to 1200
+//! events live in their own key, so reading metadata or recording a score
+//! never touches them.
 
 use crate::client::RedisClient;
+use crate::codec::{BinaryCodec, SessionCodec};
+use chrono::{DateTime, TimeZone, Utc};
 use redis::AsyncCommands;
 use scrybe_core::{
-    types::{Session, SessionId},
+    types::{EventBatch, Session, SessionId},
     ScrybeError,
 };
+use std::sync::Arc;
+
+/// Default session TTL (1 hour).
+pub const DEFAULT_TTL_SECONDS: usize = 3_600;
+
+/// Set of sessions changed since they were last scored.
+const DIRTY_KEY: &str = "sessions:dirty";
+
+/// Attempts to append events before giving up on concurrent writers.
+const MAX_APPEND_RETRIES: usize = 8;
+
+/// Maximum sessions taken from the dirty set at once (DoS protection).
+pub const MAX_DIRTY_BATCH: usize = 1_000;
+
+/// Hash field holding the encoded session without its events.
+const SIGNALS: &str = "signals";
+
+/// Metadata hash fields, in the order [`SessionMetadata`] reads them.
+const METADATA_FIELDS: [&str; 8] = [
+    "first_seen",
+    "last_seen",
+    "page_loads",
+    "page_offset_ms",
+    "request_count",
+    "event_count",
+    "fingerprint_hash",
+    "bot_probability",
+];
+
+/// Sets score fields only on sessions that are still cached, so scoring
+/// an expired session does not recreate it without a TTL.
+const RECORD_SCORE_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    return 0
+end
+redis.call('HSET', KEYS[1], 'fingerprint_hash', ARGV[1], 'bot_probability', ARGV[2])
+return 1
+";
+
+/// Session metadata kept in the session hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionMetadata {
+    /// When the session started
+    pub first_seen: DateTime<Utc>,
+    /// Last page load or event batch
+    pub last_seen: DateTime<Utc>,
+    /// Number of page loads stitched into the session
+    pub page_loads: u32,
+    /// Start of the latest page load, in milliseconds since session start
+    pub page_offset_ms: u64,
+    /// Page loads and event batches received
+    pub request_count: u64,
+    /// Behavioral events held for the session
+    pub event_count: u64,
+    /// Fingerprint hash, once the session has been scored
+    pub fingerprint_hash: Option<String>,
+    /// Bot probability (0.0 - 1.0), once the session has been scored
+    pub bot_probability: Option<f64>,
+}
+
+impl SessionMetadata {
+    /// Parse metadata from hash values read in [`METADATA_FIELDS`] order.
+    ///
+    /// Returns `None` if the session is not cached.
+    fn from_fields(values: &[Option<String>]) -> Option<Self> {
+        let field = |index: usize| values.get(index).and_then(Option::as_deref);
+        let timestamp = |index: usize| {
+            field(index)
+                .and_then(|ms| ms.parse().ok())
+                .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+        };
+        let number = |index: usize| field(index).and_then(|n| n.parse().ok()).unwrap_or(0);
+
+        let first_seen = timestamp(0)?;
+        Some(Self {
+            first_seen,
+            last_seen: timestamp(1).unwrap_or(first_seen),
+            page_loads: u32::try_from(number(2)).unwrap_or(u32::MAX),
+            page_offset_ms: number(3),
+            request_count: number(4),
+            event_count: number(5),
+            fingerprint_hash: field(6).map(str::to_string),
+            bot_probability: field(7).and_then(|p| p.parse().ok()),
+        })
+    }
+}
 
 /// Redis-backed session cache with TTL.
 ///
 /// Sessions are stored for 1 hour (3600 seconds) to minimize memory usage.
+/// Signals and events are encoded with a [`SessionCodec`]; every codec
+/// reads values written by the others, so the codec can be changed on a
+/// live cache.
+#[derive(Clone)]
 pub struct SessionCache {
     client: RedisClient,
     ttl_seconds: usize,
+    codec: Arc<dyn SessionCodec>,
 }
 
 impl SessionCache {
@@ -22,57 +135,446 @@ impl SessionCache {
     ///
     /// * `client` - Redis client instance
     /// * `ttl_seconds` - Time-to-live for sessions (default: 3600 = 1 hour)
-    pub fn new(client: RedisClient, ttl_seconds: Option<usize>) -> Self {
+    /// * `codec` - Session encoding (default: [`BinaryCodec`])
+    pub fn new(
+        client: RedisClient,
+        ttl_seconds: Option<usize>,
+        codec: Option<Arc<dyn SessionCodec>>,
+    ) -> Self {
         Self {
             client,
-            ttl_seconds: ttl_seconds.unwrap_or(3600),
+            ttl_seconds: ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS),
+            codec: codec.unwrap_or_else(|| Arc::new(BinaryCodec::default())),
         }
     }
 
-    /// Store a session in the cache with TTL.
+    /// Store a new session in the cache with TTL.
+    ///
+    /// Writes the signals and counts the request. Events already cached
+    /// for the session are kept, so batches appended concurrently are never
+    /// overwritten; store later page loads with
+    /// [`store_page_load`](Self::store_page_load).
     ///
     /// # Errors
     ///
     /// Returns `ScrybeError::CacheError` if the operation fails.
     pub async fn store(&self, session: &Session) -> Result<(), ScrybeError> {
-        let key = format!("session:{}", session.id);
-        let value = serde_json::to_string(session).map_err(|e| {
-            ScrybeError::cache_error("redis", format!("Serialization failed: {}", e))
-        })?;
+        let key = self.key(&session.id);
+        let events_key = self.events_key(&session.id);
+
+        let mut signals = session.clone();
+        let events = signals.behavioral.take_events();
+        let signals = self.codec.encode(&signals)?;
+        let encoded_events = self.codec.encode_events(&events)?;
 
         let mut conn = self.client.get_connection().await?;
 
-        conn.set_ex::<_, _, ()>(&key, &value, self.ttl_seconds as u64)
+        let mut pipe = redis::pipe();
+        self.write_signals(pipe.atomic(), &key, session, signals);
+        pipe.hset_nx(&key, "event_count", events.len() as i64)
+            .ignore()
+            .cmd("SET")
+            .arg(&events_key)
+            .arg(encoded_events)
+            .arg("NX")
+            .ignore()
+            .expire(&events_key, self.ttl_seconds as i64)
+            .ignore()
+            .query_async::<_, ()>(&mut conn)
             .await
-            .map_err(|e| ScrybeError::cache_error("redis", format!("SET failed: {}", e)))?;
+            .map_err(|e| ScrybeError::cache_error("redis", format!("EXEC failed: {}", e)))?;
 
         Ok(())
     }
 
-    /// Retrieve a session from the cache.
+    /// Store a later page load of a cached session.
+    ///
+    /// `page_load` is applied to the cached session, read without its
+    /// events, and returns the events sent with the page load; they are
+    /// appended, relative to the page start, to the events cached now. The
+    /// read-modify-write is optimistic like
+    /// [`append_events`](Self::append_events) and watches both keys, so
+    /// `page_load` may run more than once and concurrent page loads and
+    /// batches are never lost. Returns the session with its events after
+    /// the append, or `None` if it is not cached (expired or unknown).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails or keeps
+    /// conflicting with concurrent writers.
+    pub async fn store_page_load<F>(
+        &self,
+        session_id: &SessionId,
+        mut page_load: F,
+    ) -> Result<Option<Session>, ScrybeError>
+    where
+        F: FnMut(&mut Session) -> EventBatch,
+    {
+        let key = self.key(session_id);
+        let events_key = self.events_key(session_id);
+
+        let mut conn = self.client.get_connection().await?;
+
+        for _ in 0..MAX_APPEND_RETRIES {
+            watch(&mut conn, &[&key, &events_key]).await?;
+
+            let attempt = async {
+                let (signals, events): (Option<Vec<u8>>, Option<Vec<u8>>) = redis::pipe()
+                    .hget(&key, SIGNALS)
+                    .get(&events_key)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| ScrybeError::cache_error("redis", format!("GET failed: {}", e)))?;
+
+                let Some(signals) = signals else {
+                    return Ok(Appended::NotCached);
+                };
+
+                let mut session = self.codec.decode(&signals)?;
+                let page_events = page_load(&mut session);
+                session.behavioral.take_events();
+                let signals = self.codec.encode(&session)?;
+
+                let mut events = match events {
+                    Some(bytes) => self.codec.decode_events(&bytes)?,
+                    None => EventBatch::default(),
+                };
+                events.append(session.page_offset_ms, page_events);
+                let value = self.codec.encode_events(&events)?;
+
+                // EXEC replies nil if the session changed since WATCH
+                let mut pipe = redis::pipe();
+                self.write_signals(pipe.atomic(), &key, &session, signals);
+                let committed: Option<()> = pipe
+                    .hset(&key, "event_count", events.len() as i64)
+                    .ignore()
+                    .set_ex(&events_key, &value, self.ttl_seconds as u64)
+                    .ignore()
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| {
+                        ScrybeError::cache_error("redis", format!("EXEC failed: {}", e))
+                    })?;
+
+                Ok(match committed {
+                    Some(()) => {
+                        session.behavioral.set_events(events);
+                        Appended::Committed(session)
+                    }
+                    None => Appended::Conflict,
+                })
+            }
+            .await;
+
+            match unwatch_on_error(&mut conn, attempt).await? {
+                Appended::Committed(session) => return Ok(Some(session)),
+                Appended::NotCached => {
+                    unwatch(&mut conn).await?;
+                    return Ok(None);
+                }
+                Appended::Conflict => {}
+            }
+        }
+
+        Err(ScrybeError::cache_error(
+            "redis",
+            format!("Page load of session {} kept conflicting", session_id),
+        ))
+    }
+
+    /// Queue the writes of a session's signals and metadata, except the
+    /// event count.
+    fn write_signals(
+        &self,
+        pipe: &mut redis::Pipeline,
+        key: &str,
+        session: &Session,
+        signals: Vec<u8>,
+    ) {
+        pipe.hset_multiple(
+            key,
+            &[
+                ("last_seen", Utc::now().timestamp_millis()),
+                ("page_loads", i64::from(session.page_loads)),
+                (
+                    "page_offset_ms",
+                    i64::try_from(session.page_offset_ms).unwrap_or(i64::MAX),
+                ),
+            ],
+        )
+        .ignore()
+        .hset(key, SIGNALS, signals)
+        .ignore()
+        .hset_nx(key, "first_seen", session.timestamp.timestamp_millis())
+        .ignore()
+        .hincr(key, "request_count", 1)
+        .ignore()
+        .expire(key, self.ttl_seconds as i64)
+        .ignore();
+    }
+
+    /// Retrieve a session from the cache, with its events.
     ///
     /// # Errors
     ///
     /// Returns `ScrybeError::CacheError` if the operation fails.
     pub async fn get(&self, session_id: &SessionId) -> Result<Option<Session>, ScrybeError> {
-        let key = format!("session:{}", session_id);
+        let key = self.key(session_id);
+
+        let mut conn = self.client.get_connection().await?;
+
+        let (signals, events): (Option<Vec<u8>>, Option<Vec<u8>>) = redis::pipe()
+            .hget(&key, SIGNALS)
+            .get(self.events_key(session_id))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("GET failed: {}", e)))?;
+
+        let Some(signals) = signals else {
+            return Ok(None);
+        };
+
+        let mut session = self.codec.decode(&signals)?;
+        if let Some(events) = events {
+            session
+                .behavioral
+                .set_events(self.codec.decode_events(&events)?);
+        }
+        Ok(Some(session))
+    }
+
+    /// Retrieve a session from the cache without reading its events.
+    ///
+    /// The returned session has empty event collections.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn get_signals(
+        &self,
+        session_id: &SessionId,
+    ) -> Result<Option<Session>, ScrybeError> {
+        let key = self.key(session_id);
+
+        let mut conn = self.client.get_connection().await?;
+
+        let signals: Option<Vec<u8>> = conn
+            .hget(&key, SIGNALS)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("HGET failed: {}", e)))?;
+
+        signals.map(|bytes| self.codec.decode(&bytes)).transpose()
+    }
+
+    /// Retrieve only the behavioral events of a cached session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn get_events(
+        &self,
+        session_id: &SessionId,
+    ) -> Result<Option<EventBatch>, ScrybeError> {
+        let key = self.events_key(session_id);
 
         let mut conn = self.client.get_connection().await?;
 
-        let value: Option<String> = conn
+        let events: Option<Vec<u8>> = conn
             .get(&key)
             .await
             .map_err(|e| ScrybeError::cache_error("redis", format!("GET failed: {}", e)))?;
 
-        match value {
-            Some(json) => {
-                let session = serde_json::from_str(&json).map_err(|e| {
-                    ScrybeError::cache_error("redis", format!("Deserialization failed: {}", e))
-                })?;
-                Ok(Some(session))
+        events
+            .map(|bytes| self.codec.decode_events(&bytes))
+            .transpose()
+    }
+
+    /// Retrieve only the metadata of a cached session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn metadata(
+        &self,
+        session_id: &SessionId,
+    ) -> Result<Option<SessionMetadata>, ScrybeError> {
+        let key = self.key(session_id);
+
+        let mut conn = self.client.get_connection().await?;
+
+        let values: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(&key)
+            .arg(&METADATA_FIELDS[..])
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("HMGET failed: {}", e)))?;
+
+        Ok(SessionMetadata::from_fields(&values))
+    }
+
+    /// Record the fingerprint hash and bot probability of a scored session.
+    ///
+    /// Returns `false` if the session is no longer cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn record_score(
+        &self,
+        session_id: &SessionId,
+        fingerprint_hash: &str,
+        bot_probability: f64,
+    ) -> Result<bool, ScrybeError> {
+        let key = self.key(session_id);
+
+        let mut conn = self.client.get_connection().await?;
+
+        redis::Script::new(RECORD_SCORE_SCRIPT)
+            .key(&key)
+            .arg(fingerprint_hash)
+            .arg(bot_probability)
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("EVALSHA failed: {}", e)))
+    }
+
+    /// Append a batch of events to a cached session and mark it dirty for
+    /// re-scoring.
+    ///
+    /// Only the events key is rewritten; the signals are not read. The
+    /// read-modify-write is optimistic (`WATCH`/`MULTI`), so concurrent
+    /// batches for the same session are never lost. Returns the session's
+    /// events after the append, or `None` if it is not cached (expired or
+    /// unknown).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails or keeps
+    /// conflicting with concurrent writers.
+    pub async fn append_events(
+        &self,
+        session_id: &SessionId,
+        batch: EventBatch,
+    ) -> Result<Option<EventBatch>, ScrybeError> {
+        let key = self.key(session_id);
+        let events_key = self.events_key(session_id);
+
+        let mut conn = self.client.get_connection().await?;
+
+        for _ in 0..MAX_APPEND_RETRIES {
+            watch(&mut conn, &[&key, &events_key]).await?;
+
+            let attempt = async {
+                let (page_offset_ms, events): (Option<u64>, Option<Vec<u8>>) = redis::pipe()
+                    .hget(&key, "page_offset_ms")
+                    .get(&events_key)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| ScrybeError::cache_error("redis", format!("GET failed: {}", e)))?;
+
+                let Some(page_offset_ms) = page_offset_ms else {
+                    return Ok(Appended::NotCached);
+                };
+
+                let mut events = match events {
+                    Some(bytes) => self.codec.decode_events(&bytes)?,
+                    None => EventBatch::default(),
+                };
+                events.append(page_offset_ms, batch.clone());
+                let value = self.codec.encode_events(&events)?;
+
+                // EXEC replies nil if the session changed since WATCH
+                let committed: Option<()> = redis::pipe()
+                    .atomic()
+                    .set_ex(&events_key, &value, self.ttl_seconds as u64)
+                    .ignore()
+                    .hset_multiple(
+                        &key,
+                        &[
+                            ("last_seen", Utc::now().timestamp_millis()),
+                            ("event_count", events.len() as i64),
+                        ],
+                    )
+                    .ignore()
+                    .hincr(&key, "request_count", 1)
+                    .ignore()
+                    .expire(&key, self.ttl_seconds as i64)
+                    .ignore()
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(|e| {
+                        ScrybeError::cache_error("redis", format!("EXEC failed: {}", e))
+                    })?;
+
+                Ok(match committed {
+                    Some(()) => Appended::Committed(events),
+                    None => Appended::Conflict,
+                })
             }
-            None => Ok(None),
+            .await;
+
+            match unwatch_on_error(&mut conn, attempt).await? {
+                Appended::Committed(events) => {
+                    // The dirty set lives in another slot, so it cannot join
+                    // the transaction
+                    conn.sadd::<_, _, ()>(self.client.key(DIRTY_KEY), session_id.to_string())
+                        .await
+                        .map_err(|e| {
+                            ScrybeError::cache_error("redis", format!("SADD failed: {}", e))
+                        })?;
+                    return Ok(Some(events));
+                }
+                Appended::NotCached => {
+                    unwatch(&mut conn).await?;
+                    return Ok(None);
+                }
+                Appended::Conflict => {}
+            }
+        }
+
+        Err(ScrybeError::cache_error(
+            "redis",
+            format!("Append to session {} kept conflicting", session_id),
+        ))
+    }
+
+    /// Take up to `count` sessions marked dirty, removing them from the
+    /// dirty set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn take_dirty(&self, count: usize) -> Result<Vec<SessionId>, ScrybeError> {
+        let mut conn = self.client.get_connection().await?;
+
+        let ids: Vec<String> = redis::cmd("SPOP")
+            .arg(self.client.key(DIRTY_KEY))
+            .arg(count.clamp(1, MAX_DIRTY_BATCH))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("SPOP failed: {}", e)))?;
+
+        Ok(ids.iter().filter_map(|id| id.parse().ok()).collect())
+    }
+
+    /// Mark sessions dirty again, e.g., after re-scoring them failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn mark_dirty(&self, session_ids: &[SessionId]) -> Result<(), ScrybeError> {
+        if session_ids.is_empty() {
+            return Ok(());
         }
+
+        let ids: Vec<String> = session_ids.iter().map(|id| id.to_string()).collect();
+        let mut conn = self.client.get_connection().await?;
+
+        conn.sadd::<_, _, ()>(self.client.key(DIRTY_KEY), ids)
+            .await
+            .map_err(|e| ScrybeError::cache_error("redis", format!("SADD failed: {}", e)))?;
+
+        Ok(())
     }
 
     /// Delete a session from the cache.
@@ -81,11 +583,12 @@ impl SessionCache {
     ///
     /// Returns `ScrybeError::CacheError` if the operation fails.
     pub async fn delete(&self, session_id: &SessionId) -> Result<(), ScrybeError> {
-        let key = format!("session:{}", session_id);
+        let key = self.key(session_id);
+        let events_key = self.events_key(session_id);
 
         let mut conn = self.client.get_connection().await?;
 
-        conn.del::<_, ()>(&key)
+        conn.del::<_, ()>(&[key, events_key])
             .await
             .map_err(|e| ScrybeError::cache_error("redis", format!("DEL failed: {}", e)))?;
 
@@ -98,7 +601,7 @@ impl SessionCache {
     ///
     /// Returns `ScrybeError::CacheError` if the operation fails.
     pub async fn exists(&self, session_id: &SessionId) -> Result<bool, ScrybeError> {
-        let key = format!("session:{}", session_id);
+        let key = self.key(session_id);
 
         let mut conn = self.client.get_connection().await?;
 
@@ -109,6 +612,62 @@ impl SessionCache {
 
         Ok(exists)
     }
+
+    /// Metadata hash of a session, hash-tagged by its ID.
+    fn key(&self, session_id: &SessionId) -> String {
+        self.client.key(&format!("session:{{{}}}", session_id))
+    }
+
+    /// Events key of a session, in the same slot as its metadata hash.
+    fn events_key(&self, session_id: &SessionId) -> String {
+        self.client
+            .key(&format!("session:{{{}}}:events", session_id))
+    }
+}
+
+/// Outcome of one optimistic append attempt.
+enum Appended<T> {
+    /// The events or session after the append
+    Committed(T),
+    /// The session changed since `WATCH`
+    Conflict,
+    /// The session is not cached
+    NotCached,
+}
+
+/// Watch `keys` for the next transaction on `conn`.
+async fn watch<C: redis::aio::ConnectionLike>(
+    conn: &mut C,
+    keys: &[&str],
+) -> Result<(), ScrybeError> {
+    redis::cmd("WATCH")
+        .arg(keys)
+        .query_async(conn)
+        .await
+        .map_err(|e| ScrybeError::cache_error("redis", format!("WATCH failed: {}", e)))
+}
+
+/// Stop watching keys on `conn`.
+async fn unwatch<C: redis::aio::ConnectionLike>(conn: &mut C) -> Result<(), ScrybeError> {
+    redis::cmd("UNWATCH")
+        .query_async(conn)
+        .await
+        .map_err(|e| ScrybeError::cache_error("redis", format!("UNWATCH failed: {}", e)))
+}
+
+/// Stop watching if a transaction attempt failed before `EXEC`.
+///
+/// Connections go back to a pool, and a watch left behind would make the
+/// next caller's unrelated transaction fail. The attempt's error is kept;
+/// a failed `UNWATCH` means the connection is broken anyway.
+async fn unwatch_on_error<C: redis::aio::ConnectionLike, T>(
+    conn: &mut C,
+    attempt: Result<T, ScrybeError>,
+) -> Result<T, ScrybeError> {
+    if attempt.is_err() {
+        let _ = unwatch(conn).await;
+    }
+    attempt
 }
 
 #[cfg(test)]
@@ -116,6 +675,37 @@ mod tests {
     #[tokio::test]
     async fn test_session_cache_compiles() {
         // Placeholder test
-        assert!(true);
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<super::SessionCache>();
+    }
+
+    #[test]
+    fn test_metadata_from_fields() {
+        use super::SessionMetadata;
+
+        let values: Vec<Option<String>> = [
+            Some("1705920000000"),
+            Some("1705920123000"),
+            Some("2"),
+            Some("61000"),
+            Some("5"),
+            Some("240"),
+            None,
+            None,
+        ]
+        .iter()
+        .map(|v| v.map(str::to_string))
+        .collect();
+
+        let metadata = SessionMetadata::from_fields(&values).unwrap();
+        assert_eq!(metadata.first_seen.timestamp_millis(), 1_705_920_000_000);
+        assert_eq!(metadata.page_loads, 2);
+        assert_eq!(metadata.request_count, 5);
+        assert_eq!(metadata.event_count, 240);
+        assert!(metadata.fingerprint_hash.is_none());
+        assert!(metadata.bot_probability.is_none());
+
+        // HMGET on a missing key returns only nils
+        assert!(SessionMetadata::from_fields(&vec![None; 8]).is_none());
     }
 }
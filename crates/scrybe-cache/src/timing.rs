@@ -0,0 +1,257 @@
+//! Per-site page timing baselines.
+//!
+//! Each metric keeps a running count, mean and sum of squared deviations
+//! (Welford's algorithm), updated atomically by a script so concurrent
+//! workers never lose a sample. Every page load is learned at most once, so
+//! re-scoring a session does not weigh its timings again.
+//!
+//! Keys:
+//!
+//! ```text
+//! timing:{site}:baseline                   # Running statistics (Hash)
+//! timing:{site}:seen:{page}                # Page load already learned (String)
+//! ```
+//!
+//! The braces are a literal hash tag, so the keys of a site share a Cluster
+//! slot and are updated by one script. Baselines expire when a site sends
+//! no traffic for the retention period. Keys are prefixed by the client's
+//! key space.
+
+use crate::client::RedisClient;
+use crate::keyspace::KeySpace;
+use redis::AsyncCommands;
+use scrybe_core::ScrybeError;
+use std::collections::HashMap;
+
+/// Default retention of an idle site's baseline (30 days).
+const DEFAULT_TTL_SECONDS: u64 = 30 * 24 * 3_600;
+
+/// How long a learned page load is remembered (1 day).
+const SEEN_TTL_SECONDS: u64 = 24 * 3_600;
+
+/// Maximum length of a site name (DoS protection).
+pub const MAX_SITE_LENGTH: usize = 253;
+
+/// Maximum metrics updated in one observation (DoS protection).
+pub const MAX_METRICS: usize = 16;
+
+/// Folds samples into a site's statistics, once per page load.
+///
+/// KEYS[1] baseline hash, KEYS[2] seen marker; ARGV[1] baseline TTL,
+/// ARGV[2] seen TTL, then metric name and value pairs.
+const OBSERVE_SCRIPT: &str = r"
+if not redis.call('SET', KEYS[2], 1, 'NX', 'EX', ARGV[2]) then
+    return 0
+end
+for i = 3, #ARGV, 2 do
+    local metric = ARGV[i]
+    local value = tonumber(ARGV[i + 1])
+    local count = tonumber(redis.call('HGET', KEYS[1], metric .. ':count') or '0') + 1
+    local mean = tonumber(redis.call('HGET', KEYS[1], metric .. ':mean') or '0')
+    local m2 = tonumber(redis.call('HGET', KEYS[1], metric .. ':m2') or '0')
+    local delta = value - mean
+    mean = mean + delta / count
+    m2 = m2 + delta * (value - mean)
+    redis.call('HSET', KEYS[1], metric .. ':count', count,
+        metric .. ':mean', string.format('%.17g', mean),
+        metric .. ':m2', string.format('%.17g', m2))
+end
+redis.call('EXPIRE', KEYS[1], ARGV[1])
+return 1
+";
+
+/// Running statistics of one metric.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MetricSummary {
+    /// Number of samples
+    pub count: u64,
+    /// Sample mean
+    pub mean: f64,
+    /// Sum of squared deviations from the mean
+    pub m2: f64,
+}
+
+/// Redis-backed per-site timing baselines.
+#[derive(Clone)]
+pub struct TimingBaselines {
+    client: RedisClient,
+    ttl_seconds: u64,
+}
+
+impl TimingBaselines {
+    /// Create timing baselines.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Redis client instance
+    /// * `ttl_seconds` - Retention of an idle site's baseline (default: 2592000 = 30 days)
+    pub fn new(client: RedisClient, ttl_seconds: Option<u64>) -> Self {
+        Self {
+            client,
+            ttl_seconds: ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS).max(1),
+        }
+    }
+
+    /// Statistics of every metric learned for a site.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ValidationError` if the site name is too long,
+    /// or `ScrybeError::CacheError` if the operation fails.
+    pub async fn load(&self, site: &str) -> Result<HashMap<String, MetricSummary>, ScrybeError> {
+        validate_site(site)?;
+
+        let mut conn = self.client.get_connection().await?;
+
+        let fields: HashMap<String, String> = conn
+            .hgetall(baseline_key(self.client.key_space(), site))
+            .await
+            .map_err(|e| ScrybeError::cache_error("timing", format!("HGETALL failed: {}", e)))?;
+
+        Ok(summaries(&fields))
+    }
+
+    /// Fold a page load's samples into its site's baseline.
+    ///
+    /// `page` identifies the page load (e.g., session ID and page number).
+    /// Returns `false` if the page load was already learned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ValidationError` if the site name is too long
+    /// or there are more than [`MAX_METRICS`] samples, or
+    /// `ScrybeError::CacheError` if the operation fails.
+    pub async fn observe(
+        &self,
+        site: &str,
+        page: &str,
+        samples: &[(&str, f64)],
+    ) -> Result<bool, ScrybeError> {
+        validate_site(site)?;
+        if samples.len() > MAX_METRICS {
+            return Err(ScrybeError::validation_error(
+                "samples",
+                format!("<= {}", MAX_METRICS),
+                samples.len().to_string(),
+            ));
+        }
+        if samples.is_empty() {
+            return Ok(false);
+        }
+
+        let mut conn = self.client.get_connection().await?;
+
+        let script = redis::Script::new(OBSERVE_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(baseline_key(self.client.key_space(), site))
+            .key(seen_key(self.client.key_space(), site, page))
+            .arg(self.ttl_seconds)
+            .arg(SEEN_TTL_SECONDS);
+        for (metric, value) in samples {
+            invocation.arg(*metric).arg(*value);
+        }
+
+        let learned: i64 = invocation
+            .invoke_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("timing", format!("EVALSHA failed: {}", e)))?;
+
+        Ok(learned == 1)
+    }
+}
+
+/// Reject site names that would make oversized keys.
+fn validate_site(site: &str) -> Result<(), ScrybeError> {
+    if site.len() > MAX_SITE_LENGTH {
+        return Err(ScrybeError::validation_error(
+            "site",
+            format!("<= {} bytes", MAX_SITE_LENGTH),
+            site.len().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Parse `{metric}:count`, `{metric}:mean` and `{metric}:m2` hash fields.
+///
+/// Metrics with missing or malformed fields are skipped.
+fn summaries(fields: &HashMap<String, String>) -> HashMap<String, MetricSummary> {
+    fields
+        .keys()
+        .filter_map(|field| field.strip_suffix(":count"))
+        .filter_map(|metric| {
+            let value = |suffix: &str| fields.get(&format!("{}:{}", metric, suffix));
+            let summary = MetricSummary {
+                count: value("count")?.parse().ok()?,
+                mean: value("mean")?.parse().ok()?,
+                m2: value("m2")?.parse().ok()?,
+            };
+            Some((metric.to_string(), summary))
+        })
+        .collect()
+}
+
+/// Key of a site's baseline.
+fn baseline_key(keys: &KeySpace, site: &str) -> String {
+    keys.key(&format!("timing:{{{}}}:baseline", site))
+}
+
+/// Key marking a page load as learned.
+fn seen_key(keys: &KeySpace, site: &str, page: &str) -> String {
+    keys.key(&format!("timing:{{{}}}:seen:{}", site, page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summaries_from_fields() {
+        let fields: HashMap<String, String> = [
+            ("load_time:count", "3"),
+            ("load_time:mean", "1500.5"),
+            ("load_time:m2", "20000"),
+            ("time_to_first_byte:count", "2"),
+            ("time_to_first_byte:mean", "not a number"),
+            ("time_to_first_byte:m2", "0"),
+            ("dom_content_loaded:mean", "800"),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let summaries = summaries(&fields);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(
+            summaries["load_time"],
+            MetricSummary {
+                count: 3,
+                mean: 1500.5,
+                m2: 20000.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_keys_share_site_slot() {
+        let keys = KeySpace::new("production", "acme").unwrap();
+        assert_eq!(
+            baseline_key(&keys, "shop.example.com"),
+            keys.key("timing:{shop.example.com}:baseline")
+        );
+        assert_eq!(
+            seen_key(&keys, "shop.example.com", "session-1:2"),
+            keys.key("timing:{shop.example.com}:seen:session-1:2")
+        );
+    }
+
+    #[test]
+    fn test_validate_site() {
+        assert!(validate_site("shop.example.com").is_ok());
+        assert!(matches!(
+            validate_site(&"a".repeat(MAX_SITE_LENGTH + 1)),
+            Err(ScrybeError::ValidationError { .. })
+        ));
+    }
+}
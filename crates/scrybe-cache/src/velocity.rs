@@ -0,0 +1,342 @@
+//! Velocity counters over sliding windows.
+//!
+//! Counts events per entity (IP hash, subnet hash, fingerprint, ASN, JA4)
+//! in fixed-size time buckets. A window query reads the buckets covering
+//! the window: plain counts are summed, distinct counts are the union
+//! cardinality of per-bucket HyperLogLogs (`PFCOUNT` over several keys).
+//! Windows are therefore accurate to one bucket, and distinct counts carry
+//! HyperLogLog's ~0.8% standard error.
+//!
+//! Keys:
+//!
+//! ```text
+//! velocity:{counter:kind:value}:bucket     # Count (String) or distinct (HyperLogLog)
+//! ```
+//!
+//! The braces are a literal hash tag, so all buckets of one counter share
+//! a Cluster slot and a window is read with one multi-key command. Keys
+//! are prefixed by the client's key space.
+
+use crate::client::RedisClient;
+use crate::keyspace::KeySpace;
+use chrono::{DateTime, Utc};
+use redis::AsyncCommands;
+use scrybe_core::ScrybeError;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Default bucket size (1 minute).
+const DEFAULT_BUCKET_SECONDS: u64 = 60;
+
+/// Default retention, the longest window that can be queried (1 hour).
+const DEFAULT_RETENTION_SECONDS: u64 = 3_600;
+
+/// Maximum buckets read by a single query (DoS protection).
+pub const MAX_WINDOW_BUCKETS: u64 = 1_440;
+
+/// Maximum updates in one batch (DoS protection).
+pub const MAX_BATCH_UPDATES: usize = 1_000;
+
+/// Kind of entity a counter is keyed by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityKind {
+    /// Salted hash of a client IP
+    IpHash,
+    /// Salted hash of a client network (/24 or /48)
+    SubnetHash,
+    /// Fingerprint hash
+    Fingerprint,
+    /// Autonomous system number
+    Asn,
+    /// JA4 TLS fingerprint
+    Ja4,
+}
+
+impl EntityKind {
+    /// Stable name used in keys.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::IpHash => "ip",
+            Self::SubnetHash => "subnet",
+            Self::Fingerprint => "fp",
+            Self::Asn => "asn",
+            Self::Ja4 => "ja4",
+        }
+    }
+}
+
+/// An entity counters are kept for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Entity {
+    /// Entity kind
+    pub kind: EntityKind,
+    /// Entity value (hashed for IPs and subnets)
+    pub value: String,
+}
+
+impl Entity {
+    /// Create an entity.
+    pub fn new(kind: EntityKind, value: impl Into<String>) -> Self {
+        Self {
+            kind,
+            value: value.into(),
+        }
+    }
+}
+
+/// A single counter update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VelocityUpdate {
+    /// Count one event
+    Count {
+        /// Counter name (e.g., "requests")
+        counter: &'static str,
+        /// Entity the event belongs to
+        entity: Entity,
+    },
+    /// Add a member to a distinct count
+    Distinct {
+        /// Counter name (e.g., "fingerprints")
+        counter: &'static str,
+        /// Entity the member belongs to
+        entity: Entity,
+        /// Member to count once (e.g., a fingerprint hash)
+        member: String,
+    },
+}
+
+/// Redis-backed sliding-window velocity counters.
+#[derive(Clone)]
+pub struct VelocityCounters {
+    client: RedisClient,
+    bucket_seconds: u64,
+    retention_seconds: u64,
+}
+
+impl VelocityCounters {
+    /// Create new velocity counters.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Redis client instance
+    /// * `bucket_seconds` - Bucket size (default: 60 = 1 minute)
+    /// * `retention_seconds` - Longest queryable window (default: 3600 = 1 hour)
+    pub fn new(
+        client: RedisClient,
+        bucket_seconds: Option<u64>,
+        retention_seconds: Option<u64>,
+    ) -> Self {
+        let bucket_seconds = bucket_seconds.unwrap_or(DEFAULT_BUCKET_SECONDS).max(1);
+        let max_retention = bucket_seconds.saturating_mul(MAX_WINDOW_BUCKETS);
+        Self {
+            client,
+            bucket_seconds,
+            retention_seconds: retention_seconds
+                .unwrap_or(DEFAULT_RETENTION_SECONDS)
+                .clamp(bucket_seconds, max_retention),
+        }
+    }
+
+    /// Apply a batch of updates at time `at`, pipelined.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ValidationError` if the batch exceeds
+    /// [`MAX_BATCH_UPDATES`], or `ScrybeError::CacheError` if the operation
+    /// fails.
+    pub async fn record(
+        &self,
+        updates: &[VelocityUpdate],
+        at: DateTime<Utc>,
+    ) -> Result<(), ScrybeError> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        if updates.len() > MAX_BATCH_UPDATES {
+            return Err(ScrybeError::validation_error(
+                "updates",
+                format!("<= {}", MAX_BATCH_UPDATES),
+                updates.len().to_string(),
+            ));
+        }
+
+        let bucket = bucket(at, self.bucket_seconds);
+        // Keep each bucket until the last window that can include it ends
+        let ttl = (self.retention_seconds + self.bucket_seconds) as i64;
+
+        // One pipeline per counter, as each counter is in its own slot
+        let mut pipes: BTreeMap<String, redis::Pipeline> = BTreeMap::new();
+        for update in updates {
+            match update {
+                VelocityUpdate::Count { counter, entity } => {
+                    let key = key(self.client.key_space(), counter, entity, bucket);
+                    pipes
+                        .entry(tag(counter, entity))
+                        .or_default()
+                        .incr(&key, 1)
+                        .ignore()
+                        .expire(&key, ttl)
+                        .ignore();
+                }
+                VelocityUpdate::Distinct {
+                    counter,
+                    entity,
+                    member,
+                } => {
+                    let key = key(self.client.key_space(), counter, entity, bucket);
+                    pipes
+                        .entry(tag(counter, entity))
+                        .or_default()
+                        .pfadd(&key, member)
+                        .ignore()
+                        .expire(&key, ttl)
+                        .ignore();
+                }
+            }
+        }
+        let pipes: Vec<redis::Pipeline> = pipes.into_values().collect();
+
+        let mut conn = self.client.get_connection().await?;
+
+        conn.query_pipelines(&pipes)
+            .await
+            .map_err(|e| ScrybeError::cache_error("velocity", format!("Pipeline failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Number of events counted for the entity within `window` of now.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn count(
+        &self,
+        counter: &str,
+        entity: &Entity,
+        window: Duration,
+    ) -> Result<u64, ScrybeError> {
+        let keys = window_keys(
+            self.client.key_space(),
+            counter,
+            entity,
+            window,
+            Utc::now(),
+            self.bucket_seconds,
+            self.retention_seconds,
+        );
+
+        let mut conn = self.client.get_connection().await?;
+
+        let counts: Vec<Option<u64>> = conn
+            .mget(&keys)
+            .await
+            .map_err(|e| ScrybeError::cache_error("velocity", format!("MGET failed: {}", e)))?;
+
+        Ok(counts.into_iter().flatten().sum())
+    }
+
+    /// Approximate number of distinct members added for the entity within
+    /// `window` of now.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn distinct(
+        &self,
+        counter: &str,
+        entity: &Entity,
+        window: Duration,
+    ) -> Result<u64, ScrybeError> {
+        let keys = window_keys(
+            self.client.key_space(),
+            counter,
+            entity,
+            window,
+            Utc::now(),
+            self.bucket_seconds,
+            self.retention_seconds,
+        );
+
+        let mut conn = self.client.get_connection().await?;
+
+        conn.pfcount(&keys)
+            .await
+            .map_err(|e| ScrybeError::cache_error("velocity", format!("PFCOUNT failed: {}", e)))
+    }
+}
+
+/// Bucket index containing `at`.
+fn bucket(at: DateTime<Utc>, bucket_seconds: u64) -> u64 {
+    u64::try_from(at.timestamp()).unwrap_or(0) / bucket_seconds
+}
+
+/// Keys of the buckets covering `window` up to `now`, newest first.
+///
+/// Windows are rounded up to whole buckets and capped at the retention.
+fn window_keys(
+    keys: &KeySpace,
+    counter: &str,
+    entity: &Entity,
+    window: Duration,
+    now: DateTime<Utc>,
+    bucket_seconds: u64,
+    retention_seconds: u64,
+) -> Vec<String> {
+    let window_seconds = window.as_secs().clamp(1, retention_seconds);
+    let newest = bucket(now, bucket_seconds);
+
+    (0..window_seconds.div_ceil(bucket_seconds))
+        .map_while(|i| newest.checked_sub(i))
+        .map(|bucket| key(keys, counter, entity, bucket))
+        .collect()
+}
+
+/// Hash tag shared by all buckets of a counter.
+fn tag(counter: &str, entity: &Entity) -> String {
+    format!("{}:{}:{}", counter, entity.kind.as_str(), entity.value)
+}
+
+/// Key of one bucket of a counter.
+fn key(keys: &KeySpace, counter: &str, entity: &Entity, bucket: u64) -> String {
+    keys.key(&format!("velocity:{{{}}}:{}", tag(counter, entity), bucket))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_window_keys() {
+        let now = Utc.timestamp_opt(6_000, 0).unwrap();
+        let entity = Entity::new(EntityKind::Fingerprint, "abc");
+        let key_space = KeySpace::new("production", "acme").unwrap();
+        let keys = |seconds| {
+            window_keys(
+                &key_space,
+                "sessions",
+                &entity,
+                Duration::from_secs(seconds),
+                now,
+                60,
+                3_600,
+            )
+        };
+
+        let five_minutes = keys(300);
+        assert_eq!(five_minutes.len(), 5);
+        assert_eq!(
+            five_minutes[0],
+            "production:acme:velocity:{sessions:fp:abc}:100"
+        );
+        assert_eq!(
+            five_minutes[4],
+            "production:acme:velocity:{sessions:fp:abc}:96"
+        );
+
+        // Partial buckets round up, long windows are capped at the retention
+        assert_eq!(keys(90).len(), 2);
+        assert_eq!(keys(86_400).len(), 60);
+    }
+}
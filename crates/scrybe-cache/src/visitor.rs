@@ -0,0 +1,108 @@
+//! Visitor linking by device fingerprint.
+//!
+//! The first session seen with a fingerprint creates a visitor and every
+//! later session with the same fingerprint is linked to it. Session counts
+//! are kept in a HyperLogLog, so memory per visitor stays bounded however
+//! many sessions share a fingerprint.
+
+use crate::client::RedisClient;
+use scrybe_core::{
+    types::{SessionId, Visitor},
+    ScrybeError,
+};
+use uuid::Uuid;
+
+/// Default visitor retention (90 days, matching session storage).
+const DEFAULT_TTL_SECONDS: usize = 90 * 24 * 60 * 60;
+
+/// Redis index from fingerprint hash to visitor.
+///
+/// Visitors expire `ttl_seconds` after their last session.
+#[derive(Clone)]
+pub struct VisitorIndex {
+    client: RedisClient,
+    ttl_seconds: usize,
+}
+
+impl VisitorIndex {
+    /// Create a new visitor index.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - Redis client instance
+    /// * `ttl_seconds` - Visitor retention (default: 7776000 = 90 days)
+    pub fn new(client: RedisClient, ttl_seconds: Option<usize>) -> Self {
+        Self {
+            client,
+            ttl_seconds: ttl_seconds.unwrap_or(DEFAULT_TTL_SECONDS),
+        }
+    }
+
+    /// Link a session to the visitor owning its fingerprint, creating the
+    /// visitor if the fingerprint is new.
+    ///
+    /// Linking the same session twice does not increase the session count.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the operation fails.
+    pub async fn link(
+        &self,
+        fingerprint_hash: &str,
+        session_id: &SessionId,
+    ) -> Result<Visitor, ScrybeError> {
+        let fingerprint_key = self.client.key(&format!("visitor:fp:{}", fingerprint_hash));
+        let candidate = Uuid::new_v4().to_string();
+
+        let mut conn = self.client.get_connection().await?;
+
+        // SET NX keeps the first visitor ID written for the fingerprint
+        let (visitor_id,): (String,) = redis::pipe()
+            .atomic()
+            .cmd("SET")
+            .arg(&fingerprint_key)
+            .arg(&candidate)
+            .arg("NX")
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&fingerprint_key)
+            .arg(self.ttl_seconds)
+            .ignore()
+            .cmd("GET")
+            .arg(&fingerprint_key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("visitor", format!("SET NX failed: {}", e)))?;
+
+        let sessions_key = self.client.key(&format!("visitor:{}:sessions", visitor_id));
+        let (session_count,): (u64,) = redis::pipe()
+            .atomic()
+            .cmd("PFADD")
+            .arg(&sessions_key)
+            .arg(session_id.to_string())
+            .ignore()
+            .cmd("EXPIRE")
+            .arg(&sessions_key)
+            .arg(self.ttl_seconds)
+            .ignore()
+            .cmd("PFCOUNT")
+            .arg(&sessions_key)
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| ScrybeError::cache_error("visitor", format!("PFADD failed: {}", e)))?;
+
+        Ok(Visitor {
+            id: visitor_id,
+            session_count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_visitor_index_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<super::VisitorIndex>();
+    }
+}
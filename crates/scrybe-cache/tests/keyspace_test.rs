@@ -0,0 +1,89 @@
+//! Integration tests for tenant isolation and key space deletion.
+//!
+//! These tests require a local Redis, and the Cluster test the setup in
+//! `deployment/redis/docker-compose.ha.yml`:
+//!
+//! ```text
+//! docker run -d -p 6379:6379 redis:7-alpine
+//! docker compose -f deployment/redis/docker-compose.ha.yml up -d
+//! cargo test -p scrybe-cache --test keyspace_test -- --ignored
+//! ```
+//!
+//! `SCRYBE_TEST_REDIS_URL` and `SCRYBE_TEST_REDIS_CLUSTER_URLS` override
+//! the addresses.
+
+use scrybe_cache::{
+    KeySpace, NonceValidator, RateLimiter, RedisClient, RedisPoolConfig, RedisTopology,
+};
+use scrybe_core::types::SessionId;
+
+fn standalone() -> RedisTopology {
+    RedisTopology::Standalone {
+        url: std::env::var("SCRYBE_TEST_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+    }
+}
+
+fn cluster() -> RedisTopology {
+    RedisTopology::Cluster {
+        urls: std::env::var("SCRYBE_TEST_REDIS_CLUSTER_URLS")
+            .unwrap_or_else(|_| {
+                "redis://127.0.0.1:7000,redis://127.0.0.1:7001,redis://127.0.0.1:7002".to_string()
+            })
+            .split(',')
+            .map(|url| url.trim().to_string())
+            .collect(),
+    }
+}
+
+/// Two tenants use the same identifiers without seeing each other, and
+/// deleting one tenant leaves the other intact.
+async fn exercise_isolation(topology: RedisTopology) {
+    let client = RedisClient::connect(&topology, RedisPoolConfig::default())
+        .await
+        .expect("Redis is running");
+
+    // Unique tenants so reruns start from empty key spaces
+    let run = SessionId::new().to_string().replace('-', "");
+    let site_a = KeySpace::new("test", &format!("a{}", run)).unwrap();
+    let site_b = KeySpace::new("test", &format!("b{}", run)).unwrap();
+    let client_a = client.clone().with_key_space(site_a.clone());
+    let client_b = client.clone().with_key_space(site_b.clone());
+
+    let nonces_a = NonceValidator::new(client_a.clone(), None);
+    let nonces_b = NonceValidator::new(client_b.clone(), None);
+    assert!(nonces_a.validate_nonce("shared-nonce").await.unwrap());
+    assert!(nonces_b.validate_nonce("shared-nonce").await.unwrap());
+    assert!(!nonces_a.validate_nonce("shared-nonce").await.unwrap());
+
+    let limiter_a = RateLimiter::new(client_a.clone(), 100, 60);
+    let limiter_b = RateLimiter::new(client_b.clone(), 100, 60);
+    for i in 0..50 {
+        limiter_a.check(&format!("ip-{}", i)).await.unwrap();
+    }
+    limiter_b.check("ip-0").await.unwrap();
+    assert_eq!(limiter_a.get_count("ip-0").await.unwrap(), 1);
+    assert_eq!(limiter_b.get_count("ip-0").await.unwrap(), 1);
+
+    // One nonce and 50 counters, more than one SCAN batch on small nodes
+    assert_eq!(site_a.delete_all(&client).await.unwrap(), 51);
+    assert!(!nonces_a.exists("shared-nonce").await.unwrap());
+    assert_eq!(limiter_a.get_count("ip-0").await.unwrap(), 0);
+
+    assert!(nonces_b.exists("shared-nonce").await.unwrap());
+    assert_eq!(limiter_b.get_count("ip-0").await.unwrap(), 1);
+
+    assert_eq!(site_b.delete_all(&client).await.unwrap(), 2);
+}
+
+#[tokio::test]
+#[ignore] // Requires Redis - run with `cargo test -- --ignored`
+async fn test_standalone_tenant_isolation() {
+    exercise_isolation(standalone()).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Cluster setup - run with `cargo test -- --ignored`
+async fn test_cluster_tenant_isolation() {
+    exercise_isolation(cluster()).await;
+}
@@ -0,0 +1,395 @@
+//! Integration tests against Redis Sentinel and Redis Cluster.
+//!
+//! These tests require the local setup in
+//! `deployment/redis/docker-compose.ha.yml`:
+//!
+//! ```text
+//! docker compose -f deployment/redis/docker-compose.ha.yml up -d
+//! cargo test -p scrybe-cache --test topology_test -- --ignored --test-threads=1
+//! ```
+//!
+//! `SCRYBE_TEST_REDIS_SENTINEL_URLS` and `SCRYBE_TEST_REDIS_CLUSTER_URLS`
+//! override the addresses.
+
+use chrono::{TimeZone, Utc};
+use scrybe_cache::{
+    Anomaly, AnomalyFeed, CanvasGroupStats, CanvasPopulations, CorrelationIndex, Entity,
+    EntityKind, NonceValidator, QueuedSession, RateLimiter, RedisClient, RedisPoolConfig,
+    RedisTopology, SessionCache, SessionQueue, TimingBaselines, VelocityCounters, VelocityUpdate,
+};
+use scrybe_core::test_util;
+use scrybe_core::types::{EventBatch, MouseEvent, MouseEventType, Session, SessionId};
+use std::time::Duration;
+
+const SENTINEL_MASTER: &str = "scrybe";
+
+fn urls(var: &str, default: &str) -> Vec<String> {
+    std::env::var(var)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|url| url.trim().to_string())
+        .collect()
+}
+
+fn sentinel() -> RedisTopology {
+    RedisTopology::Sentinel {
+        urls: urls(
+            "SCRYBE_TEST_REDIS_SENTINEL_URLS",
+            "redis://127.0.0.1:26379,redis://127.0.0.1:26380,redis://127.0.0.1:26381",
+        ),
+        master: SENTINEL_MASTER.to_string(),
+    }
+}
+
+fn cluster() -> RedisTopology {
+    RedisTopology::Cluster {
+        urls: urls(
+            "SCRYBE_TEST_REDIS_CLUSTER_URLS",
+            "redis://127.0.0.1:7000,redis://127.0.0.1:7001,redis://127.0.0.1:7002",
+        ),
+    }
+}
+
+async fn connect(topology: &RedisTopology) -> RedisClient {
+    RedisClient::connect(topology, RedisPoolConfig::default())
+        .await
+        .expect("Redis topology is running")
+}
+
+fn create_test_session() -> Session {
+    let mut session = test_util::session();
+    session.network.ja4 = Some("t13d1516h2_8daaf6152771_b186095e22b6".to_string());
+    session
+}
+
+fn mouse_batch(count: u64) -> EventBatch {
+    EventBatch {
+        mouse_events: (0..count)
+            .map(|i| MouseEvent {
+                timestamp_ms: i * 16,
+                x: i as i32,
+                y: i as i32,
+                event_type: MouseEventType::Move,
+            })
+            .collect(),
+        ..EventBatch::default()
+    }
+}
+
+/// Store, stitch and score a session; its two keys must share a slot.
+async fn exercise_session_cache(client: RedisClient) {
+    let cache = SessionCache::new(client, None, None);
+    let session = create_test_session();
+
+    cache.store(&session).await.unwrap();
+    assert!(cache.get(&session.id).await.unwrap().is_some());
+
+    let events = cache
+        .append_events(&session.id, mouse_batch(5))
+        .await
+        .unwrap()
+        .expect("session is cached");
+    assert_eq!(events.len(), 5);
+
+    // Storing again keeps the appended events; each later page load is
+    // applied to the cached session and adds to them
+    cache.store(&session).await.unwrap();
+    for page_loads in 2..=3 {
+        let stitched = cache
+            .store_page_load(&session.id, |cached| {
+                cached.page_loads += 1;
+                mouse_batch(1)
+            })
+            .await
+            .unwrap()
+            .expect("session is cached");
+        assert_eq!(stitched.page_loads, page_loads);
+    }
+    let metadata = cache.metadata(&session.id).await.unwrap().unwrap();
+    assert_eq!(metadata.page_loads, 3);
+    let events = cache.get_events(&session.id).await.unwrap().unwrap();
+    assert_eq!(events.len(), 7);
+
+    assert!(cache
+        .record_score(&session.id, "fp-topology", 0.9)
+        .await
+        .unwrap());
+    let metadata = cache.metadata(&session.id).await.unwrap().unwrap();
+    assert_eq!(metadata.event_count, 7);
+
+    cache.delete(&session.id).await.unwrap();
+    assert!(!cache.exists(&session.id).await.unwrap());
+}
+
+/// Reclaims pending entries in turns, wrapping around at the end.
+async fn exercise_queue(client: RedisClient) {
+    let stream = format!("queue-{}", SessionId::new());
+    let queue = SessionQueue::new(client, Some(&stream), None, None);
+    queue.ensure_group().await.unwrap();
+
+    let mut ids = Vec::new();
+    for _ in 0..3 {
+        ids.push(queue.enqueue(&create_test_session()).await.unwrap());
+    }
+    assert_eq!(queue.read("dead", 3, 0).await.unwrap().len(), 3);
+
+    let claimed = |entries: Vec<QueuedSession>| -> Vec<String> {
+        entries.into_iter().map(|entry| entry.id).collect()
+    };
+    assert_eq!(
+        claimed(queue.reclaim("live", 0, 2).await.unwrap()),
+        ids[..2]
+    );
+    assert_eq!(
+        claimed(queue.reclaim("live", 0, 2).await.unwrap()),
+        ids[2..]
+    );
+    assert_eq!(
+        claimed(queue.reclaim("live", 0, 2).await.unwrap()),
+        ids[..2]
+    );
+
+    queue.ack(&ids).await.unwrap();
+}
+
+/// Multi-key indexes, scripts and nonces.
+async fn exercise_indexes(client: RedisClient) {
+    let suffix = SessionId::new().to_string();
+    let fingerprint = format!("fp-{}", suffix);
+
+    let correlation = CorrelationIndex::new(client.clone(), None, None);
+    let sessions = [SessionId::new(), SessionId::new()];
+    let first_seen = Utc::now() - chrono::Duration::seconds(10);
+    for (offset, (ip, session_id)) in ["ip-a", "ip-b"].iter().zip(&sessions).enumerate() {
+        let seen_at = first_seen + chrono::Duration::seconds(offset as i64);
+        correlation
+            .record(&fingerprint, ip, session_id, seen_at)
+            .await
+            .unwrap();
+    }
+    // Recording a session again moves it to the front without adding it
+    correlation
+        .record(&fingerprint, "ip-a", &sessions[0], Utc::now())
+        .await
+        .unwrap();
+    assert_eq!(
+        correlation
+            .distinct_ips(&fingerprint, Duration::from_secs(60))
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        correlation.sessions(&fingerprint, 10).await.unwrap(),
+        vec![sessions[0], sessions[1]]
+    );
+    assert_eq!(
+        correlation
+            .seen(&fingerprint)
+            .await
+            .unwrap()
+            .unwrap()
+            .first_seen,
+        Utc.timestamp_millis_opt(first_seen.timestamp_millis())
+            .unwrap()
+    );
+
+    // Updates for several counters land in several slots
+    let velocity = VelocityCounters::new(client.clone(), None, None);
+    let entity = Entity::new(EntityKind::Fingerprint, fingerprint.clone());
+    let subnet = Entity::new(EntityKind::SubnetHash, format!("subnet-{}", suffix));
+    velocity
+        .record(
+            &[
+                VelocityUpdate::Count {
+                    counter: "requests",
+                    entity: entity.clone(),
+                },
+                VelocityUpdate::Distinct {
+                    counter: "sessions",
+                    entity: entity.clone(),
+                    member: "session-a".to_string(),
+                },
+                VelocityUpdate::Distinct {
+                    counter: "fingerprints",
+                    entity: subnet.clone(),
+                    member: fingerprint.clone(),
+                },
+            ],
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+    let window = Duration::from_secs(300);
+    assert_eq!(
+        velocity.count("requests", &entity, window).await.unwrap(),
+        1
+    );
+    assert_eq!(
+        velocity
+            .distinct("sessions", &entity, window)
+            .await
+            .unwrap(),
+        1
+    );
+
+    // The baseline and its seen markers share the site's slot
+    let timing = TimingBaselines::new(client.clone(), None);
+    let site = format!("{}.example.com", suffix);
+    let samples = [("load_time", 1_000.0), ("time_to_first_byte", 100.0)];
+    assert!(timing.observe(&site, "page-a", &samples).await.unwrap());
+    assert!(!timing.observe(&site, "page-a", &samples).await.unwrap());
+    assert!(timing
+        .observe(&site, "page-b", &[("load_time", 2_000.0)])
+        .await
+        .unwrap());
+    let baseline = timing.load(&site).await.unwrap();
+    assert_eq!(baseline["load_time"].count, 2);
+    assert_eq!(baseline["load_time"].mean, 1_500.0);
+    assert_eq!(baseline["load_time"].m2, 500_000.0);
+    assert_eq!(baseline["time_to_first_byte"].count, 1);
+
+    // A re-scored session is counted once in its canvas group
+    let canvas = CanvasPopulations::new(client.clone(), None);
+    let group = format!("group-{}", suffix);
+    let single = CanvasGroupStats {
+        sessions: 1,
+        canvas_sessions: 1,
+        singletons: 1,
+    };
+    assert_eq!(
+        canvas.observe(&group, "session-a", "canvas").await.unwrap(),
+        single
+    );
+    assert_eq!(
+        canvas.observe(&group, "session-a", "canvas").await.unwrap(),
+        single
+    );
+    assert_eq!(
+        canvas.observe(&group, "session-b", "canvas").await.unwrap(),
+        CanvasGroupStats {
+            sessions: 2,
+            canvas_sessions: 2,
+            singletons: 0,
+        }
+    );
+
+    let anomalies = AnomalyFeed::new(client.clone(), Some(&suffix), None, None);
+    let anomaly = Anomaly {
+        session_id: SessionId::new(),
+        fingerprint_hash: Some(fingerprint),
+        score: 0.95,
+        rules: vec!["topology_test".to_string()],
+        detected_at: Utc::now(),
+    };
+    assert!(anomalies.record(&anomaly).await.unwrap());
+    assert_eq!(anomalies.top(10).await.unwrap(), vec![anomaly.clone()]);
+
+    // Re-scoring the session replaces its entry instead of adding another
+    let rescored = Anomaly {
+        score: 0.99,
+        detected_at: anomaly.detected_at + chrono::Duration::seconds(1),
+        ..anomaly.clone()
+    };
+    assert!(anomalies.record(&rescored).await.unwrap());
+    assert_eq!(anomalies.recent(10).await.unwrap(), vec![rescored.clone()]);
+    assert_eq!(anomalies.top(10).await.unwrap(), vec![rescored.clone()]);
+    assert_eq!(
+        anomalies.since(anomaly.detected_at, 10).await.unwrap(),
+        vec![rescored.clone()]
+    );
+
+    // A full feed evicts its oldest session from every key
+    let small = AnomalyFeed::new(
+        client.clone(),
+        Some(&format!("{}-small", suffix)),
+        None,
+        Some(1),
+    );
+    assert!(small.record(&rescored).await.unwrap());
+    let newer = Anomaly {
+        session_id: SessionId::new(),
+        detected_at: rescored.detected_at + chrono::Duration::seconds(1),
+        ..rescored.clone()
+    };
+    assert!(small.record(&newer).await.unwrap());
+    assert_eq!(small.recent(10).await.unwrap(), vec![newer.clone()]);
+    assert_eq!(small.top(10).await.unwrap(), vec![newer]);
+
+    let nonces = NonceValidator::new(client.clone(), None);
+    assert!(nonces.validate_nonce(&suffix).await.unwrap());
+    assert!(!nonces.validate_nonce(&suffix).await.unwrap());
+
+    let limiter = RateLimiter::new(client, 1, 60);
+    assert!(limiter.check(&suffix).await.unwrap());
+    assert!(!limiter.check(&suffix).await.unwrap());
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Sentinel setup - run with `cargo test -- --ignored`
+async fn test_sentinel_session_cache() {
+    exercise_session_cache(connect(&sentinel()).await).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Sentinel setup - run with `cargo test -- --ignored`
+async fn test_sentinel_indexes() {
+    exercise_indexes(connect(&sentinel()).await).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Sentinel setup - run with `cargo test -- --ignored`
+async fn test_sentinel_queue() {
+    exercise_queue(connect(&sentinel()).await).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Cluster setup - run with `cargo test -- --ignored`
+async fn test_cluster_session_cache() {
+    exercise_session_cache(connect(&cluster()).await).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Cluster setup - run with `cargo test -- --ignored`
+async fn test_cluster_indexes() {
+    exercise_indexes(connect(&cluster()).await).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Cluster setup - run with `cargo test -- --ignored`
+async fn test_cluster_queue() {
+    exercise_queue(connect(&cluster()).await).await;
+}
+
+#[tokio::test]
+#[ignore] // Requires the local Sentinel setup - run with `cargo test -- --ignored`
+async fn test_sentinel_failover() {
+    let RedisTopology::Sentinel { urls, .. } = sentinel() else {
+        unreachable!();
+    };
+    let client = connect(&sentinel()).await;
+    let cache = SessionCache::new(client, None, None);
+    cache.store(&create_test_session()).await.unwrap();
+
+    let sentinel = redis::Client::open(urls[0].as_str()).unwrap();
+    let mut conn = sentinel.get_async_connection().await.unwrap();
+    redis::cmd("SENTINEL")
+        .arg("FAILOVER")
+        .arg(SENTINEL_MASTER)
+        .query_async::<_, ()>(&mut conn)
+        .await
+        .unwrap();
+
+    // Pooled connections to the demoted master are replaced on checkout
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+    loop {
+        match cache.store(&create_test_session()).await {
+            Ok(()) => break,
+            Err(e) if tokio::time::Instant::now() < deadline => {
+                eprintln!("Waiting for failover: {}", e);
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+            Err(e) => panic!("Writes did not recover after failover: {}", e),
+        }
+    }
+}
@@ -131,6 +131,34 @@ impl SecretConfig {
         })
     }
 
+    /// Load the key for signing session tokens from the hex-encoded
+    /// `SCRYBE_SESSION_KEY`.
+    ///
+    /// There is no fallback: a gateway without the key must not issue or
+    /// accept session tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the variable is missing, not
+    /// valid hex, or shorter than [`MIN_SESSION_KEY_BYTES`].
+    pub fn session_key() -> Result<Secret<Vec<u8>>, ScrybeError> {
+        parse_session_key(&required("SCRYBE_SESSION_KEY")?)
+    }
+
+    /// Load the salt for hashing client IPs from `SCRYBE_IP_HASH_SALT`.
+    ///
+    /// The gateway and the workers must use the same salt, or the hashes
+    /// they write to the velocity counters and correlation index will not
+    /// match; neither falls back to a default.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the variable is missing or
+    /// shorter than [`MIN_IP_HASH_SALT_LENGTH`].
+    pub fn ip_hash_salt() -> Result<Secret<String>, ScrybeError> {
+        parse_ip_hash_salt(required("SCRYBE_IP_HASH_SALT")?)
+    }
+
     /// Create test configuration with dummy secrets.
     #[cfg(test)]
     pub fn test_default() -> Self {
@@ -144,6 +172,44 @@ impl SecretConfig {
     }
 }
 
+/// Minimum length of the session token key, in bytes.
+pub const MIN_SESSION_KEY_BYTES: usize = 32;
+
+/// Minimum length of the IP hash salt, in characters.
+pub const MIN_IP_HASH_SALT_LENGTH: usize = 16;
+
+/// Read a required, non-empty environment variable.
+fn required(name: &str) -> Result<String, ScrybeError> {
+    env::var(name)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| ScrybeError::config_error(format!("Missing {}", name)))
+}
+
+/// Decode a hex-encoded session token key.
+fn parse_session_key(hex_key: &str) -> Result<Secret<Vec<u8>>, ScrybeError> {
+    let key = hex::decode(hex_key)
+        .map_err(|e| ScrybeError::config_error(format!("Invalid SCRYBE_SESSION_KEY: {}", e)))?;
+    if key.len() < MIN_SESSION_KEY_BYTES {
+        return Err(ScrybeError::config_error(format!(
+            "SCRYBE_SESSION_KEY must be at least {} bytes",
+            MIN_SESSION_KEY_BYTES
+        )));
+    }
+    Ok(Secret::new(key))
+}
+
+/// Check the length of an IP hash salt.
+fn parse_ip_hash_salt(salt: String) -> Result<Secret<String>, ScrybeError> {
+    if salt.chars().count() < MIN_IP_HASH_SALT_LENGTH {
+        return Err(ScrybeError::config_error(format!(
+            "SCRYBE_IP_HASH_SALT must be at least {} characters",
+            MIN_IP_HASH_SALT_LENGTH
+        )));
+    }
+    Ok(Secret::new(salt))
+}
+
 impl fmt::Debug for SecretConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("SecretConfig")
@@ -271,4 +337,20 @@ mod tests {
             _ => panic!("Expected ConfigError"),
         }
     }
+
+    #[test]
+    fn test_parse_session_key() {
+        let key = parse_session_key(&"ab".repeat(MIN_SESSION_KEY_BYTES)).unwrap();
+        assert_eq!(key.expose(), &vec![0xab; MIN_SESSION_KEY_BYTES]);
+
+        assert!(parse_session_key("not hex").is_err());
+        assert!(parse_session_key(&"ab".repeat(MIN_SESSION_KEY_BYTES - 1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_ip_hash_salt() {
+        let salt = "s".repeat(MIN_IP_HASH_SALT_LENGTH);
+        assert_eq!(parse_ip_hash_salt(salt.clone()).unwrap().expose(), &salt);
+        assert!(parse_ip_hash_salt("short".to_string()).is_err());
+    }
 }
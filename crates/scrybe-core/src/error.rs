@@ -42,6 +42,15 @@ pub enum ScrybeError {
         reason: String,
     },
 
+    /// No pooled connection became free within the wait timeout.
+    #[error("Connection pool exhausted: pool='{pool}', waited_ms={waited_ms}")]
+    PoolExhausted {
+        /// The pool that ran out of connections
+        pool: String,
+        /// How long the caller waited, in milliseconds
+        waited_ms: u64,
+    },
+
     /// Enrichment pipeline failed.
     #[error("Enrichment failed: stage='{stage}', reason='{reason}'")]
     EnrichmentError {
@@ -118,6 +127,14 @@ impl ScrybeError {
         }
     }
 
+    /// Creates a pool exhausted error.
+    pub fn pool_exhausted(pool: impl Into<String>, waited_ms: u64) -> Self {
+        Self::PoolExhausted {
+            pool: pool.into(),
+            waited_ms,
+        }
+    }
+
     /// Creates an enrichment error.
     pub fn enrichment_error(stage: impl Into<String>, reason: impl Into<String>) -> Self {
         Self::EnrichmentError {
@@ -197,6 +214,7 @@ mod tests {
             ScrybeError::config_error("test"),
             ScrybeError::storage_error("write", "timeout"),
             ScrybeError::cache_error("get", "not found"),
+            ScrybeError::pool_exhausted("redis", 500),
             ScrybeError::enrichment_error("fingerprint", "invalid data"),
             ScrybeError::rate_limit(100, "second"),
             ScrybeError::authentication_error("invalid token"),
@@ -16,6 +16,7 @@
 //! - [`error`]: Error types for all Scrybe operations
 //! - [`config`]: Configuration and secrets management
 //! - [`types`]: Core domain types
+//! - `test_util`: Session fixtures for tests (`test-util` feature)
 
 #![warn(missing_docs)]
 #![warn(missing_debug_implementations)]
@@ -27,6 +28,9 @@ pub mod error;
 pub mod privacy;
 pub mod types;
 
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+
 // Re-export commonly used types
-pub use config::{Config, Secret};
+pub use config::{Config, Secret, SecretConfig};
 pub use error::ScrybeError;
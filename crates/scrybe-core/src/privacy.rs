@@ -2,6 +2,7 @@
 
 use crate::ScrybeError;
 use sha2::{Digest, Sha256};
+use std::net::IpAddr;
 
 /// Hash an IP address with salt for privacy-preserving storage.
 ///
@@ -34,6 +35,33 @@ pub fn hash_ip(ip: &str, salt: &[u8]) -> String {
     hex::encode(result)
 }
 
+/// Network an IP address belongs to, for per-network aggregates.
+///
+/// IPv4 addresses are truncated to their /24 and IPv6 addresses to their
+/// /48 (a typical end-site allocation). Hash the result with [`hash_ip`]
+/// before storing it.
+///
+/// # Example
+///
+/// ```
+/// use scrybe_core::privacy::subnet;
+///
+/// let ip = "192.0.2.77".parse().unwrap();
+/// assert_eq!(subnet(&ip), "192.0.2.0/24");
+/// ```
+pub fn subnet(ip: &IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        IpAddr::V6(v6) => {
+            let s = v6.segments();
+            format!("{:x}:{:x}:{:x}::/48", s[0], s[1], s[2])
+        }
+    }
+}
+
 /// Validate that no PII (Personally Identifiable Information) is present.
 ///
 /// Checks for common PII patterns that should never be collected.
@@ -96,6 +124,15 @@ pub enum DataSubjectRight {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_subnet() {
+        let v4: IpAddr = "203.0.113.200".parse().unwrap();
+        let v6: IpAddr = "2001:db8:abcd:12::1".parse().unwrap();
+
+        assert_eq!(subnet(&v4), "203.0.113.0/24");
+        assert_eq!(subnet(&v6), "2001:db8:abcd::/48");
+    }
+
     #[test]
     fn test_hash_ip_deterministic() {
         let salt = b"test-salt";
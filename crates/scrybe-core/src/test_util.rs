@@ -0,0 +1,70 @@
+//! Session fixtures shared by the tests of every crate.
+//!
+//! Compiled for this crate's tests and, with the `test-util` feature, for
+//! other crates that list it in their dev-dependencies:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! scrybe-core = { path = "../scrybe-core", features = ["test-util"] }
+//! ```
+//!
+//! Fixtures are plain values; tests adjust the fields they care about.
+
+use crate::types::{
+    BehavioralSignals, BrowserSignals, EnrichedSession, HttpVersion, NetworkSignals, ScreenInfo,
+    Session, TimingMetrics,
+};
+use std::net::{IpAddr, Ipv4Addr};
+
+/// Network signals of a client at a documentation address (203.0.113.7)
+/// over HTTP/2, with no TLS fingerprints or headers.
+pub fn network_signals() -> NetworkSignals {
+    NetworkSignals {
+        ip: IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)),
+        ja3: None,
+        ja4: None,
+        headers: vec![],
+        http_version: HttpVersion::Http2,
+        gateway_timing: None,
+    }
+}
+
+/// Browser signals with a default screen, `en-US` in UTC, and no
+/// fingerprinting hashes.
+pub fn browser_signals() -> BrowserSignals {
+    BrowserSignals {
+        canvas_hash: None,
+        canvas_hash_repeat: None,
+        webgl_hash: None,
+        webgl: None,
+        audio_hash: None,
+        audio_hash_repeat: None,
+        fonts: vec![],
+        plugins: vec![],
+        timezone: "UTC".to_string(),
+        language: "en-US".to_string(),
+        screen: ScreenInfo::default(),
+        user_agent: "Mozilla/5.0 Test".to_string(),
+        automation: None,
+    }
+}
+
+/// Behavioral signals with no events or timings.
+pub fn behavioral_signals() -> BehavioralSignals {
+    BehavioralSignals {
+        mouse_events: vec![],
+        scroll_events: vec![],
+        click_events: vec![],
+        timing: TimingMetrics::default(),
+    }
+}
+
+/// A new session of one page load, started now, with the fixture signals.
+pub fn session() -> Session {
+    Session::new(network_signals(), browser_signals(), behavioral_signals())
+}
+
+/// A fixture [`session`] with no enrichment outputs yet.
+pub fn enriched_session() -> EnrichedSession {
+    EnrichedSession::new(session())
+}
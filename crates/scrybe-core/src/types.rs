@@ -5,11 +5,19 @@
 
 pub mod behavioral;
 pub mod browser;
+pub mod enriched;
+pub mod evidence;
+pub mod geo;
 pub mod network;
+pub mod reputation;
 pub mod session;
 
 // Re-export main types for convenience
 pub use behavioral::*;
 pub use browser::*;
+pub use enriched::*;
+pub use evidence::*;
+pub use geo::*;
 pub use network::*;
+pub use reputation::*;
 pub use session::*;
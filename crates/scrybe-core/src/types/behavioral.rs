@@ -97,6 +97,113 @@ pub struct TimingMetrics {
     pub time_to_first_interaction_ms: Option<u64>,
 }
 
+/// Behavioral events sent after the initial ingest of a page.
+///
+/// Timestamps are milliseconds since the start of the page load the
+/// events belong to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct EventBatch {
+    /// Mouse movement events
+    #[serde(default)]
+    pub mouse_events: Vec<MouseEvent>,
+    /// Scroll events
+    #[serde(default)]
+    pub scroll_events: Vec<ScrollEvent>,
+    /// Click events
+    #[serde(default)]
+    pub click_events: Vec<ClickEvent>,
+}
+
+impl EventBatch {
+    /// Whether the batch contains no events.
+    pub fn is_empty(&self) -> bool {
+        self.mouse_events.is_empty()
+            && self.scroll_events.is_empty()
+            && self.click_events.is_empty()
+    }
+
+    /// Total number of events in the batch.
+    pub fn len(&self) -> usize {
+        self.mouse_events.len() + self.scroll_events.len() + self.click_events.len()
+    }
+
+    /// Append a batch of events, shifting their timestamps by `offset_ms`.
+    ///
+    /// Once a collection would exceed its `MAX_*` limit it is down-sampled
+    /// by dropping every second event until it fits, so the retained events
+    /// stay spread evenly over the whole session (DoS protection).
+    pub fn append(&mut self, offset_ms: u64, batch: EventBatch) {
+        append_bounded(
+            &mut self.mouse_events,
+            batch.mouse_events.into_iter().map(|mut e| {
+                e.timestamp_ms = e.timestamp_ms.saturating_add(offset_ms);
+                e
+            }),
+            MAX_MOUSE_EVENTS,
+        );
+        append_bounded(
+            &mut self.scroll_events,
+            batch.scroll_events.into_iter().map(|mut e| {
+                e.timestamp_ms = e.timestamp_ms.saturating_add(offset_ms);
+                e
+            }),
+            MAX_SCROLL_EVENTS,
+        );
+        append_bounded(
+            &mut self.click_events,
+            batch.click_events.into_iter().map(|mut e| {
+                e.timestamp_ms = e.timestamp_ms.saturating_add(offset_ms);
+                e
+            }),
+            MAX_CLICK_EVENTS,
+        );
+    }
+}
+
+impl BehavioralSignals {
+    /// Append a batch of events, shifting their timestamps by `offset_ms`
+    /// (see [`EventBatch::append`]).
+    pub fn append_events(&mut self, offset_ms: u64, batch: EventBatch) {
+        let mut events = self.take_events();
+        events.append(offset_ms, batch);
+        self.set_events(events);
+    }
+
+    /// Down-sample event collections over their `MAX_*` limits like
+    /// [`EventBatch::append`] does (DoS protection).
+    pub fn bound_events(&mut self) {
+        self.append_events(0, EventBatch::default());
+    }
+
+    /// Move the event collections out, leaving them empty.
+    pub fn take_events(&mut self) -> EventBatch {
+        EventBatch {
+            mouse_events: std::mem::take(&mut self.mouse_events),
+            scroll_events: std::mem::take(&mut self.scroll_events),
+            click_events: std::mem::take(&mut self.click_events),
+        }
+    }
+
+    /// Replace the event collections.
+    pub fn set_events(&mut self, events: EventBatch) {
+        self.mouse_events = events.mouse_events;
+        self.scroll_events = events.scroll_events;
+        self.click_events = events.click_events;
+    }
+}
+
+/// Append events, halving the collection until it holds at most `max`.
+fn append_bounded<T>(events: &mut Vec<T>, new: impl IntoIterator<Item = T>, max: usize) {
+    events.extend(new);
+    while events.len() > max.max(1) {
+        let mut index = 0;
+        events.retain(|_| {
+            index += 1;
+            index % 2 == 1
+        });
+    }
+}
+
 /// Maximum number of mouse events to store (DoS protection).
 pub const MAX_MOUSE_EVENTS: usize = 1000;
 
@@ -163,11 +270,113 @@ mod tests {
         assert_eq!(signals, deserialized);
     }
 
+    #[test]
+    fn test_append_events_shifts_timestamps() {
+        let mut signals = BehavioralSignals {
+            mouse_events: vec![],
+            scroll_events: vec![],
+            click_events: vec![],
+            timing: TimingMetrics::default(),
+        };
+        let batch = EventBatch {
+            click_events: vec![ClickEvent {
+                timestamp_ms: 20,
+                x: 1,
+                y: 1,
+                button: MouseButton::Left,
+            }],
+            ..Default::default()
+        };
+
+        signals.append_events(1_000, batch);
+        assert_eq!(signals.click_events[0].timestamp_ms, 1_020);
+    }
+
+    #[test]
+    fn test_append_events_down_samples_at_limit() {
+        let move_at = |timestamp_ms| MouseEvent {
+            timestamp_ms,
+            x: 0,
+            y: 0,
+            event_type: MouseEventType::Move,
+        };
+        let mut signals = BehavioralSignals {
+            mouse_events: (0..MAX_MOUSE_EVENTS as u64).map(move_at).collect(),
+            scroll_events: vec![],
+            click_events: vec![],
+            timing: TimingMetrics::default(),
+        };
+        let batch = EventBatch {
+            mouse_events: (0..10).map(move_at).collect(),
+            ..Default::default()
+        };
+
+        signals.append_events(MAX_MOUSE_EVENTS as u64, batch);
+
+        let events = &signals.mouse_events;
+        assert!(events.len() <= MAX_MOUSE_EVENTS);
+        assert!(events.len() >= MAX_MOUSE_EVENTS / 2);
+        // Events from the whole session are kept, not just the oldest
+        assert_eq!(events[0].timestamp_ms, 0);
+        assert!(events[events.len() - 1].timestamp_ms >= MAX_MOUSE_EVENTS as u64);
+    }
+
+    #[test]
+    fn test_bound_events_down_samples_oversized_collections() {
+        let click_at = |timestamp_ms| ClickEvent {
+            timestamp_ms,
+            x: 0,
+            y: 0,
+            button: MouseButton::Left,
+        };
+        let mut signals = BehavioralSignals {
+            mouse_events: vec![],
+            scroll_events: vec![],
+            click_events: (0..10 * MAX_CLICK_EVENTS as u64).map(click_at).collect(),
+            timing: TimingMetrics::default(),
+        };
+
+        signals.bound_events();
+
+        let events = &signals.click_events;
+        assert!(events.len() <= MAX_CLICK_EVENTS);
+        assert!(events.len() >= MAX_CLICK_EVENTS / 2);
+        assert_eq!(events[0].timestamp_ms, 0);
+    }
+
+    #[test]
+    fn test_take_events_leaves_timing() {
+        let mut signals = BehavioralSignals {
+            mouse_events: vec![],
+            scroll_events: vec![],
+            click_events: vec![ClickEvent {
+                timestamp_ms: 5,
+                x: 1,
+                y: 1,
+                button: MouseButton::Left,
+            }],
+            timing: TimingMetrics {
+                load_time_ms: Some(800),
+                ..Default::default()
+            },
+        };
+
+        let events = signals.take_events();
+        assert_eq!(events.len(), 1);
+        assert!(signals.click_events.is_empty());
+        assert_eq!(signals.timing.load_time_ms, Some(800));
+
+        signals.set_events(events);
+        assert_eq!(signals.click_events.len(), 1);
+    }
+
     #[test]
     fn test_bounded_collection_constants() {
         // Verify DoS protection limits are reasonable
-        assert!(MAX_MOUSE_EVENTS <= 1000);
-        assert!(MAX_SCROLL_EVENTS <= 100);
-        assert!(MAX_CLICK_EVENTS <= 100);
+        const {
+            assert!(MAX_MOUSE_EVENTS <= 1000);
+            assert!(MAX_SCROLL_EVENTS <= 100);
+            assert!(MAX_CLICK_EVENTS <= 100);
+        }
     }
 }
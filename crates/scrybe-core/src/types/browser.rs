@@ -1,5 +1,6 @@
 //! Browser environment signals.
 
+use crate::error::ScrybeError;
 use serde::{Deserialize, Serialize};
 
 /// Browser environment signals collected from browser APIs.
@@ -7,10 +8,19 @@ use serde::{Deserialize, Serialize};
 pub struct BrowserSignals {
     /// Canvas fingerprint hash (SHA-256)
     pub canvas_hash: Option<String>,
+    /// Canvas hash of a second, identical render in the same page load
+    #[serde(default)]
+    pub canvas_hash_repeat: Option<String>,
     /// WebGL fingerprint hash (SHA-256)
     pub webgl_hash: Option<String>,
+    /// Structured WebGL parameters (if collected)
+    #[serde(default)]
+    pub webgl: Option<WebGlInfo>,
     /// Audio fingerprint hash (SHA-256)
     pub audio_hash: Option<String>,
+    /// Audio hash of a second, identical render in the same page load
+    #[serde(default)]
+    pub audio_hash_repeat: Option<String>,
     /// List of installed fonts
     pub fonts: Vec<String>,
     /// List of browser plugins
@@ -23,6 +33,134 @@ pub struct BrowserSignals {
     pub screen: ScreenInfo,
     /// User agent string
     pub user_agent: String,
+    /// Automation framework markers (if collected)
+    #[serde(default)]
+    pub automation: Option<AutomationMarkers>,
+}
+
+impl BrowserSignals {
+    /// Check list sizes against their `MAX_*` limits (DoS protection).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ValidationError` naming the first list over
+    /// its limit.
+    pub fn validate(&self) -> Result<(), ScrybeError> {
+        let globals = self.automation.as_ref().map_or(0, |m| m.globals.len());
+        let (extensions, shader_precisions) = self.webgl.as_ref().map_or((0, 0), |webgl| {
+            (webgl.extensions.len(), webgl.shader_precisions.len())
+        });
+
+        for (field, len, max) in [
+            ("automation.globals", globals, MAX_AUTOMATION_GLOBALS),
+            ("webgl.extensions", extensions, MAX_WEBGL_EXTENSIONS),
+            (
+                "webgl.shader_precisions",
+                shader_precisions,
+                MAX_WEBGL_SHADER_PRECISIONS,
+            ),
+        ] {
+            if len > max {
+                return Err(ScrybeError::validation_error(
+                    field,
+                    format!("<= {} entries", max),
+                    len.to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Markers left in the page environment by automation frameworks.
+///
+/// Every field is optional so older SDK versions can omit what they do not
+/// collect.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct AutomationMarkers {
+    /// Value of `navigator.webdriver`
+    pub webdriver: Option<bool>,
+    /// Whether `window.chrome` is present
+    pub chrome_object: Option<bool>,
+    /// Whether `window.outerWidth` and `window.outerHeight` are both zero
+    pub zero_outer_dimensions: Option<bool>,
+    /// Known automation globals found on `window` or `document`
+    /// (e.g., "__playwright__binding__", "cdc_adoQpoasnfa76pfcZLmcfl_Array")
+    #[serde(default)]
+    pub globals: Vec<String>,
+}
+
+/// Maximum number of automation globals accepted per session (DoS protection).
+pub const MAX_AUTOMATION_GLOBALS: usize = 32;
+
+/// WebGL parameters reported by the browser.
+///
+/// Vendor and renderer come from the `WEBGL_debug_renderer_info` extension;
+/// browsers that block it leave them empty.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct WebGlInfo {
+    /// Unmasked vendor (e.g., "Google Inc. (NVIDIA)")
+    pub unmasked_vendor: Option<String>,
+    /// Unmasked renderer (e.g., "ANGLE (NVIDIA, NVIDIA GeForce RTX 3060 Direct3D11 vs_5_0 ps_5_0)")
+    pub unmasked_renderer: Option<String>,
+    /// `MAX_TEXTURE_SIZE` parameter
+    pub max_texture_size: Option<u32>,
+    /// Supported extensions
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// Shader precision formats
+    #[serde(default)]
+    pub shader_precisions: Vec<ShaderPrecision>,
+}
+
+/// Maximum number of WebGL extensions accepted per session (DoS protection).
+pub const MAX_WEBGL_EXTENSIONS: usize = 128;
+
+/// Maximum number of shader precision formats accepted per session: one per
+/// shader stage and precision type (DoS protection).
+pub const MAX_WEBGL_SHADER_PRECISIONS: usize = 12;
+
+/// Result of `getShaderPrecisionFormat` for one shader and precision type.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ShaderPrecision {
+    /// Shader stage
+    pub shader: ShaderStage,
+    /// Precision type queried
+    pub precision_type: PrecisionType,
+    /// Base-2 log of the minimum representable magnitude
+    pub range_min: i32,
+    /// Base-2 log of the maximum representable magnitude
+    pub range_max: i32,
+    /// Bits of precision
+    pub precision: i32,
+}
+
+/// WebGL shader stage.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ShaderStage {
+    /// `VERTEX_SHADER`
+    Vertex,
+    /// `FRAGMENT_SHADER`
+    Fragment,
+}
+
+/// WebGL shader precision type.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PrecisionType {
+    /// `LOW_FLOAT`
+    LowFloat,
+    /// `MEDIUM_FLOAT`
+    MediumFloat,
+    /// `HIGH_FLOAT`
+    HighFloat,
+    /// `LOW_INT`
+    LowInt,
+    /// `MEDIUM_INT`
+    MediumInt,
+    /// `HIGH_INT`
+    HighInt,
 }
 
 /// Screen and display information.
@@ -130,18 +268,84 @@ mod tests {
         assert_eq!(screen.color_depth, 24);
     }
 
+    #[test]
+    fn test_browser_signals_validate_list_limits() {
+        let mut signals = crate::test_util::browser_signals();
+        assert!(signals.validate().is_ok());
+
+        let precision = ShaderPrecision {
+            shader: ShaderStage::Vertex,
+            precision_type: PrecisionType::HighFloat,
+            range_min: 127,
+            range_max: 127,
+            precision: 23,
+        };
+        signals.webgl = Some(WebGlInfo {
+            extensions: vec!["OES_texture_float".to_string(); MAX_WEBGL_EXTENSIONS],
+            shader_precisions: vec![precision; MAX_WEBGL_SHADER_PRECISIONS],
+            ..WebGlInfo::default()
+        });
+        signals.automation = Some(AutomationMarkers {
+            globals: vec!["__playwright__binding__".to_string(); MAX_AUTOMATION_GLOBALS],
+            ..AutomationMarkers::default()
+        });
+        assert!(signals.validate().is_ok());
+
+        let mut over = signals.clone();
+        if let Some(webgl) = over.webgl.as_mut() {
+            webgl.shader_precisions.push(precision);
+        }
+        assert!(matches!(
+            over.validate(),
+            Err(ScrybeError::ValidationError { field, .. }) if field == "webgl.shader_precisions"
+        ));
+
+        let mut over = signals.clone();
+        if let Some(webgl) = over.webgl.as_mut() {
+            webgl.extensions.push("WEBGL_lose_context".to_string());
+        }
+        assert!(over.validate().is_err());
+
+        let mut over = signals;
+        if let Some(markers) = over.automation.as_mut() {
+            markers
+                .globals
+                .push("cdc_adoQpoasnfa76pfcZLmcfl_Array".to_string());
+        }
+        assert!(over.validate().is_err());
+    }
+
     #[test]
     fn test_browser_signals_serialization() {
         let signals = BrowserSignals {
             canvas_hash: Some("abc123".to_string()),
+            canvas_hash_repeat: None,
             webgl_hash: None,
+            webgl: Some(WebGlInfo {
+                unmasked_vendor: Some("Google Inc. (Apple)".to_string()),
+                unmasked_renderer: Some("ANGLE (Apple, Apple M1, OpenGL 4.1)".to_string()),
+                max_texture_size: Some(16384),
+                extensions: vec!["OES_texture_float".to_string()],
+                shader_precisions: vec![ShaderPrecision {
+                    shader: ShaderStage::Fragment,
+                    precision_type: PrecisionType::HighFloat,
+                    range_min: 127,
+                    range_max: 127,
+                    precision: 23,
+                }],
+            }),
             audio_hash: None,
+            audio_hash_repeat: None,
             fonts: vec!["Arial".to_string(), "Helvetica".to_string()],
             plugins: vec![],
             timezone: "America/New_York".to_string(),
             language: "en-US".to_string(),
             screen: ScreenInfo::default(),
             user_agent: "Mozilla/5.0".to_string(),
+            automation: Some(AutomationMarkers {
+                webdriver: Some(false),
+                ..AutomationMarkers::default()
+            }),
         };
 
         let json = serde_json::to_string(&signals).unwrap();
@@ -149,6 +353,21 @@ mod tests {
         assert_eq!(signals, deserialized);
     }
 
+    #[test]
+    fn test_automation_markers_optional() {
+        let json = r#"{
+            "canvas_hash": null, "webgl_hash": null, "audio_hash": null,
+            "fonts": [], "plugins": [], "timezone": "UTC", "language": "en-US",
+            "screen": {"width": 1920, "height": 1080, "avail_width": 1920,
+                       "avail_height": 1040, "color_depth": 24, "pixel_ratio": 1.0},
+            "user_agent": "Mozilla/5.0"
+        }"#;
+        let signals: BrowserSignals = serde_json::from_str(json).unwrap();
+        assert!(signals.automation.is_none());
+        assert!(signals.webgl.is_none());
+        assert!(signals.canvas_hash_repeat.is_none());
+    }
+
     #[test]
     fn test_retina_display() {
         let screen = ScreenInfo::new(2880, 1800, 2880, 1800, 24, 2.0);
@@ -0,0 +1,265 @@
+//! Enriched sessions: raw session data plus derived enrichment outputs.
+//!
+//! [`Session`] is the raw ingest contract and never changes during
+//! enrichment. Every derived value lives next to it in [`EnrichedSession`],
+//! tagged with the stage, version and time that produced it, so outputs
+//! can be added or recomputed without touching ingest.
+
+use super::{AutomationLikelihoods, BotEvidence, Fingerprint, GeoInfo, IpReputation, Session};
+use crate::error::ScrybeError;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Current [`EnrichedSession`] schema version.
+///
+/// Bump when a change cannot be read by older consumers (renamed or
+/// retyped fields). New optional fields do not require a bump.
+pub const ENRICHED_SESSION_SCHEMA_VERSION: u32 = 1;
+
+/// Where an enrichment output came from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Provenance {
+    /// Name of the stage that produced the output (e.g., "geo")
+    pub stage: String,
+    /// Version of the stage implementation or model
+    pub version: String,
+    /// When the output was produced
+    pub produced_at: DateTime<Utc>,
+}
+
+impl Provenance {
+    /// Create provenance for an output produced now.
+    pub fn new(stage: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            stage: stage.into(),
+            version: version.into(),
+            produced_at: Utc::now(),
+        }
+    }
+}
+
+/// An enrichment output together with its provenance.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Enrichment<T> {
+    /// The output value
+    pub value: T,
+    /// Stage, version and time that produced the value
+    pub provenance: Provenance,
+}
+
+impl<T> Enrichment<T> {
+    /// Wrap a value with its provenance.
+    pub fn new(value: T, provenance: Provenance) -> Self {
+        Self { value, provenance }
+    }
+}
+
+/// A previously seen fingerprint similar to the session's.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SimilarFingerprint {
+    /// Hash of the similar fingerprint
+    pub fingerprint_hash: String,
+    /// Similarity score (0.0 - 1.0)
+    pub similarity: f64,
+    /// Number of sessions seen with that fingerprint
+    pub session_count: u64,
+}
+
+/// Fingerprint similarity results.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct Similarity {
+    /// Similar fingerprints, most similar first
+    pub similar_fingerprints: Vec<SimilarFingerprint>,
+    /// Cluster the fingerprint was assigned to, if any
+    pub cluster_id: Option<String>,
+}
+
+/// Device-level visitor a session was linked to.
+///
+/// Sessions sharing a fingerprint belong to the same visitor, so a
+/// returning device is recognised even after its session has expired.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Visitor {
+    /// Visitor identifier (UUID v4)
+    pub id: String,
+    /// Number of sessions linked to the visitor, including this one
+    pub session_count: u64,
+}
+
+impl Visitor {
+    /// Whether the visitor had sessions before this one.
+    pub fn is_returning(&self) -> bool {
+        self.session_count > 1
+    }
+}
+
+/// Overall bot score.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct BotScore {
+    /// Probability that the session is automated (0.0 - 1.0)
+    pub probability: f64,
+}
+
+/// A session with every enrichment output computed for it.
+///
+/// Outputs are `None` until their stage succeeds, so consumers can tell a
+/// missing result from an empty one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EnrichedSession {
+    /// Schema version this value was written with
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
+    /// Raw session as ingested
+    pub session: Session,
+    /// Composite fingerprint
+    #[serde(default)]
+    pub fingerprint: Option<Enrichment<Fingerprint>>,
+    /// Geolocation resolved from the client IP
+    #[serde(default)]
+    pub geo: Option<Enrichment<GeoInfo>>,
+    /// Reputation of the client IP
+    #[serde(default)]
+    pub ip_reputation: Option<Enrichment<IpReputation>>,
+    /// Per-framework automation likelihoods
+    #[serde(default)]
+    pub automation: Option<Enrichment<AutomationLikelihoods>>,
+    /// Visitor linked through the fingerprint
+    #[serde(default)]
+    pub visitor: Option<Enrichment<Visitor>>,
+    /// Similar fingerprints seen before
+    #[serde(default)]
+    pub similarity: Option<Enrichment<Similarity>>,
+    /// Overall bot score
+    #[serde(default)]
+    pub bot_score: Option<Enrichment<BotScore>>,
+    /// Rule hits recorded by every detector (named by rule)
+    #[serde(default)]
+    pub evidence: BotEvidence,
+    /// When enrichment last completed
+    pub enriched_at: DateTime<Utc>,
+}
+
+/// Schema version assumed for values written before versioning.
+fn default_schema_version() -> u32 {
+    1
+}
+
+impl EnrichedSession {
+    /// Wrap a raw session with no enrichment outputs yet.
+    pub fn new(session: Session) -> Self {
+        Self {
+            schema_version: ENRICHED_SESSION_SCHEMA_VERSION,
+            session,
+            fingerprint: None,
+            geo: None,
+            ip_reputation: None,
+            automation: None,
+            visitor: None,
+            similarity: None,
+            bot_score: None,
+            evidence: BotEvidence::default(),
+            enriched_at: Utc::now(),
+        }
+    }
+
+    /// Deserialize from JSON, rejecting schema versions newer than this
+    /// build understands.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ValidationError` if the JSON is malformed or
+    /// the schema version is unsupported.
+    pub fn from_json(json: &str) -> Result<Self, ScrybeError> {
+        let enriched: Self = serde_json::from_str(json).map_err(|e| {
+            ScrybeError::validation_error("enriched_session", "valid JSON", e.to_string())
+        })?;
+
+        if enriched.schema_version > ENRICHED_SESSION_SCHEMA_VERSION {
+            return Err(ScrybeError::validation_error(
+                "schema_version",
+                format!("<= {}", ENRICHED_SESSION_SCHEMA_VERSION),
+                enriched.schema_version.to_string(),
+            ));
+        }
+
+        Ok(enriched)
+    }
+
+    /// Probability that the session is automated (0.0 - 1.0).
+    ///
+    /// Uses the bot score if it was computed, otherwise the combined score
+    /// of the recorded evidence.
+    pub fn bot_probability(&self) -> f64 {
+        match &self.bot_score {
+            Some(score) => score.value.probability,
+            None => self.evidence.score(),
+        }
+    }
+
+    /// Fingerprint hash, if the fingerprint has been computed.
+    pub fn fingerprint_hash(&self) -> Option<&str> {
+        self.fingerprint.as_ref().map(|f| f.value.hash.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util;
+    use crate::types::FingerprintComponents;
+
+    #[test]
+    fn test_new_has_no_outputs() {
+        let enriched = test_util::enriched_session();
+        assert_eq!(enriched.schema_version, ENRICHED_SESSION_SCHEMA_VERSION);
+        assert!(enriched.fingerprint.is_none());
+        assert!(enriched.fingerprint_hash().is_none());
+        assert!(enriched.evidence.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip_with_provenance() {
+        let mut enriched = test_util::enriched_session();
+        let fingerprint =
+            Fingerprint::new("a".repeat(64), FingerprintComponents::default(), 0.9).unwrap();
+        enriched.fingerprint = Some(Enrichment::new(
+            fingerprint,
+            Provenance::new("fingerprint", "0.1.0"),
+        ));
+
+        let json = serde_json::to_string(&enriched).unwrap();
+        let parsed = EnrichedSession::from_json(&json).unwrap();
+        assert_eq!(parsed, enriched);
+        assert_eq!(parsed.fingerprint_hash(), Some("a".repeat(64).as_str()));
+        assert_eq!(
+            parsed.fingerprint.map(|f| f.provenance.stage),
+            Some("fingerprint".to_string())
+        );
+    }
+
+    #[test]
+    fn test_missing_outputs_deserialize_as_none() {
+        let enriched = test_util::enriched_session();
+        let mut value = serde_json::to_value(&enriched).unwrap();
+        let object = value.as_object_mut().unwrap();
+        object.remove("schema_version");
+        object.remove("geo");
+        object.remove("evidence");
+
+        let parsed = EnrichedSession::from_json(&value.to_string()).unwrap();
+        assert_eq!(parsed.schema_version, 1);
+        assert!(parsed.geo.is_none());
+    }
+
+    #[test]
+    fn test_newer_schema_rejected() {
+        let mut enriched = test_util::enriched_session();
+        enriched.schema_version = ENRICHED_SESSION_SCHEMA_VERSION + 1;
+        let json = serde_json::to_string(&enriched).unwrap();
+
+        assert!(matches!(
+            EnrichedSession::from_json(&json),
+            Err(ScrybeError::ValidationError { .. })
+        ));
+    }
+}
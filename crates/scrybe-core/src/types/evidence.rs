@@ -0,0 +1,260 @@
+//! Bot evidence produced by enrichment detectors.
+
+use serde::{Deserialize, Serialize};
+
+/// Severity of a detector finding.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Weak signal, common in legitimate traffic
+    Low,
+    /// Suspicious on its own, conclusive in combination
+    Medium,
+    /// Strong indicator of automation
+    High,
+}
+
+impl Severity {
+    /// Contribution of a single finding of this severity to the bot score.
+    pub fn weight(&self) -> f64 {
+        match self {
+            Self::Low => 0.1,
+            Self::Medium => 0.3,
+            Self::High => 0.6,
+        }
+    }
+}
+
+/// A named finding reported by a detector.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Finding {
+    /// Stable machine-readable name (e.g., "teleport_click")
+    pub name: String,
+    /// Severity of the finding
+    pub severity: Severity,
+    /// Human-readable explanation with supporting numbers
+    pub description: String,
+}
+
+impl Finding {
+    /// Create a new finding.
+    pub fn new(
+        name: impl Into<String>,
+        severity: Severity,
+        description: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            severity,
+            description: description.into(),
+        }
+    }
+}
+
+/// Evidence that a session is automated, accumulated across detectors.
+///
+/// Findings are keyed by name: recording a finding that is already present
+/// keeps the more severe of the two. The collection is bounded to
+/// [`MAX_FINDINGS`] entries (DoS protection).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct BotEvidence {
+    /// Findings recorded so far
+    pub findings: Vec<Finding>,
+}
+
+/// Maximum number of findings stored per session (DoS protection).
+pub const MAX_FINDINGS: usize = 64;
+
+impl BotEvidence {
+    /// Record a single finding.
+    pub fn record(&mut self, finding: Finding) {
+        if let Some(existing) = self.findings.iter_mut().find(|f| f.name == finding.name) {
+            if finding.severity > existing.severity {
+                *existing = finding;
+            }
+            return;
+        }
+
+        if self.findings.len() < MAX_FINDINGS {
+            self.findings.push(finding);
+        }
+    }
+
+    /// Record every finding from an iterator.
+    pub fn extend(&mut self, findings: impl IntoIterator<Item = Finding>) {
+        for finding in findings {
+            self.record(finding);
+        }
+    }
+
+    /// Check whether a finding with the given name was recorded.
+    pub fn contains(&self, name: &str) -> bool {
+        self.findings.iter().any(|f| f.name == name)
+    }
+
+    /// Check whether no findings were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    /// Combined bot score (0.0 - 1.0).
+    ///
+    /// Findings are treated as independent: the score is the probability
+    /// that at least one of them is a true positive.
+    pub fn score(&self) -> f64 {
+        let clean = self
+            .findings
+            .iter()
+            .fold(1.0, |acc, f| acc * (1.0 - f.severity.weight()));
+        1.0 - clean
+    }
+}
+
+/// Browser automation framework.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AutomationFramework {
+    /// Puppeteer (Chrome DevTools Protocol)
+    Puppeteer,
+    /// Playwright
+    Playwright,
+    /// Selenium / WebDriver
+    Selenium,
+    /// Headless Chrome, whichever framework drives it
+    HeadlessChrome,
+}
+
+impl AutomationFramework {
+    /// All frameworks, in declaration order.
+    pub const ALL: [Self; 4] = [
+        Self::Puppeteer,
+        Self::Playwright,
+        Self::Selenium,
+        Self::HeadlessChrome,
+    ];
+
+    /// Stable lowercase name (e.g., "headless_chrome").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Puppeteer => "puppeteer",
+            Self::Playwright => "playwright",
+            Self::Selenium => "selenium",
+            Self::HeadlessChrome => "headless_chrome",
+        }
+    }
+}
+
+impl std::fmt::Display for AutomationFramework {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Likelihood (0.0 - 1.0) that a session is driven by each framework.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct AutomationLikelihoods {
+    /// Puppeteer likelihood
+    pub puppeteer: f64,
+    /// Playwright likelihood
+    pub playwright: f64,
+    /// Selenium likelihood
+    pub selenium: f64,
+    /// Headless Chrome likelihood
+    pub headless_chrome: f64,
+}
+
+impl AutomationLikelihoods {
+    /// Get the likelihood for a framework.
+    pub fn get(&self, framework: AutomationFramework) -> f64 {
+        match framework {
+            AutomationFramework::Puppeteer => self.puppeteer,
+            AutomationFramework::Playwright => self.playwright,
+            AutomationFramework::Selenium => self.selenium,
+            AutomationFramework::HeadlessChrome => self.headless_chrome,
+        }
+    }
+
+    /// Get a mutable reference to the likelihood for a framework.
+    pub fn get_mut(&mut self, framework: AutomationFramework) -> &mut f64 {
+        match framework {
+            AutomationFramework::Puppeteer => &mut self.puppeteer,
+            AutomationFramework::Playwright => &mut self.playwright,
+            AutomationFramework::Selenium => &mut self.selenium,
+            AutomationFramework::HeadlessChrome => &mut self.headless_chrome,
+        }
+    }
+
+    /// Get the most likely framework, if any likelihood is non-zero.
+    pub fn most_likely(&self) -> Option<(AutomationFramework, f64)> {
+        AutomationFramework::ALL
+            .into_iter()
+            .map(|framework| (framework, self.get(framework)))
+            .filter(|(_, likelihood)| *likelihood > 0.0)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_evidence_scores_zero() {
+        let evidence = BotEvidence::default();
+        assert!(evidence.is_empty());
+        assert_eq!(evidence.score(), 0.0);
+    }
+
+    #[test]
+    fn test_record_keeps_most_severe() {
+        let mut evidence = BotEvidence::default();
+        evidence.record(Finding::new("linear_path", Severity::Low, "first"));
+        evidence.record(Finding::new("linear_path", Severity::High, "second"));
+        evidence.record(Finding::new("linear_path", Severity::Medium, "third"));
+
+        assert_eq!(evidence.findings.len(), 1);
+        assert_eq!(evidence.findings[0].severity, Severity::High);
+        assert_eq!(evidence.findings[0].description, "second");
+    }
+
+    #[test]
+    fn test_score_combines_findings() {
+        let mut evidence = BotEvidence::default();
+        evidence.record(Finding::new("a", Severity::High, ""));
+        let single = evidence.score();
+        evidence.record(Finding::new("b", Severity::Medium, ""));
+
+        assert!((single - 0.6).abs() < f64::EPSILON);
+        assert!(evidence.score() > single);
+        assert!(evidence.score() < 1.0);
+    }
+
+    #[test]
+    fn test_findings_are_bounded() {
+        let mut evidence = BotEvidence::default();
+        evidence
+            .extend((0..MAX_FINDINGS + 10).map(|i| Finding::new(i.to_string(), Severity::Low, "")));
+        assert_eq!(evidence.findings.len(), MAX_FINDINGS);
+    }
+
+    #[test]
+    fn test_most_likely_framework() {
+        assert!(AutomationLikelihoods::default().most_likely().is_none());
+
+        let likelihoods = AutomationLikelihoods {
+            selenium: 0.9,
+            headless_chrome: 0.4,
+            ..AutomationLikelihoods::default()
+        };
+        assert_eq!(
+            likelihoods.most_likely(),
+            Some((AutomationFramework::Selenium, 0.9))
+        );
+    }
+
+    #[test]
+    fn test_severity_serializes_lowercase() {
+        let json = serde_json::to_string(&Severity::Medium).unwrap();
+        assert_eq!(json, "\"medium\"");
+    }
+}
@@ -0,0 +1,64 @@
+//! Geolocation and network ownership resolved from the client IP.
+
+use serde::{Deserialize, Serialize};
+
+/// Geolocation and ASN information for a session's IP address.
+///
+/// Every field is optional: local databases may not cover an address, and
+/// City and ASN data come from separate databases.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct GeoInfo {
+    /// ISO 3166-1 alpha-2 country code (e.g., "GB")
+    pub country_code: Option<String>,
+    /// Country name (English)
+    pub country: Option<String>,
+    /// City name (English)
+    pub city: Option<String>,
+    /// Latitude in degrees
+    pub latitude: Option<f64>,
+    /// Longitude in degrees
+    pub longitude: Option<f64>,
+    /// IANA timezone of the location (e.g., "Europe/London")
+    pub timezone: Option<String>,
+    /// Autonomous system number
+    pub asn: Option<u32>,
+    /// Organization owning the autonomous system
+    pub asn_org: Option<String>,
+}
+
+impl GeoInfo {
+    /// Check whether no field was resolved.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_geo_info_default_is_empty() {
+        assert!(GeoInfo::default().is_empty());
+
+        let geo = GeoInfo {
+            asn: Some(20712),
+            ..GeoInfo::default()
+        };
+        assert!(!geo.is_empty());
+    }
+
+    #[test]
+    fn test_geo_info_serialization() {
+        let geo = GeoInfo {
+            country_code: Some("GB".to_string()),
+            city: Some("London".to_string()),
+            timezone: Some("Europe/London".to_string()),
+            ..GeoInfo::default()
+        };
+
+        let json = serde_json::to_string(&geo).unwrap();
+        let deserialized: GeoInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(geo, deserialized);
+    }
+}
@@ -16,6 +16,30 @@ pub struct NetworkSignals {
     pub headers: Vec<Header>,
     /// HTTP version used
     pub http_version: HttpVersion,
+    /// Request timing the gateway observed (set by the gateway only)
+    #[serde(default)]
+    pub gateway_timing: Option<GatewayTiming>,
+}
+
+/// Request timing the gateway observed for a page load.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GatewayTiming {
+    /// Time since the gateway received the session's previous page load (ms)
+    pub page_interval_ms: u64,
+}
+
+impl NetworkSignals {
+    /// Value of a header the gateway observed on the request.
+    ///
+    /// Headers the client reported itself are ignored, as are all but the
+    /// last observed header of that name. Names are case-insensitive.
+    pub fn observed_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .rev()
+            .find(|h| h.observed && h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
 }
 
 /// HTTP header key-value pair.
@@ -25,14 +49,27 @@ pub struct Header {
     pub name: String,
     /// Header value
     pub value: String,
+    /// Whether the gateway observed the header on the request, rather than
+    /// the client reporting it
+    #[serde(default)]
+    pub observed: bool,
 }
 
 impl Header {
-    /// Create a new header.
+    /// Create a client-reported header.
     pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
         Self {
             name: name.into(),
             value: value.into(),
+            observed: false,
+        }
+    }
+
+    /// Create a header the gateway observed on the request.
+    pub fn observed(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            observed: true,
+            ..Self::new(name, value)
         }
     }
 }
@@ -67,7 +104,7 @@ impl HttpVersion {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::net::Ipv4Addr;
+    use crate::test_util;
 
     #[test]
     fn test_header_creation() {
@@ -79,11 +116,9 @@ mod tests {
     #[test]
     fn test_network_signals_serialization() {
         let signals = NetworkSignals {
-            ip: IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
             ja3: Some("abc123".to_string()),
-            ja4: None,
             headers: vec![Header::new("User-Agent", "Test")],
-            http_version: HttpVersion::Http2,
+            ..test_util::network_signals()
         };
 
         let json = serde_json::to_string(&signals).unwrap();
@@ -91,6 +126,27 @@ mod tests {
         assert_eq!(signals, deserialized);
     }
 
+    #[test]
+    fn test_observed_header_ignores_client_headers() {
+        let signals = NetworkSignals {
+            headers: vec![
+                Header::new("Accept-Language", "en-US"),
+                Header::observed("accept-language", "ru-RU"),
+                Header::new("Referer", "https://spoofed.example/"),
+            ],
+            ..test_util::network_signals()
+        };
+
+        assert_eq!(signals.observed_header("Accept-Language"), Some("ru-RU"));
+        assert_eq!(signals.observed_header("referer"), None);
+    }
+
+    #[test]
+    fn test_header_observed_defaults_to_false() {
+        let header: Header = serde_json::from_str(r#"{"name":"Accept","value":"*/*"}"#).unwrap();
+        assert!(!header.observed);
+    }
+
     #[test]
     fn test_http_version_default() {
         let version = HttpVersion::default();
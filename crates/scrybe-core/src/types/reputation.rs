@@ -0,0 +1,92 @@
+//! IP reputation classification.
+
+use serde::{Deserialize, Serialize};
+
+/// Category of network an IP address belongs to.
+///
+/// Variants are ordered by precedence: when an address appears on several
+/// lists, the highest category is reported.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum IpCategory {
+    /// Cloud provider or hosting range
+    Datacenter,
+    /// Commercial VPN exit
+    Vpn,
+    /// Residential proxy network
+    ResidentialProxy,
+    /// Tor exit node
+    Tor,
+    /// Operator-supplied blocklist
+    Blocklisted,
+}
+
+impl IpCategory {
+    /// Stable lowercase name (e.g., "residential_proxy").
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Datacenter => "datacenter",
+            Self::Vpn => "vpn",
+            Self::ResidentialProxy => "residential_proxy",
+            Self::Tor => "tor",
+            Self::Blocklisted => "blocklisted",
+        }
+    }
+}
+
+impl std::fmt::Display for IpCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for IpCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "datacenter" => Ok(Self::Datacenter),
+            "vpn" => Ok(Self::Vpn),
+            "residential_proxy" => Ok(Self::ResidentialProxy),
+            "tor" => Ok(Self::Tor),
+            "blocklisted" => Ok(Self::Blocklisted),
+            _ => Err(format!("unknown IP category '{}'", s)),
+        }
+    }
+}
+
+/// Reputation of a session's IP address.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IpReputation {
+    /// Highest-precedence category among matching lists
+    pub category: IpCategory,
+    /// Names of every list that matched (e.g., "aws", "tor-exits")
+    pub sources: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_precedence() {
+        assert!(IpCategory::Blocklisted > IpCategory::Tor);
+        assert!(IpCategory::Tor > IpCategory::Datacenter);
+    }
+
+    #[test]
+    fn test_category_serialization_matches_name() {
+        for category in [
+            IpCategory::Datacenter,
+            IpCategory::Vpn,
+            IpCategory::ResidentialProxy,
+            IpCategory::Tor,
+            IpCategory::Blocklisted,
+        ] {
+            let json = serde_json::to_string(&category).unwrap();
+            assert_eq!(json, format!("\"{}\"", category));
+            assert_eq!(category.as_str().parse::<IpCategory>(), Ok(category));
+        }
+        assert!("proxy".parse::<IpCategory>().is_err());
+    }
+}
@@ -1,11 +1,14 @@
 //! Session and fingerprint types.
 
-use super::{BehavioralSignals, BrowserSignals, NetworkSignals};
+use super::{BehavioralSignals, BrowserSignals, EventBatch, NetworkSignals};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-/// Complete browser session with all collected signals.
+/// Browser session as collected at ingest.
+///
+/// Holds raw signals only; everything derived from them lives in
+/// [`EnrichedSession`](super::EnrichedSession).
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Session {
     /// Unique session identifier
@@ -18,8 +21,71 @@ pub struct Session {
     pub browser: BrowserSignals,
     /// User behavioral patterns
     pub behavioral: BehavioralSignals,
-    /// Computed fingerprint
-    pub fingerprint: Fingerprint,
+    /// Number of page loads stitched into this session
+    #[serde(default = "default_page_loads")]
+    pub page_loads: u32,
+    /// Start of the latest page load, in milliseconds since session start
+    #[serde(default)]
+    pub page_offset_ms: u64,
+}
+
+/// Page loads assumed for sessions written before stitching.
+fn default_page_loads() -> u32 {
+    1
+}
+
+impl Session {
+    /// Create a session from the signals of its first page load.
+    pub fn new(
+        network: NetworkSignals,
+        browser: BrowserSignals,
+        behavioral: BehavioralSignals,
+    ) -> Self {
+        Self {
+            id: SessionId::new(),
+            timestamp: Utc::now(),
+            network,
+            browser,
+            behavioral,
+            page_loads: 1,
+            page_offset_ms: 0,
+        }
+    }
+
+    /// Append the signals of a later page load to this session.
+    ///
+    /// Network and browser signals and timing metrics are replaced by the
+    /// latest page load. Events are re-based from page start to session
+    /// start and appended (see [`BehavioralSignals::append_events`]).
+    pub fn append_page_load(
+        &mut self,
+        loaded_at: DateTime<Utc>,
+        network: NetworkSignals,
+        browser: BrowserSignals,
+        behavioral: BehavioralSignals,
+    ) {
+        self.page_offset_ms =
+            u64::try_from((loaded_at - self.timestamp).num_milliseconds()).unwrap_or(0);
+
+        self.network = network;
+        self.browser = browser;
+        self.behavioral.timing = behavioral.timing;
+        self.behavioral.append_events(
+            self.page_offset_ms,
+            EventBatch {
+                mouse_events: behavioral.mouse_events,
+                scroll_events: behavioral.scroll_events,
+                click_events: behavioral.click_events,
+            },
+        );
+
+        self.page_loads = self.page_loads.saturating_add(1);
+    }
+
+    /// Append events sent during the latest page load.
+    pub fn append_events(&mut self, batch: EventBatch) {
+        self.behavioral.append_events(self.page_offset_ms, batch);
+    }
 }
 
 /// Unique session identifier (UUID v4).
@@ -81,6 +147,9 @@ pub struct FingerprintComponents {
     pub canvas: Option<String>,
     /// WebGL fingerprint hash
     pub webgl: Option<String>,
+    /// Structured WebGL parameters hash
+    #[serde(default)]
+    pub webgl_params: Option<String>,
     /// Audio fingerprint hash
     pub audio: Option<String>,
     /// Font list hash
@@ -177,42 +246,97 @@ mod tests {
 
     #[test]
     fn test_session_serialization() {
-        use crate::types::{HttpVersion, ScreenInfo};
-        use std::net::Ipv4Addr;
-
-        let session = Session {
-            id: SessionId::new(),
-            timestamp: Utc::now(),
-            network: NetworkSignals {
-                ip: std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-                ja3: None,
-                ja4: None,
-                headers: vec![],
-                http_version: HttpVersion::Http2,
-            },
-            browser: BrowserSignals {
-                canvas_hash: None,
-                webgl_hash: None,
-                audio_hash: None,
-                fonts: vec![],
-                plugins: vec![],
-                timezone: "UTC".to_string(),
-                language: "en-US".to_string(),
-                screen: ScreenInfo::default(),
-                user_agent: "Test".to_string(),
-            },
-            behavioral: BehavioralSignals {
-                mouse_events: vec![],
-                scroll_events: vec![],
-                click_events: vec![],
-                timing: Default::default(),
-            },
-            fingerprint: Fingerprint::new("a".repeat(64), FingerprintComponents::default(), 0.9)
-                .unwrap(),
-        };
+        let session = crate::test_util::session();
 
         let json = serde_json::to_string(&session).unwrap();
         let deserialized: Session = serde_json::from_str(&json).unwrap();
         assert_eq!(session.id, deserialized.id);
     }
+
+    fn create_test_signals() -> (NetworkSignals, BrowserSignals, BehavioralSignals) {
+        use crate::test_util;
+
+        (
+            test_util::network_signals(),
+            test_util::browser_signals(),
+            test_util::behavioral_signals(),
+        )
+    }
+
+    #[test]
+    fn test_append_page_load_rebases_events() {
+        use crate::types::{MouseEvent, MouseEventType};
+
+        let (network, browser, behavioral) = create_test_signals();
+        let mut session = Session::new(network.clone(), browser.clone(), behavioral.clone());
+
+        let mut next_page = behavioral;
+        next_page.mouse_events.push(MouseEvent {
+            timestamp_ms: 10,
+            x: 1,
+            y: 2,
+            event_type: MouseEventType::Move,
+        });
+        next_page.timing.load_time_ms = Some(250);
+        let loaded_at = session.timestamp + chrono::Duration::milliseconds(5_000);
+
+        session.append_page_load(loaded_at, network, browser, next_page);
+
+        assert_eq!(session.page_loads, 2);
+        assert_eq!(session.behavioral.mouse_events[0].timestamp_ms, 5_010);
+        assert_eq!(session.behavioral.timing.load_time_ms, Some(250));
+    }
+
+    #[test]
+    fn test_append_events_uses_latest_page_offset() {
+        use crate::types::ScrollEvent;
+
+        let (network, browser, behavioral) = create_test_signals();
+        let mut session = Session::new(network.clone(), browser.clone(), behavioral.clone());
+        let loaded_at = session.timestamp + chrono::Duration::milliseconds(2_000);
+        session.append_page_load(loaded_at, network, browser, behavioral);
+
+        session.append_events(EventBatch {
+            scroll_events: vec![ScrollEvent {
+                timestamp_ms: 30,
+                x: 0,
+                y: 100,
+                delta_x: 0,
+                delta_y: 100,
+            }],
+            ..Default::default()
+        });
+
+        assert_eq!(session.behavioral.scroll_events[0].timestamp_ms, 2_030);
+    }
+
+    #[test]
+    fn test_append_page_load_is_bounded() {
+        use crate::types::{ClickEvent, MouseButton, MAX_CLICK_EVENTS};
+
+        let (network, browser, mut behavioral) = create_test_signals();
+        let click = ClickEvent {
+            timestamp_ms: 0,
+            x: 0,
+            y: 0,
+            button: MouseButton::Left,
+        };
+        behavioral.click_events = vec![click; MAX_CLICK_EVENTS];
+        let mut session = Session::new(network.clone(), browser.clone(), behavioral.clone());
+
+        session.append_page_load(Utc::now(), network, browser, behavioral);
+
+        assert_eq!(session.behavioral.click_events.len(), MAX_CLICK_EVENTS);
+    }
+
+    #[test]
+    fn test_page_loads_defaults_to_one() {
+        let (network, browser, behavioral) = create_test_signals();
+        let session = Session::new(network, browser, behavioral);
+        let mut value = serde_json::to_value(&session).unwrap();
+        value.as_object_mut().unwrap().remove("page_loads");
+
+        let parsed: Session = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed.page_loads, 1);
+    }
 }
@@ -0,0 +1,331 @@
+//! Headless browser and automation framework detection.
+//!
+//! Combines individual indicators from `BrowserSignals` (and the optional
+//! `AutomationMarkers` section) into a likelihood per framework. Each
+//! indicator carries a weight per framework it points to; weights are
+//! combined as independent evidence, so one conclusive marker outweighs
+//! several weak ones.
+
+use crate::webgl::is_software_renderer;
+use scrybe_core::types::{
+    AutomationFramework, AutomationLikelihoods, BrowserSignals, Finding, Severity,
+    MAX_AUTOMATION_GLOBALS,
+};
+
+/// Likelihood at or above which a framework finding is recorded.
+const REPORT_THRESHOLD: f64 = 0.5;
+
+/// Likelihood at or above which a framework finding is High severity.
+const HIGH_THRESHOLD: f64 = 0.8;
+
+/// Global name prefixes injected by Selenium / ChromeDriver.
+const SELENIUM_GLOBALS: &[&str] = &[
+    "cdc_",
+    "$cdc_",
+    "$wdc_",
+    "__webdriver",
+    "__selenium",
+    "__fxdriver",
+    "__driver_evaluate",
+    "_selenium",
+    "_Selenium_IDE_Recorder",
+    "calledSelenium",
+];
+
+/// Global name prefixes injected by Playwright.
+const PLAYWRIGHT_GLOBALS: &[&str] = &["__playwright", "__pw"];
+
+/// Global name prefixes injected by Puppeteer.
+const PUPPETEER_GLOBALS: &[&str] = &["__puppeteer"];
+
+/// A single observed indicator and the frameworks it points to.
+#[derive(Debug, Clone, PartialEq)]
+struct Indicator {
+    /// Short description for finding text
+    name: &'static str,
+    /// Weight (0.0 - 1.0) contributed to each framework
+    weights: &'static [(AutomationFramework, f64)],
+}
+
+/// Detects headless browsers and automation frameworks.
+pub struct AutomationDetector;
+
+impl AutomationDetector {
+    /// Compute per-framework likelihoods from browser signals.
+    pub fn detect(browser: &BrowserSignals) -> AutomationLikelihoods {
+        Self::likelihoods(&Self::indicators(browser))
+    }
+
+    /// Compute likelihoods and a finding per likely framework.
+    ///
+    /// # Findings
+    ///
+    /// - `automation_{framework}` (Medium at 0.5, High at 0.8 likelihood),
+    ///   e.g. `automation_selenium`
+    pub fn assess(browser: &BrowserSignals) -> (AutomationLikelihoods, Vec<Finding>) {
+        let indicators = Self::indicators(browser);
+        let likelihoods = Self::likelihoods(&indicators);
+
+        let mut findings = Vec::new();
+        for framework in AutomationFramework::ALL {
+            let likelihood = likelihoods.get(framework);
+            if likelihood < REPORT_THRESHOLD {
+                continue;
+            }
+
+            let severity = if likelihood >= HIGH_THRESHOLD {
+                Severity::High
+            } else {
+                Severity::Medium
+            };
+            let reasons: Vec<&str> = indicators
+                .iter()
+                .filter(|i| i.weights.iter().any(|(f, _)| *f == framework))
+                .map(|i| i.name)
+                .collect();
+            findings.push(Finding::new(
+                format!("automation_{}", framework),
+                severity,
+                format!(
+                    "{} likelihood {:.2}: {}",
+                    framework,
+                    likelihood,
+                    reasons.join(", ")
+                ),
+            ));
+        }
+
+        (likelihoods, findings)
+    }
+
+    /// Combine indicator weights into per-framework likelihoods.
+    fn likelihoods(indicators: &[Indicator]) -> AutomationLikelihoods {
+        let mut clean = AutomationLikelihoods {
+            puppeteer: 1.0,
+            playwright: 1.0,
+            selenium: 1.0,
+            headless_chrome: 1.0,
+        };
+        for (framework, weight) in indicators.iter().flat_map(|i| i.weights) {
+            *clean.get_mut(*framework) *= 1.0 - weight;
+        }
+
+        let mut likelihoods = AutomationLikelihoods::default();
+        for framework in AutomationFramework::ALL {
+            *likelihoods.get_mut(framework) = 1.0 - clean.get(framework);
+        }
+        likelihoods
+    }
+
+    /// Collect every indicator present in the browser signals.
+    fn indicators(browser: &BrowserSignals) -> Vec<Indicator> {
+        use AutomationFramework::*;
+
+        let mut found = Vec::new();
+        let ua = browser.user_agent.as_str();
+        let desktop_chrome = is_desktop_chrome(ua);
+
+        if ua.contains("HeadlessChrome") {
+            found.push(Indicator {
+                name: "HeadlessChrome user agent",
+                weights: &[(HeadlessChrome, 0.95), (Puppeteer, 0.3), (Playwright, 0.3)],
+            });
+        }
+
+        if desktop_chrome && browser.plugins.is_empty() {
+            found.push(Indicator {
+                name: "no plugins on desktop Chrome",
+                weights: &[(HeadlessChrome, 0.4)],
+            });
+        }
+
+        if browser.fonts.is_empty() {
+            found.push(Indicator {
+                name: "no fonts detected",
+                weights: &[(HeadlessChrome, 0.3)],
+            });
+        }
+
+        let screen = &browser.screen;
+        if desktop_chrome
+            && screen.avail_width == screen.width
+            && screen.avail_height == screen.height
+        {
+            found.push(Indicator {
+                name: "screen has no reserved OS area",
+                weights: &[(HeadlessChrome, 0.15)],
+            });
+        }
+
+        let renderer = browser
+            .webgl
+            .as_ref()
+            .and_then(|w| w.unmasked_renderer.as_deref());
+        if renderer.is_some_and(is_software_renderer) {
+            found.push(Indicator {
+                name: "software WebGL renderer",
+                weights: &[(HeadlessChrome, 0.5)],
+            });
+        }
+
+        let Some(markers) = &browser.automation else {
+            return found;
+        };
+
+        if markers.webdriver == Some(true) {
+            found.push(Indicator {
+                name: "navigator.webdriver is true",
+                weights: &[(Selenium, 0.7), (Puppeteer, 0.5), (Playwright, 0.5)],
+            });
+        }
+
+        if markers.zero_outer_dimensions == Some(true) {
+            found.push(Indicator {
+                name: "window outer dimensions are zero",
+                weights: &[(HeadlessChrome, 0.6)],
+            });
+        }
+
+        if desktop_chrome && markers.chrome_object == Some(false) {
+            found.push(Indicator {
+                name: "Chrome user agent without window.chrome",
+                weights: &[(HeadlessChrome, 0.4)],
+            });
+        }
+
+        let globals = &markers.globals[..markers.globals.len().min(MAX_AUTOMATION_GLOBALS)];
+        if has_global(globals, SELENIUM_GLOBALS) {
+            found.push(Indicator {
+                name: "Selenium globals present",
+                weights: &[(Selenium, 0.95)],
+            });
+        }
+        if has_global(globals, PLAYWRIGHT_GLOBALS) {
+            found.push(Indicator {
+                name: "Playwright globals present",
+                weights: &[(Playwright, 0.95)],
+            });
+        }
+        if has_global(globals, PUPPETEER_GLOBALS) {
+            found.push(Indicator {
+                name: "Puppeteer globals present",
+                weights: &[(Puppeteer, 0.95)],
+            });
+        }
+
+        found
+    }
+}
+
+/// Check whether a user agent claims desktop Chrome (or Chromium).
+fn is_desktop_chrome(ua: &str) -> bool {
+    (ua.contains("Chrome/") || ua.contains("HeadlessChrome/"))
+        && !ua.contains("Mobile")
+        && !ua.contains("Android")
+}
+
+/// Check whether any global starts with one of the given prefixes.
+fn has_global(globals: &[String], prefixes: &[&str]) -> bool {
+    globals
+        .iter()
+        .any(|g| prefixes.iter().any(|p| g.starts_with(p)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+    use scrybe_core::types::{AutomationMarkers, ScreenInfo, WebGlInfo};
+
+    const CHROME_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                             (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+    fn browser(user_agent: &str, markers: Option<AutomationMarkers>) -> BrowserSignals {
+        BrowserSignals {
+            fonts: vec!["Arial".to_string(), "Segoe UI".to_string()],
+            plugins: vec!["PDF Viewer".to_string()],
+            timezone: "Europe/Berlin".to_string(),
+            language: "de-DE".to_string(),
+            screen: ScreenInfo::new(1920, 1080, 1920, 1040, 24, 1.0).unwrap(),
+            user_agent: user_agent.to_string(),
+            automation: markers,
+            ..test_util::browser_signals()
+        }
+    }
+
+    #[test]
+    fn test_regular_chrome_is_clean() {
+        let markers = AutomationMarkers {
+            webdriver: Some(false),
+            chrome_object: Some(true),
+            zero_outer_dimensions: Some(false),
+            globals: vec![],
+        };
+        let likelihoods = AutomationDetector::detect(&browser(CHROME_UA, Some(markers)));
+        assert!(likelihoods.most_likely().is_none());
+    }
+
+    #[test]
+    fn test_headless_chrome() {
+        let ua = CHROME_UA.replace("Chrome/", "HeadlessChrome/");
+        let mut signals = browser(&ua, None);
+        signals.plugins.clear();
+
+        let likelihoods = AutomationDetector::detect(&signals);
+        assert_eq!(
+            likelihoods.most_likely().map(|(f, _)| f),
+            Some(AutomationFramework::HeadlessChrome)
+        );
+        assert!(likelihoods.headless_chrome > 0.95);
+    }
+
+    #[test]
+    fn test_selenium_globals() {
+        let markers = AutomationMarkers {
+            webdriver: Some(true),
+            globals: vec!["cdc_adoQpoasnfa76pfcZLmcfl_Array".to_string()],
+            ..AutomationMarkers::default()
+        };
+        let likelihoods = AutomationDetector::detect(&browser(CHROME_UA, Some(markers)));
+        assert_eq!(
+            likelihoods.most_likely().map(|(f, _)| f),
+            Some(AutomationFramework::Selenium)
+        );
+        assert!(likelihoods.selenium > likelihoods.puppeteer);
+    }
+
+    #[test]
+    fn test_playwright_globals() {
+        let markers = AutomationMarkers {
+            globals: vec!["__playwright__binding__".to_string()],
+            ..AutomationMarkers::default()
+        };
+        let likelihoods = AutomationDetector::detect(&browser(CHROME_UA, Some(markers)));
+        assert!(likelihoods.playwright >= 0.95);
+        assert_eq!(likelihoods.selenium, 0.0);
+    }
+
+    #[test]
+    fn test_software_renderer_and_zero_outer_dimensions() {
+        let markers = AutomationMarkers {
+            zero_outer_dimensions: Some(true),
+            ..AutomationMarkers::default()
+        };
+        let mut signals = browser(CHROME_UA, Some(markers));
+        signals.webgl = Some(WebGlInfo {
+            unmasked_renderer: Some("Google SwiftShader".to_string()),
+            ..WebGlInfo::default()
+        });
+        let likelihoods = AutomationDetector::detect(&signals);
+        assert!(likelihoods.headless_chrome >= REPORT_THRESHOLD);
+    }
+
+    #[test]
+    fn test_mobile_chrome_without_plugins_is_clean() {
+        let ua = "Mozilla/5.0 (Linux; Android 14) AppleWebKit/537.36 \
+                  (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36";
+        let mut signals = browser(ua, None);
+        signals.plugins.clear();
+        assert!(AutomationDetector::detect(&signals).most_likely().is_none());
+    }
+}
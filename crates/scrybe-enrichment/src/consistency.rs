@@ -0,0 +1,427 @@
+//! Timezone, language and geolocation consistency checks.
+//!
+//! Compares what the browser reports about its locale with what the
+//! connecting IP says about its location. Proxied automation typically runs
+//! with the host's locale while exiting through an IP somewhere else, so
+//! disagreements here are a classic tell.
+//!
+//! IP location comes from `EnrichedSession::geo`, resolved by
+//! [`GeoEnricher`](crate::GeoEnricher) from the local database files; run
+//! that first. Checks that need location are skipped when it is missing.
+
+use chrono::{DateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use scrybe_core::types::{Finding, GeoInfo, Session, Severity};
+
+/// Offset difference (minutes) at or above which a timezone mismatch is High.
+const LARGE_OFFSET_DIFF_MINUTES: i32 = 180;
+
+/// Maximum number of Accept-Language entries considered.
+const MAX_ACCEPT_LANGUAGES: usize = 32;
+
+/// Languages commonly used in a country (ISO 3166-1 alpha-2 to ISO 639-1).
+///
+/// English is accepted everywhere and is not listed.
+const COUNTRY_LANGUAGES: &[(&str, &[&str])] = &[
+    ("AE", &["ar"]),
+    ("AR", &["es"]),
+    ("AT", &["de"]),
+    ("BE", &["nl", "fr", "de"]),
+    ("BG", &["bg"]),
+    ("BR", &["pt"]),
+    ("BY", &["be", "ru"]),
+    ("CA", &["fr"]),
+    ("CH", &["de", "fr", "it", "rm"]),
+    ("CL", &["es"]),
+    ("CN", &["zh"]),
+    ("CO", &["es"]),
+    ("CZ", &["cs"]),
+    ("DE", &["de"]),
+    ("DK", &["da"]),
+    ("EE", &["et", "ru"]),
+    ("EG", &["ar"]),
+    ("ES", &["es", "ca", "eu", "gl"]),
+    ("FI", &["fi", "sv"]),
+    ("FR", &["fr"]),
+    ("GR", &["el"]),
+    ("HK", &["zh"]),
+    ("HR", &["hr"]),
+    ("HU", &["hu"]),
+    ("ID", &["id"]),
+    ("IL", &["he", "ar"]),
+    (
+        "IN",
+        &["hi", "bn", "te", "mr", "ta", "ur", "gu", "kn", "ml"],
+    ),
+    ("IR", &["fa"]),
+    ("IT", &["it"]),
+    ("JP", &["ja"]),
+    ("KR", &["ko"]),
+    ("KZ", &["kk", "ru"]),
+    ("LT", &["lt"]),
+    ("LV", &["lv", "ru"]),
+    ("MX", &["es"]),
+    ("MY", &["ms", "zh"]),
+    ("NL", &["nl"]),
+    ("NO", &["nb", "nn", "no"]),
+    ("PE", &["es"]),
+    ("PH", &["fil", "tl"]),
+    ("PK", &["ur"]),
+    ("PL", &["pl"]),
+    ("PT", &["pt"]),
+    ("RO", &["ro"]),
+    ("RS", &["sr"]),
+    ("RU", &["ru"]),
+    ("SA", &["ar"]),
+    ("SE", &["sv"]),
+    ("SG", &["zh", "ms", "ta"]),
+    ("SI", &["sl"]),
+    ("SK", &["sk"]),
+    ("TH", &["th"]),
+    ("TR", &["tr"]),
+    ("TW", &["zh"]),
+    ("UA", &["uk", "ru"]),
+    ("VE", &["es"]),
+    ("VN", &["vi"]),
+];
+
+/// Detects locale signals that disagree with each other or the IP location.
+pub struct ConsistencyDetector;
+
+impl ConsistencyDetector {
+    /// Check a session's locale signals.
+    ///
+    /// # Findings
+    ///
+    /// - `invalid_timezone` (Medium): browser timezone is not an IANA name
+    /// - `timezone_mismatch`: browser UTC offset differs from the IP
+    ///   location's (High if 3 hours or more apart, Medium otherwise)
+    /// - `missing_accept_language` (Low): the gateway observed no
+    ///   `Accept-Language` header
+    /// - `accept_language_mismatch` (High): `navigator.language` is absent
+    ///   from the `Accept-Language` header
+    /// - `language_country_mismatch` (Low): no browser language is used in
+    ///   the IP's country
+    pub fn detect(session: &Session, geo: Option<&GeoInfo>) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        // The client may report its own Accept-Language; only the header the
+        // gateway saw on the request is trusted
+        let accept_language = session
+            .network
+            .observed_header("accept-language")
+            .map(parse_accept_language);
+
+        Self::check_timezone(
+            &session.browser.timezone,
+            geo,
+            session.timestamp,
+            &mut findings,
+        );
+        Self::check_languages(
+            &session.browser.language,
+            accept_language.as_deref(),
+            geo,
+            &mut findings,
+        );
+
+        findings
+    }
+
+    /// Compare the browser's UTC offset with the IP location's.
+    ///
+    /// Offsets are compared at the session timestamp, so zones that share an
+    /// offset (e.g., Europe/Paris and Europe/Berlin) are not reported.
+    fn check_timezone(
+        browser_timezone: &str,
+        geo: Option<&GeoInfo>,
+        at: DateTime<Utc>,
+        findings: &mut Vec<Finding>,
+    ) {
+        if browser_timezone.is_empty() {
+            return;
+        }
+        let Ok(browser_tz) = browser_timezone.parse::<Tz>() else {
+            findings.push(Finding::new(
+                "invalid_timezone",
+                Severity::Medium,
+                format!(
+                    "browser timezone '{}' is not an IANA name",
+                    browser_timezone
+                ),
+            ));
+            return;
+        };
+
+        let Some(ip_tz) = geo
+            .and_then(|g| g.timezone.as_deref())
+            .and_then(|tz| tz.parse::<Tz>().ok())
+        else {
+            return;
+        };
+
+        let diff_minutes =
+            (utc_offset_minutes(browser_tz, at) - utc_offset_minutes(ip_tz, at)).abs();
+        if diff_minutes == 0 {
+            return;
+        }
+
+        let severity = if diff_minutes >= LARGE_OFFSET_DIFF_MINUTES {
+            Severity::High
+        } else {
+            Severity::Medium
+        };
+        findings.push(Finding::new(
+            "timezone_mismatch",
+            severity,
+            format!(
+                "browser timezone {} is {} minutes from IP timezone {}",
+                browser_tz, diff_minutes, ip_tz
+            ),
+        ));
+    }
+
+    /// Compare `navigator.language`, `Accept-Language` and the IP country.
+    fn check_languages(
+        browser_language: &str,
+        accept_language: Option<&[String]>,
+        geo: Option<&GeoInfo>,
+        findings: &mut Vec<Finding>,
+    ) {
+        let browser_primary = primary_subtag(browser_language);
+
+        let Some(accepted) = accept_language else {
+            findings.push(Finding::new(
+                "missing_accept_language",
+                Severity::Low,
+                "request has no Accept-Language header",
+            ));
+            return;
+        };
+
+        if let Some(primary) = &browser_primary {
+            if !accepted.is_empty() && !accepted.contains(primary) {
+                findings.push(Finding::new(
+                    "accept_language_mismatch",
+                    Severity::High,
+                    format!(
+                        "navigator.language '{}' is not in Accept-Language ({})",
+                        browser_language,
+                        accepted.join(", ")
+                    ),
+                ));
+            }
+        }
+
+        let Some(country) = geo.and_then(|g| g.country_code.as_deref()) else {
+            return;
+        };
+        let Some(country_languages) = country_languages(country) else {
+            return;
+        };
+
+        let mut languages = accepted
+            .iter()
+            .map(String::as_str)
+            .chain(browser_primary.as_deref());
+        let plausible = languages.any(|lang| lang == "en" || country_languages.contains(&lang));
+        if !plausible {
+            findings.push(Finding::new(
+                "language_country_mismatch",
+                Severity::Low,
+                format!(
+                    "browser language '{}' is uncommon in IP country {}",
+                    browser_language, country
+                ),
+            ));
+        }
+    }
+}
+
+/// Get a timezone's UTC offset in minutes at an instant.
+fn utc_offset_minutes(tz: Tz, at: DateTime<Utc>) -> i32 {
+    tz.offset_from_utc_datetime(&at.naive_utc())
+        .fix()
+        .local_minus_utc()
+        / 60
+}
+
+/// Get the lowercase primary language subtag (e.g., "en-US" -> "en").
+fn primary_subtag(tag: &str) -> Option<String> {
+    let primary = tag.split(['-', '_']).next()?.trim();
+    (!primary.is_empty() && primary != "*").then(|| primary.to_ascii_lowercase())
+}
+
+/// Parse the primary subtags of an `Accept-Language` header, in order.
+///
+/// Entries with `q=0` are excluded; duplicates are removed.
+fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut languages: Vec<String> = Vec::new();
+    for entry in header.split(',').take(MAX_ACCEPT_LANGUAGES) {
+        let mut parts = entry.split(';');
+        let tag = parts.next().unwrap_or("");
+        let rejected = parts.any(|p| {
+            p.trim()
+                .strip_prefix("q=")
+                .and_then(|q| q.parse::<f32>().ok())
+                .is_some_and(|q| q <= 0.0)
+        });
+        if rejected {
+            continue;
+        }
+        if let Some(primary) = primary_subtag(tag) {
+            if !languages.contains(&primary) {
+                languages.push(primary);
+            }
+        }
+    }
+    languages
+}
+
+/// Look up the languages commonly used in a country.
+fn country_languages(country_code: &str) -> Option<&'static [&'static str]> {
+    COUNTRY_LANGUAGES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(country_code))
+        .map(|(_, languages)| *languages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use scrybe_core::test_util;
+    use scrybe_core::types::Header;
+
+    fn names(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    fn geo(country: &str, timezone: &str) -> GeoInfo {
+        GeoInfo {
+            country_code: Some(country.to_string()),
+            timezone: Some(timezone.to_string()),
+            ..GeoInfo::default()
+        }
+    }
+
+    fn winter() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_matching_timezone_offset() {
+        let mut findings = Vec::new();
+        let geo = geo("DE", "Europe/Berlin");
+        ConsistencyDetector::check_timezone("Europe/Paris", Some(&geo), winter(), &mut findings);
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_timezone_mismatch_severity() {
+        let geo = geo("DE", "Europe/Berlin");
+
+        let mut findings = Vec::new();
+        ConsistencyDetector::check_timezone(
+            "America/New_York",
+            Some(&geo),
+            winter(),
+            &mut findings,
+        );
+        assert_eq!(names(&findings), vec!["timezone_mismatch"]);
+        assert_eq!(findings[0].severity, Severity::High);
+
+        let mut findings = Vec::new();
+        ConsistencyDetector::check_timezone("Europe/London", Some(&geo), winter(), &mut findings);
+        assert_eq!(findings[0].severity, Severity::Medium);
+    }
+
+    #[test]
+    fn test_invalid_timezone() {
+        let mut findings = Vec::new();
+        ConsistencyDetector::check_timezone("Mars/Olympus", None, winter(), &mut findings);
+        assert_eq!(names(&findings), vec!["invalid_timezone"]);
+    }
+
+    #[test]
+    fn test_timezone_skipped_without_geo() {
+        let mut findings = Vec::new();
+        ConsistencyDetector::check_timezone("Asia/Tokyo", None, winter(), &mut findings);
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_consistent_languages() {
+        let mut findings = Vec::new();
+        let accepted = parse_accept_language("de-DE,de;q=0.9,en;q=0.8");
+        let geo = geo("DE", "Europe/Berlin");
+        ConsistencyDetector::check_languages("de-DE", Some(&accepted), Some(&geo), &mut findings);
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_accept_language_mismatch() {
+        let mut findings = Vec::new();
+        let accepted = parse_accept_language("en-US,en;q=0.9");
+        ConsistencyDetector::check_languages("ru-RU", Some(&accepted), None, &mut findings);
+        assert_eq!(names(&findings), vec!["accept_language_mismatch"]);
+    }
+
+    #[test]
+    fn test_language_country_mismatch() {
+        let mut findings = Vec::new();
+        let accepted = parse_accept_language("zh-CN,zh;q=0.9");
+        let geo = geo("BR", "America/Sao_Paulo");
+        ConsistencyDetector::check_languages("zh-CN", Some(&accepted), Some(&geo), &mut findings);
+        assert_eq!(names(&findings), vec!["language_country_mismatch"]);
+
+        // English is plausible anywhere
+        let mut findings = Vec::new();
+        let accepted = parse_accept_language("en-US");
+        ConsistencyDetector::check_languages("en-US", Some(&accepted), Some(&geo), &mut findings);
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_missing_accept_language() {
+        let mut findings = Vec::new();
+        ConsistencyDetector::check_languages("en-US", None, None, &mut findings);
+        assert_eq!(names(&findings), vec!["missing_accept_language"]);
+    }
+
+    fn session_with_headers(language: &str, headers: Vec<Header>) -> Session {
+        let mut session = test_util::session();
+        session.network.headers = headers;
+        session.browser.language = language.to_string();
+        session
+    }
+
+    #[test]
+    fn test_client_reported_accept_language_is_ignored() {
+        // The client claims a header matching navigator.language, but the
+        // request carried another one
+        let session = session_with_headers(
+            "en-US",
+            vec![
+                Header::new("accept-language", "en-US,en;q=0.9"),
+                Header::observed("accept-language", "ru-RU,ru;q=0.9"),
+            ],
+        );
+        let findings = ConsistencyDetector::detect(&session, None);
+        assert_eq!(names(&findings), vec!["accept_language_mismatch"]);
+
+        // A client-reported header alone does not count as present
+        let session = session_with_headers("en-US", vec![Header::new("accept-language", "en-US")]);
+        let findings = ConsistencyDetector::detect(&session, None);
+        assert_eq!(names(&findings), vec!["missing_accept_language"]);
+    }
+
+    #[test]
+    fn test_parse_accept_language() {
+        assert_eq!(
+            parse_accept_language("fr-CH, fr;q=0.9, en;q=0.8, de;q=0, *;q=0.5"),
+            vec!["fr", "en"]
+        );
+        assert!(parse_accept_language("").is_empty());
+    }
+}
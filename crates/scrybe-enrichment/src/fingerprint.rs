@@ -2,7 +2,10 @@
 
 use blake3::Hasher;
 use scrybe_core::{
-    types::{Fingerprint, FingerprintComponents, Session},
+    types::{
+        Fingerprint, FingerprintComponents, Session, WebGlInfo, MAX_WEBGL_EXTENSIONS,
+        MAX_WEBGL_SHADER_PRECISIONS,
+    },
     ScrybeError,
 };
 use sha2::{Digest, Sha256};
@@ -24,6 +27,7 @@ impl FingerprintGenerator {
         let components = FingerprintComponents {
             canvas: session.browser.canvas_hash.clone(),
             webgl: session.browser.webgl_hash.clone(),
+            webgl_params: session.browser.webgl.as_ref().map(Self::hash_webgl_params),
             audio: session.browser.audio_hash.clone(),
             fonts: Some(Self::hash_fonts(&session.browser.fonts)),
             plugins: Some(Self::hash_plugins(&session.browser.plugins)),
@@ -51,6 +55,9 @@ impl FingerprintGenerator {
         if let Some(ref webgl) = components.webgl {
             hasher.update(webgl.as_bytes());
         }
+        if let Some(ref webgl_params) = components.webgl_params {
+            hasher.update(webgl_params.as_bytes());
+        }
         if let Some(ref audio) = components.audio {
             hasher.update(audio.as_bytes());
         }
@@ -88,6 +95,43 @@ impl FingerprintGenerator {
         hasher.finalize().to_hex().to_string()
     }
 
+    /// Hash structured WebGL parameters using BLAKE3.
+    ///
+    /// Extensions are sorted so enumeration order does not matter. Lists are
+    /// capped at their `MAX_*` limits.
+    fn hash_webgl_params(webgl: &WebGlInfo) -> String {
+        let mut hasher = Hasher::new();
+        for value in [&webgl.unmasked_vendor, &webgl.unmasked_renderer] {
+            hasher.update(value.as_deref().unwrap_or("").as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.update(&webgl.max_texture_size.unwrap_or(0).to_le_bytes());
+
+        let mut extensions: Vec<&str> = webgl
+            .extensions
+            .iter()
+            .take(MAX_WEBGL_EXTENSIONS)
+            .map(String::as_str)
+            .collect();
+        extensions.sort_unstable();
+        for extension in extensions {
+            hasher.update(extension.as_bytes());
+            hasher.update(&[0]);
+        }
+
+        for format in webgl
+            .shader_precisions
+            .iter()
+            .take(MAX_WEBGL_SHADER_PRECISIONS)
+        {
+            hasher.update(&[format.shader as u8, format.precision_type as u8]);
+            hasher.update(&format.range_min.to_le_bytes());
+            hasher.update(&format.range_max.to_le_bytes());
+            hasher.update(&format.precision.to_le_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
     /// Hash screen info using BLAKE3.
     fn hash_screen(screen: &scrybe_core::types::ScreenInfo) -> String {
         let mut hasher = Hasher::new();
@@ -124,8 +168,8 @@ impl FingerprintGenerator {
             total_weight += 0.25;
         }
 
-        // WebGL fingerprint (weight: 0.25)
-        if components.webgl.is_some() {
+        // WebGL fingerprint or parameters (weight: 0.25)
+        if components.webgl.is_some() || components.webgl_params.is_some() {
             signal_count += 1;
             total_weight += 0.25;
         }
@@ -171,10 +215,48 @@ impl FingerprintGenerator {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+
+    fn create_test_session() -> Session {
+        let mut session = test_util::session();
+        session.browser.canvas_hash = Some("canvas".to_string());
+        session.browser.webgl_hash = Some("webgl".to_string());
+        session.browser.fonts = vec!["Arial".to_string()];
+        session
+    }
+
     #[test]
     fn test_fingerprint_generation() {
-        // Test will be implemented when we have full session creation
-        // For now, just ensure the module compiles
-        assert!(true);
+        let session = create_test_session();
+        let first = FingerprintGenerator::generate(&session).unwrap();
+        let second = FingerprintGenerator::generate(&session).unwrap();
+
+        assert_eq!(first.hash, second.hash);
+        assert_eq!(first.components.canvas.as_deref(), Some("canvas"));
+        assert!(first.confidence > 0.0 && first.confidence <= 1.0);
+    }
+
+    #[test]
+    fn test_webgl_params_component() {
+        let mut session = create_test_session();
+        let without = FingerprintGenerator::generate(&session).unwrap();
+        assert!(without.components.webgl_params.is_none());
+
+        session.browser.webgl = Some(WebGlInfo {
+            unmasked_renderer: Some("ANGLE (Intel, D3D11)".to_string()),
+            extensions: vec!["EXT_a".to_string(), "EXT_b".to_string()],
+            ..WebGlInfo::default()
+        });
+        let with = FingerprintGenerator::generate(&session).unwrap();
+        assert!(with.components.webgl_params.is_some());
+        assert_ne!(with.hash, without.hash);
+
+        // Extension order does not change the fingerprint
+        if let Some(webgl) = session.browser.webgl.as_mut() {
+            webgl.extensions.reverse();
+        }
+        let reordered = FingerprintGenerator::generate(&session).unwrap();
+        assert_eq!(reordered.hash, with.hash);
     }
 }
@@ -0,0 +1,279 @@
+//! GeoIP and ASN enrichment from local MaxMind databases.
+//!
+//! Databases are read from local `.mmdb` files only; there are no network
+//! lookups at runtime. Lookups never touch the disk: run
+//! [`spawn_reload`](crate::spawn_reload) to swap in databases changed on
+//! disk, so updates do not require a restart.
+
+use crate::reload::{FileVersion, Reload};
+use maxminddb::{geoip2, Reader};
+use scrybe_core::{types::GeoInfo, ScrybeError};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Resolves geolocation and ASN data for client IPs.
+pub struct GeoEnricher {
+    city: Option<MmdbSource>,
+    asn: Option<MmdbSource>,
+}
+
+impl GeoEnricher {
+    /// Open local City and/or ASN databases.
+    ///
+    /// # Arguments
+    ///
+    /// * `city_db` - Path to a GeoIP2/GeoLite2 City database
+    /// * `asn_db` - Path to a GeoLite2 ASN database
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if a database cannot be read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use scrybe_enrichment::GeoEnricher;
+    /// # use std::path::Path;
+    /// # fn example() -> Result<(), scrybe_core::ScrybeError> {
+    /// let geo = GeoEnricher::open(
+    ///     Some(Path::new("/var/lib/scrybe/GeoLite2-City.mmdb")),
+    ///     Some(Path::new("/var/lib/scrybe/GeoLite2-ASN.mmdb")),
+    /// )?;
+    /// let info = geo.lookup("81.2.69.142".parse().unwrap());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open(city_db: Option<&Path>, asn_db: Option<&Path>) -> Result<Self, ScrybeError> {
+        Ok(Self {
+            city: city_db.map(MmdbSource::open).transpose()?,
+            asn: asn_db.map(MmdbSource::open).transpose()?,
+        })
+    }
+
+    /// Whether any database is configured.
+    pub fn is_empty(&self) -> bool {
+        self.city.is_none() && self.asn.is_none()
+    }
+
+    /// Look up an IP address.
+    ///
+    /// Returns `None` if no database has data for the address.
+    pub fn lookup(&self, ip: IpAddr) -> Option<GeoInfo> {
+        let mut geo = GeoInfo::default();
+
+        if let Some(reader) = self.city.as_ref().and_then(MmdbSource::reader) {
+            if let Ok(record) = reader.lookup::<geoip2::City<'_>>(ip) {
+                Self::fill_city(&mut geo, &record);
+            }
+        }
+
+        if let Some(reader) = self.asn.as_ref().and_then(MmdbSource::reader) {
+            if let Ok(record) = reader.lookup::<geoip2::Asn<'_>>(ip) {
+                geo.asn = record.autonomous_system_number;
+                geo.asn_org = record.autonomous_system_organization.map(str::to_string);
+            }
+        }
+
+        (!geo.is_empty()).then_some(geo)
+    }
+
+    /// Copy City record fields into `geo`.
+    fn fill_city(geo: &mut GeoInfo, record: &geoip2::City<'_>) {
+        if let Some(country) = &record.country {
+            geo.country_code = country.iso_code.map(str::to_string);
+            geo.country = english_name(&country.names);
+        }
+        if let Some(city) = &record.city {
+            geo.city = english_name(&city.names);
+        }
+        if let Some(location) = &record.location {
+            geo.latitude = location.latitude;
+            geo.longitude = location.longitude;
+            geo.timezone = location.time_zone.map(str::to_string);
+        }
+    }
+}
+
+impl Reload for GeoEnricher {
+    fn name(&self) -> &'static str {
+        "GeoIP databases"
+    }
+
+    /// Reload any database whose file changed since it was loaded.
+    ///
+    /// Returns `true` if at least one database was reloaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if a changed file cannot be
+    /// read. The previously loaded database stays in use.
+    fn reload_if_changed(&self) -> Result<bool, ScrybeError> {
+        let mut reloaded = false;
+        for source in [&self.city, &self.asn].into_iter().flatten() {
+            reloaded |= source.reload_if_changed()?;
+        }
+        Ok(reloaded)
+    }
+}
+
+/// Pick the English name from a MaxMind names map.
+fn english_name(names: &Option<std::collections::BTreeMap<&str, &str>>) -> Option<String> {
+    names
+        .as_ref()
+        .and_then(|n| n.get("en"))
+        .map(|name| name.to_string())
+}
+
+/// A MaxMind database file that is reloaded when it changes on disk.
+struct MmdbSource {
+    path: PathBuf,
+    state: RwLock<LoadedDatabase>,
+}
+
+/// A loaded database and the file version it was loaded from.
+struct LoadedDatabase {
+    reader: Arc<Reader<Vec<u8>>>,
+    version: FileVersion,
+}
+
+impl MmdbSource {
+    /// Load the database at `path`.
+    fn open(path: &Path) -> Result<Self, ScrybeError> {
+        let (reader, version) = Self::load(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            state: RwLock::new(LoadedDatabase {
+                reader: Arc::new(reader),
+                version,
+            }),
+        })
+    }
+
+    /// Get the current reader.
+    fn reader(&self) -> Option<Arc<Reader<Vec<u8>>>> {
+        let state = self.state.read().ok()?;
+        Some(Arc::clone(&state.reader))
+    }
+
+    /// Reload the database if the file changed.
+    ///
+    /// The file is read before taking the lock, so lookups only wait for
+    /// the swap.
+    fn reload_if_changed(&self) -> Result<bool, ScrybeError> {
+        let loaded = self
+            .state
+            .read()
+            .map_err(|_| ScrybeError::enrichment_error("geoip", "database lock poisoned"))?
+            .version;
+        if FileVersion::of(&self.path, "geoip")? == loaded {
+            return Ok(false);
+        }
+
+        let (reader, version) = Self::load(&self.path)?;
+        let mut state = self
+            .state
+            .write()
+            .map_err(|_| ScrybeError::enrichment_error("geoip", "database lock poisoned"))?;
+        state.reader = Arc::new(reader);
+        state.version = version;
+        Ok(true)
+    }
+
+    /// Read the database file and its version.
+    fn load(path: &Path) -> Result<(Reader<Vec<u8>>, FileVersion), ScrybeError> {
+        let version = FileVersion::of(path, "geoip")?;
+        let reader = Reader::open_readfile(path).map_err(|e| {
+            ScrybeError::enrichment_error(
+                "geoip",
+                format!("Failed to open {}: {}", path.display(), e),
+            )
+        })?;
+        Ok((reader, version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/geo")
+            .join(name)
+    }
+
+    fn fixture_enricher() -> GeoEnricher {
+        GeoEnricher::open(
+            Some(&fixture("GeoLite2-City-Test.mmdb")),
+            Some(&fixture("GeoLite2-ASN-Test.mmdb")),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_lookup_ipv4_city_and_asn() {
+        let geo = fixture_enricher()
+            .lookup("81.2.69.142".parse().unwrap())
+            .unwrap();
+
+        assert_eq!(geo.country_code.as_deref(), Some("GB"));
+        assert_eq!(geo.country.as_deref(), Some("United Kingdom"));
+        assert_eq!(geo.city.as_deref(), Some("London"));
+        assert_eq!(geo.timezone.as_deref(), Some("Europe/London"));
+        assert_eq!(geo.asn, Some(20712));
+        assert_eq!(geo.asn_org.as_deref(), Some("Andrews & Arnold Ltd"));
+        assert!(geo.latitude.is_some() && geo.longitude.is_some());
+    }
+
+    #[test]
+    fn test_lookup_ipv6() {
+        let geo = fixture_enricher()
+            .lookup("2001:480::1".parse().unwrap())
+            .unwrap();
+        assert_eq!(geo.country_code.as_deref(), Some("US"));
+        assert_eq!(geo.asn, Some(6939));
+    }
+
+    #[test]
+    fn test_lookup_asn_only() {
+        let geo = fixture_enricher()
+            .lookup("1.128.0.1".parse().unwrap())
+            .unwrap();
+        assert_eq!(geo.asn, Some(1221));
+        assert!(geo.country_code.is_none());
+    }
+
+    #[test]
+    fn test_lookup_unknown_address() {
+        assert!(fixture_enricher()
+            .lookup("10.0.0.1".parse().unwrap())
+            .is_none());
+    }
+
+    #[test]
+    fn test_open_missing_file_fails() {
+        let result = GeoEnricher::open(Some(Path::new("/nonexistent/city.mmdb")), None);
+        assert!(matches!(result, Err(ScrybeError::EnrichmentError { .. })));
+    }
+
+    #[test]
+    fn test_reload_on_file_change() {
+        let dir = std::env::temp_dir().join(format!("scrybe-geo-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("city.mmdb");
+
+        // Start with a database that has no City data for the address.
+        std::fs::copy(fixture("GeoLite2-ASN-Test.mmdb"), &path).unwrap();
+        let geo = GeoEnricher::open(Some(&path), None).unwrap();
+        let ip: IpAddr = "81.2.69.142".parse().unwrap();
+        assert!(geo.lookup(ip).is_none());
+        assert!(!geo.reload_if_changed().unwrap());
+
+        std::fs::copy(fixture("GeoLite2-City-Test.mmdb"), &path).unwrap();
+        assert!(geo.reload_if_changed().unwrap());
+        assert_eq!(geo.lookup(ip).unwrap().city.as_deref(), Some("London"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -5,7 +5,16 @@
 //! ## Features
 //!
 //! - Composite fingerprint generation (SHA-256)
-//! - GeoIP enrichment
+//! - Synthetic mouse movement detection
+//! - Timing-metric plausibility analysis
+//! - GeoIP and ASN enrichment (local MaxMind databases)
+//! - IP reputation classification (datacenter, VPN, Tor, proxy lists)
+//! - Timezone/language/geo consistency checks
+//! - Headless browser and automation framework detection
+//! - WebGL renderer/platform consistency checks
+//! - Canvas/audio noise detection (anti-detect browsers)
+//! - Sessions-per-fingerprint and fingerprints-per-network velocity rules
+//! - Pipeline executor with stage dependencies, timeouts and graceful degradation
 //! - Similarity detection
 //! - Anomaly detection
 //!
@@ -19,7 +28,30 @@
 #![warn(rust_2018_idioms)]
 #![deny(unsafe_code)]
 
+pub mod automation;
+pub mod consistency;
 pub mod fingerprint;
+pub mod geo;
+pub mod movement;
+pub mod noise;
+pub mod pipeline;
+pub mod reload;
+pub mod reputation;
+pub mod stages;
+pub mod timing;
+pub mod velocity;
+pub mod webgl;
 
 // Re-export main types
+pub use automation::AutomationDetector;
+pub use consistency::ConsistencyDetector;
 pub use fingerprint::FingerprintGenerator;
+pub use geo::GeoEnricher;
+pub use movement::MovementDetector;
+pub use noise::{CanvasPopulationDetector, RenderNoiseDetector};
+pub use pipeline::{EnrichmentStage, Pipeline, PipelineReport, SessionUpdate, StageStatus};
+pub use reload::{spawn_reload, Reload};
+pub use reputation::{IpReputationDb, ReputationLists};
+pub use timing::{SiteBaseline, TimingAnalyzer};
+pub use velocity::{VelocityCounts, VelocityDetector};
+pub use webgl::WebGlDetector;
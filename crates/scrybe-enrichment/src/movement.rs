@@ -0,0 +1,373 @@
+//! Synthetic mouse movement detection.
+//!
+//! Automation frameworks drive the pointer programmatically, which leaves
+//! patterns that human input never produces: clicks that "teleport" to
+//! their target, perfectly regular event intervals, constant step sizes,
+//! and timestamps that run backwards.
+
+use scrybe_core::types::{
+    BehavioralSignals, ClickEvent, Finding, MouseEvent, MouseEventType, Severity,
+};
+
+/// Minimum number of movement events before statistical checks run.
+const MIN_MOVES_FOR_ANALYSIS: usize = 5;
+
+/// Radius (px) within which a move must land for a click to be "approached".
+const APPROACH_RADIUS_PX: i64 = 50;
+
+/// How far back (ms) to look for a move approaching a click.
+const APPROACH_WINDOW_MS: u64 = 2_000;
+
+/// Detects scripted mouse paths in behavioral signals.
+pub struct MovementDetector;
+
+impl MovementDetector {
+    /// Run all movement checks and return the resulting findings.
+    ///
+    /// Findings produced:
+    /// - `non_monotonic_timestamps`: event timestamps go backwards
+    /// - `teleport_click`: clicks at coordinates no mouse move approached
+    /// - `constant_interval`: zero variance between move timestamps
+    /// - `uniform_step`: every move advances by the identical (dx, dy)
+    /// - `constant_acceleration`: identical second differences, as produced
+    ///   by sampling a Bezier curve at uniform steps
+    pub fn detect(signals: &BehavioralSignals) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        let moves: Vec<&MouseEvent> = signals
+            .mouse_events
+            .iter()
+            .filter(|e| e.event_type == MouseEventType::Move)
+            .collect();
+
+        if let Some(finding) = Self::check_monotonic(signals) {
+            findings.push(finding);
+        }
+        if let Some(finding) = Self::check_teleport_clicks(&moves, &signals.click_events) {
+            findings.push(finding);
+        }
+
+        if moves.len() >= MIN_MOVES_FOR_ANALYSIS {
+            if let Some(finding) = Self::check_constant_interval(&moves) {
+                findings.push(finding);
+            }
+            if let Some(finding) = Self::check_uniform_step(&moves) {
+                findings.push(finding);
+            } else if let Some(finding) = Self::check_constant_acceleration(&moves) {
+                findings.push(finding);
+            }
+        }
+
+        findings
+    }
+
+    /// Flag event streams whose timestamps decrease.
+    fn check_monotonic(signals: &BehavioralSignals) -> Option<Finding> {
+        let mouse = count_regressions(signals.mouse_events.iter().map(|e| e.timestamp_ms));
+        let scroll = count_regressions(signals.scroll_events.iter().map(|e| e.timestamp_ms));
+        let click = count_regressions(signals.click_events.iter().map(|e| e.timestamp_ms));
+        let total = mouse + scroll + click;
+
+        if total == 0 {
+            return None;
+        }
+
+        Some(Finding::new(
+            "non_monotonic_timestamps",
+            Severity::High,
+            format!(
+                "{} timestamp regressions (mouse={}, scroll={}, click={})",
+                total, mouse, scroll, click
+            ),
+        ))
+    }
+
+    /// Flag clicks that no preceding mouse move came near.
+    fn check_teleport_clicks(moves: &[&MouseEvent], clicks: &[ClickEvent]) -> Option<Finding> {
+        let teleports = clicks
+            .iter()
+            .filter(|click| !Self::is_approached(moves, click))
+            .count();
+
+        if teleports == 0 {
+            return None;
+        }
+
+        // Without any movement at all the clicks may come from touch input,
+        // so the finding is weaker than when movement exists but never
+        // reaches the click target.
+        let severity = if moves.is_empty() {
+            Severity::Medium
+        } else {
+            Severity::High
+        };
+
+        Some(Finding::new(
+            "teleport_click",
+            severity,
+            format!(
+                "{} of {} clicks had no mouse movement within {}px",
+                teleports,
+                clicks.len(),
+                APPROACH_RADIUS_PX
+            ),
+        ))
+    }
+
+    /// Check whether a move landed near the click shortly before it.
+    fn is_approached(moves: &[&MouseEvent], click: &ClickEvent) -> bool {
+        let window_start = click.timestamp_ms.saturating_sub(APPROACH_WINDOW_MS);
+        moves.iter().any(|m| {
+            m.timestamp_ms >= window_start
+                && m.timestamp_ms <= click.timestamp_ms
+                && distance_squared(m.x, m.y, click.x, click.y)
+                    <= APPROACH_RADIUS_PX * APPROACH_RADIUS_PX
+        })
+    }
+
+    /// Flag movement sampled at perfectly regular intervals.
+    fn check_constant_interval(moves: &[&MouseEvent]) -> Option<Finding> {
+        let first = moves[1].timestamp_ms.checked_sub(moves[0].timestamp_ms)?;
+        let constant = moves
+            .windows(2)
+            .all(|w| w[1].timestamp_ms.checked_sub(w[0].timestamp_ms) == Some(first));
+
+        if !constant {
+            return None;
+        }
+
+        Some(Finding::new(
+            "constant_interval",
+            Severity::High,
+            format!("{} moves spaced exactly {}ms apart", moves.len(), first),
+        ))
+    }
+
+    /// Flag movement that advances by the same step every event.
+    fn check_uniform_step(moves: &[&MouseEvent]) -> Option<Finding> {
+        let step = delta(moves[0], moves[1]);
+        if step == (0, 0) {
+            return None;
+        }
+
+        let uniform = moves.windows(2).all(|w| delta(w[0], w[1]) == step);
+
+        if !uniform {
+            return None;
+        }
+
+        Some(Finding::new(
+            "uniform_step",
+            Severity::High,
+            format!(
+                "{} moves with identical step ({}, {})",
+                moves.len(),
+                step.0,
+                step.1
+            ),
+        ))
+    }
+
+    /// Flag movement whose second differences never change.
+    fn check_constant_acceleration(moves: &[&MouseEvent]) -> Option<Finding> {
+        // Coordinates are client-controlled, so widen before combining
+        let second_difference = |w: &[&MouseEvent]| {
+            let (x0, y0) = (i64::from(w[0].x), i64::from(w[0].y));
+            let (x1, y1) = (i64::from(w[1].x), i64::from(w[1].y));
+            let (x2, y2) = (i64::from(w[2].x), i64::from(w[2].y));
+            (x2 - 2 * x1 + x0, y2 - 2 * y1 + y0)
+        };
+
+        let first = second_difference(&moves[0..3]);
+        if first == (0, 0) {
+            return None;
+        }
+
+        let constant = moves.windows(3).all(|w| second_difference(w) == first);
+
+        if !constant {
+            return None;
+        }
+
+        Some(Finding::new(
+            "constant_acceleration",
+            Severity::Medium,
+            format!(
+                "{} moves with identical acceleration ({}, {})",
+                moves.len(),
+                first.0,
+                first.1
+            ),
+        ))
+    }
+}
+
+/// Count positions where a timestamp is smaller than its predecessor.
+fn count_regressions(timestamps: impl Iterator<Item = u64>) -> usize {
+    let mut previous: Option<u64> = None;
+    let mut regressions = 0;
+
+    for ts in timestamps {
+        if previous.is_some_and(|p| ts < p) {
+            regressions += 1;
+        }
+        previous = Some(ts);
+    }
+
+    regressions
+}
+
+/// Step from one move to the next, widened so extreme client-supplied
+/// coordinates cannot overflow.
+fn delta(from: &MouseEvent, to: &MouseEvent) -> (i64, i64) {
+    (
+        i64::from(to.x) - i64::from(from.x),
+        i64::from(to.y) - i64::from(from.y),
+    )
+}
+
+/// Squared Euclidean distance between two points, saturating for points
+/// at opposite ends of the `i32` range.
+fn distance_squared(x1: i32, y1: i32, x2: i32, y2: i32) -> i64 {
+    let dx = i64::from(x1) - i64::from(x2);
+    let dy = i64::from(y1) - i64::from(y2);
+    dx.saturating_mul(dx).saturating_add(dy.saturating_mul(dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::types::{MouseButton, TimingMetrics};
+
+    fn mouse_move(timestamp_ms: u64, x: i32, y: i32) -> MouseEvent {
+        MouseEvent {
+            timestamp_ms,
+            x,
+            y,
+            event_type: MouseEventType::Move,
+        }
+    }
+
+    fn click(timestamp_ms: u64, x: i32, y: i32) -> ClickEvent {
+        ClickEvent {
+            timestamp_ms,
+            x,
+            y,
+            button: MouseButton::Left,
+        }
+    }
+
+    fn signals(mouse_events: Vec<MouseEvent>, click_events: Vec<ClickEvent>) -> BehavioralSignals {
+        BehavioralSignals {
+            mouse_events,
+            scroll_events: vec![],
+            click_events,
+            timing: TimingMetrics::default(),
+        }
+    }
+
+    fn names(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    /// Irregular, human-like path ending at the click target.
+    fn human_path() -> Vec<MouseEvent> {
+        vec![
+            mouse_move(100, 10, 12),
+            mouse_move(117, 25, 30),
+            mouse_move(131, 47, 41),
+            mouse_move(152, 80, 66),
+            mouse_move(166, 102, 90),
+            mouse_move(190, 118, 97),
+            mouse_move(203, 121, 99),
+        ]
+    }
+
+    #[test]
+    fn test_human_path_has_no_findings() {
+        let findings = MovementDetector::detect(&signals(human_path(), vec![click(250, 122, 100)]));
+        assert!(findings.is_empty(), "unexpected findings: {:?}", findings);
+    }
+
+    #[test]
+    fn test_teleport_click_detected() {
+        let findings = MovementDetector::detect(&signals(human_path(), vec![click(250, 800, 600)]));
+        let teleport = findings
+            .iter()
+            .find(|f| f.name == "teleport_click")
+            .unwrap();
+        assert_eq!(teleport.severity, Severity::High);
+    }
+
+    #[test]
+    fn test_click_without_any_movement_is_medium() {
+        let findings = MovementDetector::detect(&signals(vec![], vec![click(250, 800, 600)]));
+        let teleport = findings
+            .iter()
+            .find(|f| f.name == "teleport_click")
+            .unwrap();
+        assert_eq!(teleport.severity, Severity::Medium);
+    }
+
+    #[test]
+    fn test_linear_constant_motion_detected() {
+        let moves = (0..10)
+            .map(|i| mouse_move(i * 16, i as i32 * 5, i as i32 * 3))
+            .collect();
+        let findings = MovementDetector::detect(&signals(moves, vec![]));
+        let names = names(&findings);
+        assert!(names.contains(&"constant_interval"));
+        assert!(names.contains(&"uniform_step"));
+    }
+
+    #[test]
+    fn test_bezier_regularity_detected() {
+        // Quadratic curve sampled at uniform t: constant second difference.
+        let moves = (0..10)
+            .map(|i: i32| mouse_move(100 + (i as u64) * 13 + (i as u64 % 3), i * i, 4 * i))
+            .collect();
+        let findings = MovementDetector::detect(&signals(moves, vec![]));
+        assert_eq!(names(&findings), vec!["constant_acceleration"]);
+    }
+
+    #[test]
+    fn test_non_monotonic_timestamps_detected() {
+        let mut moves = human_path();
+        moves[3].timestamp_ms = 50;
+        let findings = MovementDetector::detect(&signals(moves, vec![]));
+        assert!(names(&findings).contains(&"non_monotonic_timestamps"));
+    }
+
+    #[test]
+    fn test_too_few_moves_skip_statistical_checks() {
+        let moves = (0..3)
+            .map(|i| mouse_move(i * 16, i as i32 * 5, 0))
+            .collect();
+        let findings = MovementDetector::detect(&signals(moves, vec![]));
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_extreme_coordinates_do_not_overflow() {
+        // Alternating between the ends of the i32 range overflows narrow
+        // arithmetic; the steps and accelerations must still compare as
+        // different rather than wrapping into equal values
+        let moves = (0..10)
+            .map(|i| {
+                let (x, y) = if i % 2 == 0 {
+                    (i32::MIN, i32::MAX)
+                } else {
+                    (i32::MAX, i32::MIN)
+                };
+                mouse_move(100 + i * 17 + i % 3, x, y)
+            })
+            .collect();
+        let findings =
+            MovementDetector::detect(&signals(moves, vec![click(300, i32::MIN, i32::MIN)]));
+
+        let names = names(&findings);
+        assert!(!names.contains(&"uniform_step"));
+        assert!(!names.contains(&"constant_acceleration"));
+        assert!(names.contains(&"teleport_click"));
+    }
+}
@@ -0,0 +1,220 @@
+//! Canvas and audio fingerprint noise detection.
+//!
+//! Anti-detect browsers add random noise to canvas and audio output so each
+//! page load yields a new hash. Two checks catch this:
+//!
+//! - Per session: the SDK renders the same canvas/audio twice, and a real
+//!   browser produces identical hashes both times.
+//! - Per population: among sessions whose other signals are identical, a
+//!   real device model shares a handful of canvas hashes, while noised
+//!   canvases are almost all seen exactly once. Group counts are kept in
+//!   Redis ([`CanvasPopulations`](scrybe_cache::CanvasPopulations)) and
+//!   shared by every worker.
+
+use blake3::Hasher;
+use scrybe_cache::CanvasGroupStats;
+use scrybe_core::types::{BrowserSignals, EnrichedSession, Finding, Severity};
+
+/// Sessions required in a group before population checks apply.
+const MIN_GROUP_SESSIONS: u64 = 20;
+
+/// Share of a group's sessions with a never-repeated canvas hash above
+/// which the group's singletons are reported.
+const SINGLETON_RATIO_THRESHOLD: f64 = 0.5;
+
+/// Detects canvas/audio noise within a single session.
+pub struct RenderNoiseDetector;
+
+impl RenderNoiseDetector {
+    /// Compare repeated renders.
+    ///
+    /// # Findings
+    ///
+    /// - `canvas_render_noise` (High): repeated canvas renders differ
+    /// - `audio_render_noise` (High): repeated audio renders differ
+    pub fn detect(browser: &BrowserSignals) -> Vec<Finding> {
+        let pairs = [
+            (
+                "canvas_render_noise",
+                "canvas",
+                &browser.canvas_hash,
+                &browser.canvas_hash_repeat,
+            ),
+            (
+                "audio_render_noise",
+                "audio",
+                &browser.audio_hash,
+                &browser.audio_hash_repeat,
+            ),
+        ];
+
+        pairs
+            .into_iter()
+            .filter_map(|(name, kind, first, repeat)| match (first, repeat) {
+                (Some(first), Some(repeat)) if first != repeat => Some(Finding::new(
+                    name,
+                    Severity::High,
+                    format!("two identical {} renders hashed differently", kind),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Detects canvas hashes that never repeat across otherwise identical
+/// fingerprints.
+///
+/// Sessions are grouped by every fingerprint signal except canvas, audio
+/// and network, so noised browsers behind rotating proxies still land in
+/// the same group. Run after fingerprint generation.
+pub struct CanvasPopulationDetector;
+
+impl CanvasPopulationDetector {
+    /// Check a session's canvas hash against the counts of its group.
+    ///
+    /// # Findings
+    ///
+    /// - `unique_canvas_in_population` (Medium): no other session rendered
+    ///   the canvas hash in a group of at least 20 sessions where most
+    ///   canvas hashes are rendered by a single session
+    pub fn detect(stats: &CanvasGroupStats) -> Vec<Finding> {
+        let ratio = stats.singleton_ratio();
+        if stats.canvas_sessions == 1
+            && stats.sessions >= MIN_GROUP_SESSIONS
+            && ratio >= SINGLETON_RATIO_THRESHOLD
+        {
+            return vec![Finding::new(
+                "unique_canvas_in_population",
+                Severity::Medium,
+                format!(
+                    "canvas hash unseen among {} otherwise identical sessions ({:.0}% unique)",
+                    stats.sessions - 1,
+                    ratio * 100.0
+                ),
+            )];
+        }
+
+        Vec::new()
+    }
+
+    /// Key identifying sessions that match on every signal but canvas,
+    /// audio and network.
+    pub fn group_key(enriched: &EnrichedSession) -> String {
+        let components = enriched
+            .fingerprint
+            .as_ref()
+            .map(|f| f.value.components.clone())
+            .unwrap_or_default();
+        let browser = &enriched.session.browser;
+
+        let mut hasher = Hasher::new();
+        for part in [
+            components.webgl.as_deref(),
+            components.webgl_params.as_deref(),
+            components.fonts.as_deref(),
+            components.plugins.as_deref(),
+            components.screen.as_deref(),
+            Some(browser.user_agent.as_str()),
+            Some(browser.timezone.as_str()),
+            Some(browser.language.as_str()),
+        ] {
+            hasher.update(part.unwrap_or("").as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+
+    fn session(canvas: &str) -> EnrichedSession {
+        let mut enriched = test_util::enriched_session();
+        enriched.session.browser.canvas_hash = Some(canvas.to_string());
+        enriched.session.browser.webgl_hash = Some("webgl".to_string());
+        enriched.session.browser.fonts = vec!["Arial".to_string()];
+        enriched
+    }
+
+    #[test]
+    fn test_repeated_render_matches() {
+        let mut browser = session("abc").session.browser;
+        browser.canvas_hash_repeat = Some("abc".to_string());
+        assert!(RenderNoiseDetector::detect(&browser).is_empty());
+    }
+
+    #[test]
+    fn test_repeated_render_differs() {
+        let mut browser = session("abc").session.browser;
+        browser.canvas_hash_repeat = Some("abd".to_string());
+        browser.audio_hash = Some("a1".to_string());
+        browser.audio_hash_repeat = Some("a2".to_string());
+
+        let names: Vec<_> = RenderNoiseDetector::detect(&browser)
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+        assert_eq!(names, vec!["canvas_render_noise", "audio_render_noise"]);
+    }
+
+    #[test]
+    fn test_missing_repeat_is_not_reported() {
+        let browser = session("abc").session.browser;
+        assert!(RenderNoiseDetector::detect(&browser).is_empty());
+    }
+
+    #[test]
+    fn test_stable_population_not_flagged() {
+        // A device model renders one of three canvas variants
+        let stats = CanvasGroupStats {
+            sessions: 50,
+            canvas_sessions: 17,
+            singletons: 0,
+        };
+        assert!(CanvasPopulationDetector::detect(&stats).is_empty());
+    }
+
+    #[test]
+    fn test_noised_population_flagged() {
+        let stats = |sessions| CanvasGroupStats {
+            sessions,
+            canvas_sessions: 1,
+            singletons: sessions,
+        };
+
+        assert!(CanvasPopulationDetector::detect(&stats(MIN_GROUP_SESSIONS - 1)).is_empty());
+        let names: Vec<_> = CanvasPopulationDetector::detect(&stats(MIN_GROUP_SESSIONS))
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+        assert_eq!(names, vec!["unique_canvas_in_population"]);
+    }
+
+    #[test]
+    fn test_shared_canvas_in_noised_population_not_flagged() {
+        let stats = CanvasGroupStats {
+            sessions: 50,
+            canvas_sessions: 2,
+            singletons: 48,
+        };
+        assert!(CanvasPopulationDetector::detect(&stats).is_empty());
+    }
+
+    #[test]
+    fn test_groups_split_on_other_signals() {
+        let mut other = session("abc");
+        other.session.browser.user_agent = "Other/1.0".to_string();
+
+        assert_eq!(
+            CanvasPopulationDetector::group_key(&session("abc")),
+            CanvasPopulationDetector::group_key(&session("abd"))
+        );
+        assert_ne!(
+            CanvasPopulationDetector::group_key(&session("abc")),
+            CanvasPopulationDetector::group_key(&other)
+        );
+    }
+}
@@ -0,0 +1,628 @@
+//! Enrichment pipeline executor (RFC-0004).
+//!
+//! Stages declare which other stages they depend on. The pipeline orders
+//! them into waves: every stage in a wave has all of its dependencies in
+//! earlier waves, and the stages of one wave run concurrently against the
+//! same snapshot of the session. Their updates are applied in registration
+//! order once the whole wave has finished, and every output they set is
+//! tagged with the stage's name and version.
+//!
+//! Failures degrade gracefully: a stage that errors or times out is recorded
+//! in the report and the session continues through the remaining stages
+//! without its output. Only stages marked critical abort the pipeline.
+
+use async_trait::async_trait;
+use scrybe_core::{
+    types::{EnrichedSession, Provenance, Session},
+    ScrybeError,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// Default per-stage timeout.
+const DEFAULT_STAGE_TIMEOUT: Duration = Duration::from_millis(20);
+
+/// Default overall budget for one session (RFC-0004: < 50ms p99).
+const DEFAULT_BUDGET: Duration = Duration::from_millis(50);
+
+/// Maximum number of stages in a pipeline.
+const MAX_STAGES: usize = 64;
+
+/// Version recorded in the provenance of built-in stages' outputs.
+pub const ENRICHMENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Deferred mutation of a session, given the provenance of the stage's
+/// outputs.
+type ApplyFn = Box<dyn FnOnce(&mut EnrichedSession, Provenance) + Send>;
+
+/// A change to apply to the session once a stage's wave has finished.
+pub struct SessionUpdate(Option<ApplyFn>);
+
+impl SessionUpdate {
+    /// Create an update from a closure.
+    ///
+    /// The closure receives the provenance to attach to any output it sets.
+    pub fn new(apply: impl FnOnce(&mut EnrichedSession, Provenance) + Send + 'static) -> Self {
+        Self(Some(Box::new(apply)))
+    }
+
+    /// An update that changes nothing.
+    pub fn none() -> Self {
+        Self(None)
+    }
+
+    /// Apply the update to a session.
+    fn apply(self, session: &mut EnrichedSession, provenance: Provenance) {
+        if let Some(apply) = self.0 {
+            apply(session, provenance);
+        }
+    }
+}
+
+impl std::fmt::Debug for SessionUpdate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SessionUpdate")
+            .field(&self.0.as_ref().map(|_| ".."))
+            .finish()
+    }
+}
+
+/// A single step of the enrichment pipeline.
+#[async_trait]
+pub trait EnrichmentStage: Send + Sync {
+    /// Unique stage name (e.g., "geo").
+    fn name(&self) -> &'static str;
+
+    /// Names of stages whose output this stage reads.
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether a failure of this stage fails the whole pipeline.
+    fn critical(&self) -> bool {
+        false
+    }
+
+    /// Timeout for this stage, overriding the pipeline default.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Version recorded in the provenance of this stage's outputs.
+    fn version(&self) -> &'static str {
+        ENRICHMENT_VERSION
+    }
+
+    /// Compute this stage's output from the current session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if the stage cannot produce
+    /// its output.
+    async fn run(&self, session: &EnrichedSession) -> Result<SessionUpdate, ScrybeError>;
+}
+
+/// Outcome of a single stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageStatus {
+    /// Stage completed and its update was applied
+    Succeeded,
+    /// Stage returned an error
+    Failed(String),
+    /// Stage exceeded its timeout
+    TimedOut,
+    /// Stage was not started because the overall budget was exhausted
+    Skipped,
+}
+
+/// Latency and outcome of a single stage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageReport {
+    /// Stage name
+    pub name: &'static str,
+    /// Outcome
+    pub status: StageStatus,
+    /// Time spent running the stage
+    pub latency: Duration,
+}
+
+/// Per-stage results of one pipeline run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineReport {
+    /// Stage reports, in execution order
+    pub stages: Vec<StageReport>,
+    /// Total time spent in the pipeline
+    pub total: Duration,
+}
+
+impl PipelineReport {
+    /// Get the report for a stage.
+    pub fn stage(&self, name: &str) -> Option<&StageReport> {
+        self.stages.iter().find(|s| s.name == name)
+    }
+
+    /// Check whether every stage succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.stages
+            .iter()
+            .all(|s| s.status == StageStatus::Succeeded)
+    }
+}
+
+/// Runs enrichment stages in dependency order with timeouts.
+pub struct Pipeline {
+    /// Stages grouped into waves that can run concurrently
+    waves: Vec<Vec<Arc<dyn EnrichmentStage>>>,
+    stage_timeout: Duration,
+    budget: Duration,
+}
+
+impl Pipeline {
+    /// Create a pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `stages` - Stages to run, in any order
+    /// * `stage_timeout` - Default per-stage timeout (default: 20ms)
+    /// * `budget` - Overall time budget per session (default: 50ms)
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if stage names are duplicated, a
+    /// dependency is unknown, or dependencies form a cycle.
+    pub fn new(
+        stages: Vec<Arc<dyn EnrichmentStage>>,
+        stage_timeout: Option<Duration>,
+        budget: Option<Duration>,
+    ) -> Result<Self, ScrybeError> {
+        if stages.len() > MAX_STAGES {
+            return Err(ScrybeError::config_error(format!(
+                "Pipeline has {} stages, maximum is {}",
+                stages.len(),
+                MAX_STAGES
+            )));
+        }
+
+        Ok(Self {
+            waves: Self::plan(stages)?,
+            stage_timeout: stage_timeout.unwrap_or(DEFAULT_STAGE_TIMEOUT),
+            budget: budget.unwrap_or(DEFAULT_BUDGET),
+        })
+    }
+
+    /// Names of the stages in execution order.
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.waves.iter().flatten().map(|s| s.name()).collect()
+    }
+
+    /// Enrich a raw session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if a critical stage fails or
+    /// times out.
+    pub async fn enrich(
+        &self,
+        session: Session,
+    ) -> Result<(EnrichedSession, PipelineReport), ScrybeError> {
+        let mut enriched = EnrichedSession::new(session);
+        let report = self.run(&mut enriched).await?;
+        Ok((enriched, report))
+    }
+
+    /// Run every stage against the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if a critical stage fails or
+    /// times out. Non-critical failures are only recorded in the report.
+    pub async fn run(&self, session: &mut EnrichedSession) -> Result<PipelineReport, ScrybeError> {
+        let started = Instant::now();
+        let deadline = started + self.budget;
+        let mut report = PipelineReport::default();
+
+        for wave in &self.waves {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                for stage in wave {
+                    if stage.critical() {
+                        return Err(ScrybeError::enrichment_error(
+                            stage.name(),
+                            "critical stage skipped: enrichment budget exhausted",
+                        ));
+                    }
+                    report.stages.push(StageReport {
+                        name: stage.name(),
+                        status: StageStatus::Skipped,
+                        latency: Duration::ZERO,
+                    });
+                }
+                continue;
+            }
+
+            let snapshot: &EnrichedSession = session;
+            let results = futures::future::join_all(
+                wave.iter()
+                    .map(|stage| self.run_stage(stage.as_ref(), snapshot, remaining)),
+            )
+            .await;
+
+            for (stage, (result, latency)) in wave.iter().zip(results) {
+                let status = match result {
+                    Ok(update) => {
+                        update.apply(session, Provenance::new(stage.name(), stage.version()));
+                        StageStatus::Succeeded
+                    }
+                    Err(status) => status,
+                };
+
+                if status != StageStatus::Succeeded {
+                    warn!(
+                        "Enrichment stage '{}' did not complete: {:?}",
+                        stage.name(),
+                        status
+                    );
+                    if stage.critical() {
+                        return Err(ScrybeError::enrichment_error(
+                            stage.name(),
+                            format!("critical stage did not complete: {:?}", status),
+                        ));
+                    }
+                }
+
+                report.stages.push(StageReport {
+                    name: stage.name(),
+                    status,
+                    latency,
+                });
+            }
+        }
+
+        session.enriched_at = chrono::Utc::now();
+        report.total = started.elapsed();
+        Ok(report)
+    }
+
+    /// Run one stage with its timeout, capped by the remaining budget.
+    async fn run_stage(
+        &self,
+        stage: &dyn EnrichmentStage,
+        session: &EnrichedSession,
+        remaining: Duration,
+    ) -> (Result<SessionUpdate, StageStatus>, Duration) {
+        let timeout = stage.timeout().unwrap_or(self.stage_timeout).min(remaining);
+        let started = Instant::now();
+
+        let result = match tokio::time::timeout(timeout, stage.run(session)).await {
+            Ok(Ok(update)) => Ok(update),
+            Ok(Err(e)) => Err(StageStatus::Failed(e.to_string())),
+            Err(_) => Err(StageStatus::TimedOut),
+        };
+
+        (result, started.elapsed())
+    }
+
+    /// Group stages into waves in dependency order.
+    fn plan(
+        stages: Vec<Arc<dyn EnrichmentStage>>,
+    ) -> Result<Vec<Vec<Arc<dyn EnrichmentStage>>>, ScrybeError> {
+        let mut names = HashSet::new();
+        for stage in &stages {
+            if !names.insert(stage.name()) {
+                return Err(ScrybeError::config_error(format!(
+                    "Duplicate enrichment stage '{}'",
+                    stage.name()
+                )));
+            }
+        }
+        for stage in &stages {
+            if let Some(missing) = stage.depends_on().iter().find(|d| !names.contains(*d)) {
+                return Err(ScrybeError::config_error(format!(
+                    "Enrichment stage '{}' depends on unknown stage '{}'",
+                    stage.name(),
+                    missing
+                )));
+            }
+        }
+
+        let mut done: HashSet<&'static str> = HashSet::new();
+        let mut pending = stages;
+        let mut waves = Vec::new();
+
+        while !pending.is_empty() {
+            let (ready, blocked): (Vec<_>, Vec<_>) = pending
+                .into_iter()
+                .partition(|s| s.depends_on().iter().all(|d| done.contains(d)));
+
+            if ready.is_empty() {
+                let names: Vec<_> = blocked.iter().map(|s| s.name()).collect();
+                return Err(ScrybeError::config_error(format!(
+                    "Enrichment stage dependencies form a cycle: {}",
+                    names.join(", ")
+                )));
+            }
+
+            done.extend(ready.iter().map(|s| s.name()));
+            waves.push(ready);
+            pending = blocked;
+        }
+
+        Ok(waves)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+    use scrybe_core::types::{BotScore, Enrichment, Finding, Severity};
+
+    fn create_test_session() -> EnrichedSession {
+        test_util::enriched_session()
+    }
+
+    /// Test stage with configurable behavior.
+    struct TestStage {
+        name: &'static str,
+        depends_on: &'static [&'static str],
+        critical: bool,
+        delay: Duration,
+        fail: bool,
+    }
+
+    impl TestStage {
+        fn new(name: &'static str, depends_on: &'static [&'static str]) -> Self {
+            Self {
+                name,
+                depends_on,
+                critical: false,
+                delay: Duration::ZERO,
+                fail: false,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EnrichmentStage for TestStage {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn depends_on(&self) -> &'static [&'static str] {
+            self.depends_on
+        }
+
+        fn critical(&self) -> bool {
+            self.critical
+        }
+
+        async fn run(&self, session: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+            tokio::time::sleep(self.delay).await;
+            if self.fail {
+                return Err(ScrybeError::enrichment_error(self.name, "test failure"));
+            }
+
+            // Record which dependencies were visible when the stage ran
+            let seen: Vec<_> = self
+                .depends_on
+                .iter()
+                .filter(|d| session.evidence.contains(d))
+                .collect();
+            let name = self.name;
+            let description = format!("{:?}", seen);
+            Ok(SessionUpdate::new(move |s, _| {
+                s.evidence
+                    .record(Finding::new(name, Severity::Low, description));
+            }))
+        }
+    }
+
+    fn stages(list: Vec<TestStage>) -> Vec<Arc<dyn EnrichmentStage>> {
+        list.into_iter()
+            .map(|s| Arc::new(s) as Arc<dyn EnrichmentStage>)
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_orders_dependencies() {
+        let pipeline = Pipeline::new(
+            stages(vec![
+                TestStage::new("reputation", &["geo"]),
+                TestStage::new("geo", &[]),
+                TestStage::new("fingerprint", &[]),
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(pipeline.waves.len(), 2);
+        assert_eq!(
+            pipeline.stage_names(),
+            vec!["geo", "fingerprint", "reputation"]
+        );
+    }
+
+    #[test]
+    fn test_plan_rejects_invalid_graphs() {
+        let unknown = Pipeline::new(stages(vec![TestStage::new("a", &["missing"])]), None, None);
+        assert!(matches!(unknown, Err(ScrybeError::ConfigError(_))));
+
+        let cycle = Pipeline::new(
+            stages(vec![
+                TestStage::new("a", &["b"]),
+                TestStage::new("b", &["a"]),
+            ]),
+            None,
+            None,
+        );
+        assert!(matches!(cycle, Err(ScrybeError::ConfigError(_))));
+
+        let duplicate = Pipeline::new(
+            stages(vec![TestStage::new("a", &[]), TestStage::new("a", &[])]),
+            None,
+            None,
+        );
+        assert!(matches!(duplicate, Err(ScrybeError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_dependents_see_dependency_output() {
+        let pipeline = Pipeline::new(
+            stages(vec![
+                TestStage::new("geo", &[]),
+                TestStage::new("reputation", &["geo"]),
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let mut session = create_test_session();
+        let report = pipeline.run(&mut session).await.unwrap();
+
+        assert!(report.is_complete());
+        let reputation = session
+            .evidence
+            .findings
+            .iter()
+            .find(|f| f.name == "reputation")
+            .unwrap();
+        assert_eq!(reputation.description, "[\"geo\"]");
+    }
+
+    #[tokio::test]
+    async fn test_failed_stage_does_not_drop_session() {
+        let mut geo = TestStage::new("geo", &[]);
+        geo.fail = true;
+        let pipeline = Pipeline::new(
+            stages(vec![geo, TestStage::new("fingerprint", &[])]),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let mut session = create_test_session();
+        let report = pipeline.run(&mut session).await.unwrap();
+
+        assert!(matches!(
+            report.stage("geo").unwrap().status,
+            StageStatus::Failed(_)
+        ));
+        assert_eq!(
+            report.stage("fingerprint").unwrap().status,
+            StageStatus::Succeeded
+        );
+        assert!(session.evidence.contains("fingerprint"));
+        assert!(!report.is_complete());
+    }
+
+    #[tokio::test]
+    async fn test_stage_timeout() {
+        let mut slow = TestStage::new("slow", &[]);
+        slow.delay = Duration::from_millis(200);
+        let pipeline = Pipeline::new(
+            stages(vec![slow, TestStage::new("fast", &[])]),
+            Some(Duration::from_millis(10)),
+            Some(Duration::from_secs(1)),
+        )
+        .unwrap();
+
+        let mut session = create_test_session();
+        let report = pipeline.run(&mut session).await.unwrap();
+
+        assert_eq!(report.stage("slow").unwrap().status, StageStatus::TimedOut);
+        assert_eq!(report.stage("fast").unwrap().status, StageStatus::Succeeded);
+        assert!(!session.evidence.contains("slow"));
+    }
+
+    #[tokio::test]
+    async fn test_budget_exhaustion_skips_later_waves() {
+        let mut slow = TestStage::new("slow", &[]);
+        slow.delay = Duration::from_millis(30);
+        let pipeline = Pipeline::new(
+            stages(vec![slow, TestStage::new("after", &["slow"])]),
+            Some(Duration::from_secs(1)),
+            Some(Duration::from_millis(10)),
+        )
+        .unwrap();
+
+        let mut session = create_test_session();
+        let report = pipeline.run(&mut session).await.unwrap();
+
+        assert_eq!(report.stage("slow").unwrap().status, StageStatus::TimedOut);
+        assert_eq!(report.stage("after").unwrap().status, StageStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_critical_failure_fails_pipeline() {
+        let mut fingerprint = TestStage::new("fingerprint", &[]);
+        fingerprint.fail = true;
+        fingerprint.critical = true;
+        let pipeline = Pipeline::new(stages(vec![fingerprint]), None, None).unwrap();
+
+        let mut session = create_test_session();
+        let result = pipeline.run(&mut session).await;
+        assert!(matches!(result, Err(ScrybeError::EnrichmentError { .. })));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_independent_stages_run_concurrently() {
+        let mut a = TestStage::new("a", &[]);
+        a.delay = Duration::from_millis(50);
+        let mut b = TestStage::new("b", &[]);
+        b.delay = Duration::from_millis(50);
+        let pipeline = Pipeline::new(
+            stages(vec![a, b]),
+            Some(Duration::from_secs(1)),
+            Some(Duration::from_secs(1)),
+        )
+        .unwrap();
+
+        let mut session = create_test_session();
+        let report = pipeline.run(&mut session).await.unwrap();
+
+        // One after the other the stages would take 100ms of virtual time
+        assert!(report.is_complete());
+        assert!(report.total < Duration::from_millis(100));
+    }
+
+    /// Stage that sets the bot score.
+    struct ScoreStage;
+
+    #[async_trait]
+    impl EnrichmentStage for ScoreStage {
+        fn name(&self) -> &'static str {
+            "score"
+        }
+
+        fn version(&self) -> &'static str {
+            "model-7"
+        }
+
+        async fn run(&self, _session: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+            Ok(SessionUpdate::new(|s, provenance| {
+                s.bot_score = Some(Enrichment::new(BotScore { probability: 0.9 }, provenance));
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_outputs_carry_provenance() {
+        let pipeline = Pipeline::new(vec![Arc::new(ScoreStage)], None, None).unwrap();
+
+        let (enriched, report) = pipeline
+            .enrich(create_test_session().session)
+            .await
+            .unwrap();
+
+        assert!(report.is_complete());
+        let score = enriched.bot_score.unwrap();
+        assert_eq!(score.value.probability, 0.9);
+        assert_eq!(score.provenance.stage, "score");
+        assert_eq!(score.provenance.version, "model-7");
+    }
+}
@@ -0,0 +1,119 @@
+//! Background reloading of file-backed enrichment data.
+//!
+//! Checking files touches the disk, so it never happens on the enrichment
+//! path: a background task checks each source on an interval, off the async
+//! runtime, and the source swaps in the new data only once it loaded
+//! completely. Lookups keep using the previous data until then.
+
+use scrybe_core::ScrybeError;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Default interval between checks for updated files.
+pub const DEFAULT_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Data loaded from files that can be reloaded when they change.
+pub trait Reload: Send + Sync + 'static {
+    /// Name used in log messages (e.g., "GeoIP databases")
+    fn name(&self) -> &'static str;
+
+    /// Reload if a backing file changed since it was loaded.
+    ///
+    /// Blocks on file I/O. Returns `true` if new data was swapped in.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a changed file cannot be loaded. The previously
+    /// loaded data stays in use.
+    fn reload_if_changed(&self) -> Result<bool, ScrybeError>;
+}
+
+/// Spawn a task checking `source` for changes every `interval`.
+///
+/// Failures are logged and retried at the next check.
+pub fn spawn_reload(source: Arc<dyn Reload>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // The first tick completes immediately; the data was just loaded
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let check = Arc::clone(&source);
+            match tokio::task::spawn_blocking(move || check.reload_if_changed()).await {
+                Ok(Ok(true)) => info!("Reloaded {}", source.name()),
+                Ok(Ok(false)) => {}
+                Ok(Err(e)) => warn!("Failed to reload {}: {}", source.name(), e),
+                Err(e) => warn!("Reloading {} panicked: {}", source.name(), e),
+            }
+        }
+    })
+}
+
+/// Modification time and size identifying a version of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FileVersion {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileVersion {
+    /// Get the current version of the file at `path`.
+    ///
+    /// `stage` names the enrichment stage in the error.
+    pub(crate) fn of(path: &Path, stage: &str) -> Result<Self, ScrybeError> {
+        let metadata = std::fs::metadata(path).map_err(|e| {
+            ScrybeError::enrichment_error(
+                stage,
+                format!("Failed to stat {}: {}", path.display(), e),
+            )
+        })?;
+        Ok(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct Counting(AtomicUsize);
+
+    impl Reload for Counting {
+        fn name(&self) -> &'static str {
+            "test data"
+        }
+
+        fn reload_if_changed(&self) -> Result<bool, ScrybeError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(ScrybeError::enrichment_error("test", "unreadable"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checks_on_interval_and_survives_failures() {
+        let source = Arc::new(Counting(AtomicUsize::new(0)));
+        let task = spawn_reload(source.clone(), Duration::from_millis(20));
+
+        // Nothing is checked at startup
+        assert_eq!(source.0.load(Ordering::SeqCst), 0);
+
+        // A failed check does not stop later ones
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while source.0.load(Ordering::SeqCst) < 2 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        task.abort();
+    }
+}
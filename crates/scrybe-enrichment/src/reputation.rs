@@ -0,0 +1,699 @@
+//! IP reputation classification from local CIDR and ASN lists.
+//!
+//! Lists (cloud provider ranges, Tor exits, VPN ASNs, operator blocklists)
+//! are loaded into binary prefix tries, one per address family, so a lookup
+//! costs at most 32 or 128 node visits regardless of list size.
+//!
+//! [`ReputationLists`] loads the lists from files and rebuilds them when a
+//! file changes, so list updates do not require a restart.
+
+use crate::reload::{FileVersion, Reload};
+use scrybe_core::{
+    types::{Finding, IpCategory, IpReputation, Severity},
+    ScrybeError,
+};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// Index of a loaded list in `IpReputationDb::lists`.
+type ListId = usize;
+
+/// Metadata for a loaded list.
+#[derive(Debug, Clone)]
+struct ListInfo {
+    name: String,
+    category: IpCategory,
+}
+
+/// Classifies IP addresses against loaded reputation lists.
+#[derive(Debug, Default)]
+pub struct IpReputationDb {
+    lists: Vec<ListInfo>,
+    v4: PrefixTrie,
+    v6: PrefixTrie,
+    asns: HashMap<u32, Vec<ListId>>,
+}
+
+impl IpReputationDb {
+    /// Create an empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a CIDR list file.
+    ///
+    /// One entry per line: a CIDR (`203.0.113.0/24`, `2001:db8::/32`) or a
+    /// bare address. Blank lines and `#` comments are ignored.
+    ///
+    /// Returns the number of entries loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if the file cannot be read or
+    /// contains an invalid entry.
+    pub fn load_cidr_list(
+        &mut self,
+        name: &str,
+        category: IpCategory,
+        path: &Path,
+    ) -> Result<usize, ScrybeError> {
+        let contents = read_list(path)?;
+        self.add_cidr_list(name, category, &contents)
+    }
+
+    /// Add a CIDR list from its text contents (see [`Self::load_cidr_list`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if an entry is invalid. No
+    /// entries are added in that case.
+    pub fn add_cidr_list(
+        &mut self,
+        name: &str,
+        category: IpCategory,
+        contents: &str,
+    ) -> Result<usize, ScrybeError> {
+        let networks = list_entries(contents)
+            .map(|(line, entry)| parse_cidr(entry).ok_or_else(|| invalid_entry(name, line, entry)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let id = self.register(name, category);
+        for (address, prefix_len) in &networks {
+            match address {
+                IpAddr::V4(v4) => self
+                    .v4
+                    .insert(u128::from(u32::from(*v4)) << 96, *prefix_len, id),
+                IpAddr::V6(v6) => self.v6.insert(u128::from(*v6), *prefix_len, id),
+            }
+        }
+
+        Ok(networks.len())
+    }
+
+    /// Load an ASN list file.
+    ///
+    /// One ASN per line, with or without an `AS` prefix (`AS9009`, `9009`).
+    /// Blank lines and `#` comments are ignored. ASN lists match sessions
+    /// whose [`GeoInfo`](scrybe_core::types::GeoInfo) carries an ASN.
+    ///
+    /// Returns the number of entries loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if the file cannot be read or
+    /// contains an invalid entry.
+    pub fn load_asn_list(
+        &mut self,
+        name: &str,
+        category: IpCategory,
+        path: &Path,
+    ) -> Result<usize, ScrybeError> {
+        let contents = read_list(path)?;
+        self.add_asn_list(name, category, &contents)
+    }
+
+    /// Add an ASN list from its text contents (see [`Self::load_asn_list`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if an entry is invalid. No
+    /// entries are added in that case.
+    pub fn add_asn_list(
+        &mut self,
+        name: &str,
+        category: IpCategory,
+        contents: &str,
+    ) -> Result<usize, ScrybeError> {
+        let asns = list_entries(contents)
+            .map(|(line, entry)| parse_asn(entry).ok_or_else(|| invalid_entry(name, line, entry)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let id = self.register(name, category);
+        for asn in &asns {
+            let ids = self.asns.entry(*asn).or_default();
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+
+        Ok(asns.len())
+    }
+
+    /// Classify an address (and its ASN, if known).
+    ///
+    /// Returns `None` if no list matches.
+    pub fn classify(&self, ip: IpAddr, asn: Option<u32>) -> Option<IpReputation> {
+        let mut ids = match normalize(ip) {
+            IpAddr::V4(v4) => self.v4.matches(u128::from(u32::from(v4)) << 96, 32),
+            IpAddr::V6(v6) => self.v6.matches(u128::from(v6), 128),
+        };
+        if let Some(asn_ids) = asn.and_then(|asn| self.asns.get(&asn)) {
+            ids.extend(asn_ids);
+        }
+        ids.sort_unstable();
+        ids.dedup();
+
+        let category = ids.iter().map(|id| self.lists[*id].category).max()?;
+        let sources = ids.iter().map(|id| self.lists[*id].name.clone()).collect();
+
+        Some(IpReputation { category, sources })
+    }
+
+    /// Build the `ip_{category}` finding for a classified address.
+    ///
+    /// Severity: Tor and blocklisted High, datacenter and residential proxy
+    /// Medium, VPN Low.
+    pub fn finding(reputation: &IpReputation) -> Finding {
+        let severity = match reputation.category {
+            IpCategory::Vpn => Severity::Low,
+            IpCategory::Datacenter | IpCategory::ResidentialProxy => Severity::Medium,
+            IpCategory::Tor | IpCategory::Blocklisted => Severity::High,
+        };
+        Finding::new(
+            format!("ip_{}", reputation.category),
+            severity,
+            format!("client IP listed in: {}", reputation.sources.join(", ")),
+        )
+    }
+
+    /// Register a list name, reusing the id of an existing list.
+    fn register(&mut self, name: &str, category: IpCategory) -> ListId {
+        if let Some(id) = self
+            .lists
+            .iter()
+            .position(|l| l.name == name && l.category == category)
+        {
+            return id;
+        }
+        self.lists.push(ListInfo {
+            name: name.to_string(),
+            category,
+        });
+        self.lists.len() - 1
+    }
+}
+
+/// Format of a list file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    /// CIDRs or bare addresses (see [`IpReputationDb::load_cidr_list`])
+    Cidr,
+    /// ASNs (see [`IpReputationDb::load_asn_list`])
+    Asn,
+}
+
+/// A reputation list file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListFile {
+    /// List name reported in findings (e.g., "aws")
+    pub name: String,
+    /// Category of every entry
+    pub category: IpCategory,
+    /// Entry format
+    pub format: ListFormat,
+    /// File path
+    pub path: PathBuf,
+}
+
+impl ListFile {
+    /// Parse comma-separated `name:category:path` entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use scrybe_enrichment::reputation::{ListFile, ListFormat};
+    /// let lists = ListFile::parse_all(
+    ///     "aws:datacenter:/var/lib/scrybe/aws.txt,tor:tor:/var/lib/scrybe/tor.txt",
+    ///     ListFormat::Cidr,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(lists[1].name, "tor");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if an entry is malformed or names
+    /// an unknown category.
+    pub fn parse_all(value: &str, format: ListFormat) -> Result<Vec<Self>, ScrybeError> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut parts = entry.splitn(3, ':');
+                let (Some(name), Some(category), Some(path)) =
+                    (parts.next(), parts.next(), parts.next())
+                else {
+                    return Err(ScrybeError::config_error(format!(
+                        "Invalid reputation list '{}', expected name:category:path",
+                        entry
+                    )));
+                };
+                let category = category.parse().map_err(|e| {
+                    ScrybeError::config_error(format!("Invalid reputation list '{}': {}", entry, e))
+                })?;
+                Ok(Self {
+                    name: name.to_string(),
+                    category,
+                    format,
+                    path: PathBuf::from(path),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Reputation lists loaded from files, rebuilt when a file changes.
+///
+/// Lookups use the database returned by [`Self::db`]; a reload builds a
+/// complete new database and swaps it in.
+pub struct ReputationLists {
+    files: Vec<ListFile>,
+    state: RwLock<LoadedLists>,
+}
+
+/// A loaded database and the file versions it was built from.
+struct LoadedLists {
+    db: Arc<IpReputationDb>,
+    versions: Vec<FileVersion>,
+}
+
+impl ReputationLists {
+    /// Load every list file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::EnrichmentError` if a file cannot be read or
+    /// contains an invalid entry.
+    pub fn open(files: Vec<ListFile>) -> Result<Self, ScrybeError> {
+        let (db, versions) = Self::load(&files)?;
+        Ok(Self {
+            files,
+            state: RwLock::new(LoadedLists {
+                db: Arc::new(db),
+                versions,
+            }),
+        })
+    }
+
+    /// Serve a fixed database that is never reloaded.
+    pub fn fixed(db: IpReputationDb) -> Self {
+        Self {
+            files: Vec::new(),
+            state: RwLock::new(LoadedLists {
+                db: Arc::new(db),
+                versions: Vec::new(),
+            }),
+        }
+    }
+
+    /// Get the current database.
+    pub fn db(&self) -> Arc<IpReputationDb> {
+        match self.state.read() {
+            Ok(state) => Arc::clone(&state.db),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner().db),
+        }
+    }
+
+    /// Build a database from every file.
+    fn load(files: &[ListFile]) -> Result<(IpReputationDb, Vec<FileVersion>), ScrybeError> {
+        let mut db = IpReputationDb::new();
+        let mut versions = Vec::with_capacity(files.len());
+        for file in files {
+            versions.push(FileVersion::of(&file.path, "ip_reputation")?);
+            match file.format {
+                ListFormat::Cidr => db.load_cidr_list(&file.name, file.category, &file.path)?,
+                ListFormat::Asn => db.load_asn_list(&file.name, file.category, &file.path)?,
+            };
+        }
+        Ok((db, versions))
+    }
+}
+
+impl Reload for ReputationLists {
+    fn name(&self) -> &'static str {
+        "IP reputation lists"
+    }
+
+    fn reload_if_changed(&self) -> Result<bool, ScrybeError> {
+        let versions = self
+            .files
+            .iter()
+            .map(|file| FileVersion::of(&file.path, "ip_reputation"))
+            .collect::<Result<Vec<_>, _>>()?;
+        let unchanged = match self.state.read() {
+            Ok(state) => state.versions == versions,
+            Err(poisoned) => poisoned.into_inner().versions == versions,
+        };
+        if unchanged {
+            return Ok(false);
+        }
+
+        // Build outside the lock so lookups continue meanwhile
+        let (db, versions) = Self::load(&self.files)?;
+        let mut state = match self.state.write() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        *state = LoadedLists {
+            db: Arc::new(db),
+            versions,
+        };
+        Ok(true)
+    }
+}
+
+/// Binary trie over address bits, storing list ids at prefix ends.
+///
+/// Addresses are left-aligned in a `u128` so IPv4 and IPv6 share one
+/// implementation.
+#[derive(Debug)]
+struct PrefixTrie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Debug, Default)]
+struct TrieNode {
+    /// Child node indices for bit 0 and bit 1 (0 = none; root is never a child)
+    children: [u32; 2],
+    /// Lists whose prefix ends at this node
+    lists: Vec<ListId>,
+}
+
+impl Default for PrefixTrie {
+    fn default() -> Self {
+        Self {
+            nodes: vec![TrieNode::default()],
+        }
+    }
+}
+
+impl PrefixTrie {
+    /// Insert a prefix of `prefix_len` bits.
+    fn insert(&mut self, bits: u128, prefix_len: u8, id: ListId) {
+        let mut node = 0;
+        for depth in 0..prefix_len {
+            let bit = bit_at(bits, depth);
+            let child = self.nodes[node].children[bit];
+            node = if child == 0 {
+                self.nodes.push(TrieNode::default());
+                let index = self.nodes.len() - 1;
+                self.nodes[node].children[bit] = index as u32;
+                index
+            } else {
+                child as usize
+            };
+        }
+        if !self.nodes[node].lists.contains(&id) {
+            self.nodes[node].lists.push(id);
+        }
+    }
+
+    /// Collect the lists of every prefix containing the address.
+    fn matches(&self, bits: u128, address_len: u8) -> Vec<ListId> {
+        let mut found = self.nodes[0].lists.clone();
+        let mut node = 0;
+        for depth in 0..address_len {
+            let child = self.nodes[node].children[bit_at(bits, depth)];
+            if child == 0 {
+                break;
+            }
+            node = child as usize;
+            found.extend(&self.nodes[node].lists);
+        }
+        found
+    }
+}
+
+/// Get the bit at `depth` counting from the most significant bit.
+#[inline]
+fn bit_at(bits: u128, depth: u8) -> usize {
+    ((bits >> (127 - u32::from(depth))) & 1) as usize
+}
+
+/// Treat IPv4-mapped IPv6 addresses (`::ffff:a.b.c.d`) as IPv4.
+fn normalize(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// Parse a CIDR or bare address into (address, prefix length).
+///
+/// IPv4-mapped IPv6 networks (`::ffff:a.b.c.d/n`) are normalized to IPv4
+/// like the addresses they are matched against; they must not be wider
+/// than the mapped range (`n >= 96`).
+fn parse_cidr(entry: &str) -> Option<(IpAddr, u8)> {
+    let (address, prefix_len) = match entry.split_once('/') {
+        Some((address, len)) => (
+            address.parse::<IpAddr>().ok()?,
+            Some(len.parse::<u8>().ok()?),
+        ),
+        None => (entry.parse::<IpAddr>().ok()?, None),
+    };
+    let max_len = if address.is_ipv4() { 32 } else { 128 };
+    let prefix_len = prefix_len.unwrap_or(max_len);
+    if prefix_len > max_len {
+        return None;
+    }
+
+    match address {
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => Some((IpAddr::V4(v4), prefix_len.checked_sub(96)?)),
+            None => Some((address, prefix_len)),
+        },
+        IpAddr::V4(_) => Some((address, prefix_len)),
+    }
+}
+
+/// Parse an ASN with or without an `AS` prefix.
+fn parse_asn(entry: &str) -> Option<u32> {
+    let digits = entry
+        .strip_prefix("AS")
+        .or_else(|| entry.strip_prefix("as"))
+        .unwrap_or(entry);
+    digits.parse().ok()
+}
+
+/// Iterate over non-empty, non-comment entries with 1-based line numbers.
+fn list_entries(contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    contents.lines().enumerate().filter_map(|(index, line)| {
+        let entry = line.split('#').next().unwrap_or("").trim();
+        (!entry.is_empty()).then_some((index + 1, entry))
+    })
+}
+
+/// Read a list file.
+fn read_list(path: &Path) -> Result<String, ScrybeError> {
+    std::fs::read_to_string(path).map_err(|e| {
+        ScrybeError::enrichment_error(
+            "ip_reputation",
+            format!("Failed to read {}: {}", path.display(), e),
+        )
+    })
+}
+
+/// Build the error for an unparseable list entry.
+fn invalid_entry(list: &str, line: usize, entry: &str) -> ScrybeError {
+    ScrybeError::enrichment_error(
+        "ip_reputation",
+        format!(
+            "Invalid entry in list '{}' at line {}: '{}'",
+            list, line, entry
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> IpReputationDb {
+        let mut db = IpReputationDb::new();
+        db.add_cidr_list(
+            "aws",
+            IpCategory::Datacenter,
+            "# AWS ranges\n3.0.0.0/9\n2600:1f00::/24\n",
+        )
+        .unwrap();
+        db.add_cidr_list(
+            "tor-exits",
+            IpCategory::Tor,
+            "185.220.101.1\n185.220.101.2\n",
+        )
+        .unwrap();
+        db.add_cidr_list("blocklist", IpCategory::Blocklisted, "3.5.0.0/16 # abuse\n")
+            .unwrap();
+        db.add_asn_list("vpn-asns", IpCategory::Vpn, "AS9009\n60068\n")
+            .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_classify_ipv4_datacenter() {
+        let reputation = test_db()
+            .classify("3.1.2.3".parse().unwrap(), None)
+            .unwrap();
+        assert_eq!(reputation.category, IpCategory::Datacenter);
+        assert_eq!(reputation.sources, vec!["aws"]);
+    }
+
+    #[test]
+    fn test_classify_ipv6_datacenter() {
+        let reputation = test_db()
+            .classify("2600:1f00::1".parse().unwrap(), None)
+            .unwrap();
+        assert_eq!(reputation.category, IpCategory::Datacenter);
+    }
+
+    #[test]
+    fn test_classify_exact_tor_exit() {
+        let db = test_db();
+        let reputation = db.classify("185.220.101.2".parse().unwrap(), None).unwrap();
+        assert_eq!(reputation.category, IpCategory::Tor);
+        assert!(db
+            .classify("185.220.101.3".parse().unwrap(), None)
+            .is_none());
+    }
+
+    #[test]
+    fn test_overlapping_lists_report_highest_category() {
+        let reputation = test_db()
+            .classify("3.5.1.1".parse().unwrap(), None)
+            .unwrap();
+        assert_eq!(reputation.category, IpCategory::Blocklisted);
+        assert_eq!(reputation.sources, vec!["aws", "blocklist"]);
+    }
+
+    #[test]
+    fn test_classify_by_asn() {
+        let db = test_db();
+        let reputation = db.classify("8.8.8.8".parse().unwrap(), Some(9009)).unwrap();
+        assert_eq!(reputation.category, IpCategory::Vpn);
+        assert!(db
+            .classify("8.8.8.8".parse().unwrap(), Some(15169))
+            .is_none());
+    }
+
+    #[test]
+    fn test_ipv4_mapped_ipv6_uses_ipv4_trie() {
+        let reputation = test_db()
+            .classify("::ffff:3.1.2.3".parse().unwrap(), None)
+            .unwrap();
+        assert_eq!(reputation.category, IpCategory::Datacenter);
+    }
+
+    #[test]
+    fn test_ipv4_mapped_entry_uses_ipv4_trie() {
+        let mut db = IpReputationDb::new();
+        db.add_cidr_list(
+            "mapped",
+            IpCategory::Datacenter,
+            "::ffff:3.0.0.0/104
+",
+        )
+        .unwrap();
+        for ip in ["3.1.2.3", "::ffff:3.1.2.3"] {
+            let reputation = db.classify(ip.parse().unwrap(), None).unwrap();
+            assert_eq!(reputation.category, IpCategory::Datacenter);
+        }
+        assert!(db.classify("4.1.2.3".parse().unwrap(), None).is_none());
+
+        // Wider than the mapped range
+        assert!(db
+            .add_cidr_list("mapped", IpCategory::Datacenter, "::ffff:0.0.0.0/80\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_unlisted_address() {
+        assert!(test_db()
+            .classify("192.0.2.1".parse().unwrap(), None)
+            .is_none());
+    }
+
+    #[test]
+    fn test_invalid_entry_reports_line() {
+        let mut db = IpReputationDb::new();
+        let result = db.add_cidr_list("bad", IpCategory::Blocklisted, "10.0.0.0/8\n10.0.0.0/33\n");
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("line 2"));
+        assert!(db.classify("10.1.1.1".parse().unwrap(), None).is_none());
+    }
+
+    #[test]
+    fn test_parse_list_files() {
+        let lists = ListFile::parse_all(
+            "aws:datacenter:/lists/aws.txt, vpn:vpn:C:\\lists\\vpn.txt",
+            ListFormat::Asn,
+        )
+        .unwrap();
+        assert_eq!(lists.len(), 2);
+        assert_eq!(lists[0].category, IpCategory::Datacenter);
+        assert_eq!(lists[0].path, PathBuf::from("/lists/aws.txt"));
+        assert_eq!(lists[1].path, PathBuf::from("C:\\lists\\vpn.txt"));
+        assert_eq!(lists[1].format, ListFormat::Asn);
+
+        assert!(ListFile::parse_all("", ListFormat::Cidr)
+            .unwrap()
+            .is_empty());
+        assert!(ListFile::parse_all("aws:/lists/aws.txt", ListFormat::Cidr).is_err());
+        assert!(ListFile::parse_all("aws:cloud:/lists/aws.txt", ListFormat::Cidr).is_err());
+    }
+
+    #[test]
+    fn test_lists_reload_on_file_change() {
+        let dir = std::env::temp_dir().join(format!("scrybe-reputation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("blocklist.txt");
+        std::fs::write(&path, "203.0.113.0/24\n").unwrap();
+
+        let lists = ReputationLists::open(vec![ListFile {
+            name: "blocklist".to_string(),
+            category: IpCategory::Blocklisted,
+            format: ListFormat::Cidr,
+            path: path.clone(),
+        }])
+        .unwrap();
+        let ip: IpAddr = "198.51.100.7".parse().unwrap();
+        assert!(lists.db().classify(ip, None).is_none());
+        assert!(!lists.reload_if_changed().unwrap());
+
+        // A bad update keeps the previous lists
+        std::fs::write(&path, "203.0.113.0/24\n198.51.100.0/33\n").unwrap();
+        assert!(lists.reload_if_changed().is_err());
+        assert!(lists
+            .db()
+            .classify("203.0.113.1".parse().unwrap(), None)
+            .is_some());
+
+        std::fs::write(&path, "203.0.113.0/24\n198.51.100.0/24\n").unwrap();
+        assert!(lists.reload_if_changed().unwrap());
+        assert!(lists.db().classify(ip, None).is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_open_fails_on_bad_list() {
+        let result = ReputationLists::open(vec![ListFile {
+            name: "missing".to_string(),
+            category: IpCategory::Tor,
+            format: ListFormat::Cidr,
+            path: PathBuf::from("/nonexistent/tor.txt"),
+        }]);
+        assert!(matches!(result, Err(ScrybeError::EnrichmentError { .. })));
+    }
+
+    #[test]
+    fn test_parse_cidr() {
+        assert_eq!(parse_cidr("10.0.0.0/8").map(|(_, len)| len), Some(8));
+        assert_eq!(parse_cidr("10.0.0.1").map(|(_, len)| len), Some(32));
+        assert_eq!(parse_cidr("::1").map(|(_, len)| len), Some(128));
+        assert!(parse_cidr("10.0.0.0/33").is_none());
+        assert!(parse_cidr("not-an-ip").is_none());
+    }
+}
@@ -0,0 +1,528 @@
+//! Pipeline stages wrapping the enrichment components.
+//!
+//! Each stage computes its output from the session snapshot it is given and
+//! returns a [`SessionUpdate`] that the pipeline applies once the stage's
+//! wave has finished. Typed outputs are stored with the provenance the
+//! pipeline passes to the update.
+
+use crate::pipeline::{EnrichmentStage, SessionUpdate};
+use crate::timing::{SiteBaseline, TimingAnalyzer};
+use crate::velocity::{
+    VelocityCounts, FINGERPRINTS, FINGERPRINT_SESSIONS_WINDOW, SESSIONS, SUBNET_FINGERPRINTS_WINDOW,
+};
+use crate::{
+    AutomationDetector, CanvasPopulationDetector, ConsistencyDetector, FingerprintGenerator,
+    GeoEnricher, IpReputationDb, MovementDetector, RenderNoiseDetector, ReputationLists,
+    VelocityDetector, WebGlDetector,
+};
+use async_trait::async_trait;
+use scrybe_cache::{
+    CanvasPopulations, CorrelationIndex, Entity, EntityKind, TimingBaselines, VelocityCounters,
+    VelocityUpdate, VisitorIndex,
+};
+use scrybe_core::{
+    privacy::{hash_ip, subnet},
+    types::{EnrichedSession, Enrichment, Finding},
+    ScrybeError,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Stage name of [`FingerprintStage`].
+pub const FINGERPRINT: &str = "fingerprint";
+/// Stage name of [`VisitorStage`].
+pub const VISITOR: &str = "visitor";
+/// Stage name of [`CorrelationStage`].
+pub const CORRELATION: &str = "correlation";
+/// Stage name of [`GeoStage`].
+pub const GEO: &str = "geo";
+/// Stage name of [`IpReputationStage`].
+pub const IP_REPUTATION: &str = "ip_reputation";
+/// Stage name of [`ConsistencyStage`].
+pub const CONSISTENCY: &str = "consistency";
+/// Stage name of [`AutomationStage`].
+pub const AUTOMATION: &str = "automation";
+/// Stage name of [`WebGlStage`].
+pub const WEBGL: &str = "webgl";
+/// Stage name of [`MovementStage`].
+pub const MOVEMENT: &str = "movement";
+/// Stage name of [`RenderNoiseStage`].
+pub const RENDER_NOISE: &str = "render_noise";
+/// Stage name of [`CanvasPopulationStage`].
+pub const CANVAS_POPULATION: &str = "canvas_population";
+/// Stage name of [`VelocityStage`].
+pub const VELOCITY: &str = "velocity";
+/// Stage name of [`TimingStage`].
+pub const TIMING: &str = "timing";
+
+/// Build an update that records findings on the session.
+fn record_findings(findings: Vec<Finding>) -> SessionUpdate {
+    if findings.is_empty() {
+        return SessionUpdate::none();
+    }
+    SessionUpdate::new(move |enriched, _| enriched.evidence.extend(findings))
+}
+
+/// Computes the composite fingerprint. Critical: a session without a
+/// fingerprint cannot be stored.
+pub struct FingerprintStage;
+
+#[async_trait]
+impl EnrichmentStage for FingerprintStage {
+    fn name(&self) -> &'static str {
+        FINGERPRINT
+    }
+
+    fn critical(&self) -> bool {
+        true
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let fingerprint = FingerprintGenerator::generate(&enriched.session)?;
+        Ok(SessionUpdate::new(move |e, provenance| {
+            e.fingerprint = Some(Enrichment::new(fingerprint, provenance));
+        }))
+    }
+}
+
+/// Links the session to the visitor owning its fingerprint.
+pub struct VisitorStage(pub VisitorIndex);
+
+#[async_trait]
+impl EnrichmentStage for VisitorStage {
+    fn name(&self) -> &'static str {
+        VISITOR
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[FINGERPRINT]
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let hash = enriched
+            .fingerprint_hash()
+            .ok_or_else(|| ScrybeError::enrichment_error(VISITOR, "Missing fingerprint"))?;
+        let visitor = self.0.link(hash, &enriched.session.id).await?;
+
+        Ok(SessionUpdate::new(move |e, provenance| {
+            e.visitor = Some(Enrichment::new(visitor, provenance));
+        }))
+    }
+}
+
+/// Records the session in the fingerprint/IP correlation index.
+pub struct CorrelationStage {
+    index: CorrelationIndex,
+    ip_salt: Vec<u8>,
+}
+
+impl CorrelationStage {
+    /// Create a stage that hashes IPs with `ip_salt` before indexing them.
+    pub fn new(index: CorrelationIndex, ip_salt: Vec<u8>) -> Self {
+        Self { index, ip_salt }
+    }
+}
+
+#[async_trait]
+impl EnrichmentStage for CorrelationStage {
+    fn name(&self) -> &'static str {
+        CORRELATION
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[FINGERPRINT]
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let session = &enriched.session;
+        let hash = enriched
+            .fingerprint_hash()
+            .ok_or_else(|| ScrybeError::enrichment_error(CORRELATION, "Missing fingerprint"))?;
+        let ip_hash = hash_ip(&session.network.ip.to_string(), &self.ip_salt);
+        let page_offset = i64::try_from(session.page_offset_ms).unwrap_or(0);
+        let seen_at = session.timestamp + chrono::Duration::milliseconds(page_offset);
+
+        self.index
+            .record(hash, &ip_hash, &session.id, seen_at)
+            .await?;
+
+        Ok(SessionUpdate::none())
+    }
+}
+
+/// Resolves GeoIP and ASN data.
+pub struct GeoStage(pub Arc<GeoEnricher>);
+
+#[async_trait]
+impl EnrichmentStage for GeoStage {
+    fn name(&self) -> &'static str {
+        GEO
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let geo = self.0.lookup(enriched.session.network.ip);
+        Ok(SessionUpdate::new(move |e, provenance| {
+            e.geo = geo.map(|geo| Enrichment::new(geo, provenance));
+        }))
+    }
+}
+
+/// Classifies the client IP against reputation lists.
+pub struct IpReputationStage(pub Arc<ReputationLists>);
+
+#[async_trait]
+impl EnrichmentStage for IpReputationStage {
+    fn name(&self) -> &'static str {
+        IP_REPUTATION
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[GEO]
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let asn = enriched.geo.as_ref().and_then(|geo| geo.value.asn);
+        let reputation = self.0.db().classify(enriched.session.network.ip, asn);
+        Ok(SessionUpdate::new(move |e, provenance| {
+            if let Some(reputation) = &reputation {
+                e.evidence.record(IpReputationDb::finding(reputation));
+            }
+            e.ip_reputation = reputation.map(|r| Enrichment::new(r, provenance));
+        }))
+    }
+}
+
+/// Checks timezone/language/geo consistency.
+pub struct ConsistencyStage;
+
+#[async_trait]
+impl EnrichmentStage for ConsistencyStage {
+    fn name(&self) -> &'static str {
+        CONSISTENCY
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[GEO]
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        Ok(record_findings(ConsistencyDetector::detect(
+            &enriched.session,
+            enriched.geo.as_ref().map(|geo| &geo.value),
+        )))
+    }
+}
+
+/// Detects headless browsers and automation frameworks.
+pub struct AutomationStage;
+
+#[async_trait]
+impl EnrichmentStage for AutomationStage {
+    fn name(&self) -> &'static str {
+        AUTOMATION
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let (likelihoods, findings) = AutomationDetector::assess(&enriched.session.browser);
+        Ok(SessionUpdate::new(move |e, provenance| {
+            e.evidence.extend(findings);
+            e.automation = Some(Enrichment::new(likelihoods, provenance));
+        }))
+    }
+}
+
+/// Checks WebGL parameters against the claimed platform.
+pub struct WebGlStage;
+
+#[async_trait]
+impl EnrichmentStage for WebGlStage {
+    fn name(&self) -> &'static str {
+        WEBGL
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        Ok(record_findings(WebGlDetector::detect(
+            &enriched.session.browser,
+        )))
+    }
+}
+
+/// Detects synthetic mouse movement.
+pub struct MovementStage;
+
+#[async_trait]
+impl EnrichmentStage for MovementStage {
+    fn name(&self) -> &'static str {
+        MOVEMENT
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        Ok(record_findings(MovementDetector::detect(
+            &enriched.session.behavioral,
+        )))
+    }
+}
+
+/// Compares repeated canvas/audio renders.
+pub struct RenderNoiseStage;
+
+#[async_trait]
+impl EnrichmentStage for RenderNoiseStage {
+    fn name(&self) -> &'static str {
+        RENDER_NOISE
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        Ok(record_findings(RenderNoiseDetector::detect(
+            &enriched.session.browser,
+        )))
+    }
+}
+
+/// Counts canvas hashes across otherwise identical fingerprints and
+/// checks the session's canvas against its group.
+///
+/// Each session is counted once, so re-scoring a session does not inflate
+/// its canvas count.
+pub struct CanvasPopulationStage {
+    populations: CanvasPopulations,
+}
+
+impl CanvasPopulationStage {
+    /// Create a stage keeping group counts in `populations`.
+    pub fn new(populations: CanvasPopulations) -> Self {
+        Self { populations }
+    }
+}
+
+#[async_trait]
+impl EnrichmentStage for CanvasPopulationStage {
+    fn name(&self) -> &'static str {
+        CANVAS_POPULATION
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[FINGERPRINT]
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let Some(canvas) = &enriched.session.browser.canvas_hash else {
+            return Ok(SessionUpdate::none());
+        };
+
+        let stats = self
+            .populations
+            .observe(
+                &CanvasPopulationDetector::group_key(enriched),
+                &enriched.session.id.to_string(),
+                canvas,
+            )
+            .await?;
+        Ok(record_findings(CanvasPopulationDetector::detect(&stats)))
+    }
+}
+
+/// Counts the session in the velocity counters and checks the windowed
+/// counts against the velocity limits.
+///
+/// Updates are distinct counts keyed by session and fingerprint, so
+/// re-scoring a session does not inflate them.
+pub struct VelocityStage {
+    counters: VelocityCounters,
+    ip_salt: Vec<u8>,
+}
+
+impl VelocityStage {
+    /// Create a stage that hashes networks with `ip_salt` before counting
+    /// them.
+    pub fn new(counters: VelocityCounters, ip_salt: Vec<u8>) -> Self {
+        Self { counters, ip_salt }
+    }
+}
+
+#[async_trait]
+impl EnrichmentStage for VelocityStage {
+    fn name(&self) -> &'static str {
+        VELOCITY
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[FINGERPRINT, GEO]
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let session = &enriched.session;
+        let hash = enriched
+            .fingerprint_hash()
+            .ok_or_else(|| ScrybeError::enrichment_error(VELOCITY, "Missing fingerprint"))?;
+        let fingerprint = Entity::new(EntityKind::Fingerprint, hash);
+        let network = Entity::new(
+            EntityKind::SubnetHash,
+            hash_ip(&subnet(&session.network.ip), &self.ip_salt),
+        );
+
+        let mut updates = vec![
+            VelocityUpdate::Distinct {
+                counter: SESSIONS,
+                entity: fingerprint.clone(),
+                member: session.id.to_string(),
+            },
+            VelocityUpdate::Distinct {
+                counter: FINGERPRINTS,
+                entity: network.clone(),
+                member: hash.to_string(),
+            },
+        ];
+        if let Some(asn) = enriched.geo.as_ref().and_then(|geo| geo.value.asn) {
+            updates.push(VelocityUpdate::Distinct {
+                counter: FINGERPRINTS,
+                entity: Entity::new(EntityKind::Asn, asn.to_string()),
+                member: hash.to_string(),
+            });
+        }
+
+        let page_offset = i64::try_from(session.page_offset_ms).unwrap_or(0);
+        let seen_at = session.timestamp + chrono::Duration::milliseconds(page_offset);
+        self.counters.record(&updates, seen_at).await?;
+
+        let (fingerprint_sessions, subnet_fingerprints) = futures::try_join!(
+            self.counters
+                .distinct(SESSIONS, &fingerprint, FINGERPRINT_SESSIONS_WINDOW),
+            self.counters
+                .distinct(FINGERPRINTS, &network, SUBNET_FINGERPRINTS_WINDOW),
+        )?;
+
+        Ok(record_findings(VelocityDetector::detect(&VelocityCounts {
+            fingerprint_sessions,
+            subnet_fingerprints,
+        })))
+    }
+}
+
+/// Checks page timings against browser invariants, the gateway's
+/// observations and the site's baseline, and learns from sessions no
+/// detector flagged.
+///
+/// Runs after the local detectors so their findings can keep a session out
+/// of the baseline. A page load is learned at most once, so re-scoring a
+/// session does not weigh it again.
+///
+/// The site comes from the client's `Referer`, so only allowlisted sites
+/// have a baseline; any other site gets the plausibility checks only.
+pub struct TimingStage {
+    baselines: TimingBaselines,
+    sites: HashSet<String>,
+}
+
+impl TimingStage {
+    /// Create a stage keeping baselines in `baselines` for the lowercase
+    /// hosts in `sites`.
+    pub fn new(baselines: TimingBaselines, sites: HashSet<String>) -> Self {
+        Self { baselines, sites }
+    }
+}
+
+#[async_trait]
+impl EnrichmentStage for TimingStage {
+    fn name(&self) -> &'static str {
+        TIMING
+    }
+
+    fn depends_on(&self) -> &'static [&'static str] {
+        &[AUTOMATION, CONSISTENCY, WEBGL, MOVEMENT, RENDER_NOISE]
+    }
+
+    async fn run(&self, enriched: &EnrichedSession) -> Result<SessionUpdate, ScrybeError> {
+        let session = &enriched.session;
+        let timing = &session.behavioral.timing;
+        let observed = session.network.gateway_timing.as_ref();
+
+        let site = TimingAnalyzer::site(&session.network).filter(|site| self.sites.contains(site));
+        let Some(site) = site else {
+            return Ok(record_findings(TimingAnalyzer::analyze(
+                timing, observed, None,
+            )));
+        };
+
+        let baseline = SiteBaseline::from_summaries(&self.baselines.load(&site).await?);
+        let findings = TimingAnalyzer::analyze(timing, observed, Some(&baseline));
+
+        if TimingAnalyzer::should_learn(&enriched.evidence, &findings) {
+            let page = format!("{}:{}", session.id, session.page_loads);
+            self.baselines
+                .observe(&site, &page, &SiteBaseline::samples(timing))
+                .await?;
+        }
+
+        Ok(record_findings(findings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{Pipeline, StageStatus};
+    use scrybe_core::test_util;
+    use scrybe_core::types::{IpCategory, Session};
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::path::Path;
+
+    /// A session from a London IP of the GeoIP test databases.
+    fn create_test_session() -> Session {
+        let mut session = test_util::session();
+        session.network.ip = IpAddr::V4(Ipv4Addr::new(81, 2, 69, 142));
+        session.browser.canvas_hash = Some("canvas".to_string());
+        session.browser.fonts = vec!["Arial".to_string()];
+        session.browser.timezone = "Europe/London".to_string();
+        session.browser.language = "en-GB".to_string();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_full_pipeline() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/geo");
+        let geo = GeoEnricher::open(
+            Some(&fixtures.join("GeoLite2-City-Test.mmdb")),
+            Some(&fixtures.join("GeoLite2-ASN-Test.mmdb")),
+        )
+        .unwrap();
+        let mut reputation = IpReputationDb::new();
+        reputation
+            .add_asn_list("hosting-asns", IpCategory::Datacenter, "AS20712\n")
+            .unwrap();
+
+        let stages: Vec<Arc<dyn EnrichmentStage>> = vec![
+            Arc::new(IpReputationStage(Arc::new(ReputationLists::fixed(
+                reputation,
+            )))),
+            Arc::new(FingerprintStage),
+            Arc::new(GeoStage(Arc::new(geo))),
+            Arc::new(ConsistencyStage),
+            Arc::new(AutomationStage),
+            Arc::new(WebGlStage),
+            Arc::new(MovementStage),
+            Arc::new(RenderNoiseStage),
+        ];
+        let pipeline =
+            Pipeline::new(stages, None, Some(std::time::Duration::from_secs(1))).unwrap();
+
+        let (enriched, report) = pipeline.enrich(create_test_session()).await.unwrap();
+
+        assert!(report
+            .stages
+            .iter()
+            .all(|s| s.status == StageStatus::Succeeded));
+        assert!(enriched.fingerprint_hash().is_some());
+        assert_eq!(enriched.geo.as_ref().and_then(|g| g.value.asn), Some(20712));
+        assert_eq!(
+            enriched.geo.as_ref().map(|g| g.provenance.stage.as_str()),
+            Some(GEO)
+        );
+        assert_eq!(
+            enriched.ip_reputation.as_ref().map(|r| r.value.category),
+            Some(IpCategory::Datacenter)
+        );
+        assert!(enriched.evidence.contains("ip_datacenter"));
+        assert!(enriched.automation.is_some());
+    }
+}
@@ -0,0 +1,512 @@
+//! Timing-metric plausibility analysis.
+//!
+//! Checks the page-load timings reported by the SDK against browser
+//! invariants, the request timing the gateway observed, and per-site
+//! baselines learned from previously analyzed traffic.
+//!
+//! Baselines are kept in Redis ([`TimingBaselines`](scrybe_cache::TimingBaselines))
+//! so every worker scores against, and contributes to, the same baseline.
+//! The site is the host of the `Referer` the gateway observed.
+
+use scrybe_cache::MetricSummary;
+use scrybe_core::types::{
+    BotEvidence, Finding, GatewayTiming, NetworkSignals, Severity, TimingMetrics,
+};
+use std::collections::HashMap;
+
+/// First interaction faster than this (ms after navigation) is not human.
+const MIN_HUMAN_INTERACTION_MS: u64 = 150;
+
+/// Samples required before a site baseline is used for scoring.
+const MIN_BASELINE_SAMPLES: u64 = 30;
+
+/// Z-score above which a metric is reported as an outlier.
+const OUTLIER_Z_SCORE: f64 = 4.0;
+
+/// Allowance for network delay between the two requests a page interval
+/// spans.
+const PAGE_INTERVAL_SLACK_MS: u64 = 1_000;
+
+/// Running mean and variance of a single metric (Welford's algorithm).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MetricStats {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl From<MetricSummary> for MetricStats {
+    fn from(summary: MetricSummary) -> Self {
+        Self {
+            count: summary.count,
+            mean: summary.mean,
+            m2: summary.m2,
+        }
+    }
+}
+
+impl MetricStats {
+    /// Add a sample.
+    pub fn update(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Number of samples seen.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Sample mean.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Sample standard deviation (0.0 with fewer than two samples).
+    pub fn std_dev(&self) -> f64 {
+        if self.count < 2 {
+            return 0.0;
+        }
+        (self.m2 / (self.count - 1) as f64).sqrt()
+    }
+
+    /// Distance of `value` from the mean in standard deviations.
+    ///
+    /// Returns `None` until the baseline has enough samples and non-zero
+    /// spread to make the score meaningful.
+    pub fn z_score(&self, value: f64) -> Option<f64> {
+        let std_dev = self.std_dev();
+        if self.count < MIN_BASELINE_SAMPLES || std_dev <= f64::EPSILON {
+            return None;
+        }
+        Some((value - self.mean) / std_dev)
+    }
+}
+
+/// Timing baseline for one site.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SiteBaseline {
+    /// Time to first byte
+    pub time_to_first_byte: MetricStats,
+    /// DOMContentLoaded
+    pub dom_content_loaded: MetricStats,
+    /// Full page load
+    pub load_time: MetricStats,
+    /// Time to first interaction
+    pub time_to_first_interaction: MetricStats,
+}
+
+impl SiteBaseline {
+    /// Pair each baseline metric with the matching reported value.
+    fn metrics<'a>(
+        &'a self,
+        timing: &TimingMetrics,
+    ) -> [(&'static str, &'a MetricStats, Option<u64>); 4] {
+        [
+            (
+                "time_to_first_byte",
+                &self.time_to_first_byte,
+                timing.time_to_first_byte_ms,
+            ),
+            (
+                "dom_content_loaded",
+                &self.dom_content_loaded,
+                timing.dom_content_loaded_ms,
+            ),
+            ("load_time", &self.load_time, timing.load_time_ms),
+            (
+                "time_to_first_interaction",
+                &self.time_to_first_interaction,
+                timing.time_to_first_interaction_ms,
+            ),
+        ]
+    }
+
+    /// Build a baseline from the statistics stored for a site.
+    ///
+    /// Metrics without stored statistics start empty.
+    pub fn from_summaries(summaries: &HashMap<String, MetricSummary>) -> Self {
+        let stats = |name: &str| summaries.get(name).copied().map(MetricStats::from);
+        Self {
+            time_to_first_byte: stats("time_to_first_byte").unwrap_or_default(),
+            dom_content_loaded: stats("dom_content_loaded").unwrap_or_default(),
+            load_time: stats("load_time").unwrap_or_default(),
+            time_to_first_interaction: stats("time_to_first_interaction").unwrap_or_default(),
+        }
+    }
+
+    /// Reported values to fold into a baseline, by metric name.
+    pub fn samples(timing: &TimingMetrics) -> Vec<(&'static str, f64)> {
+        Self::default()
+            .metrics(timing)
+            .iter()
+            .filter_map(|(name, _, value)| Some((*name, (*value)? as f64)))
+            .collect()
+    }
+}
+
+/// Analyzes `TimingMetrics` for plausibility and per-site outliers.
+pub struct TimingAnalyzer;
+
+impl TimingAnalyzer {
+    /// Check timings.
+    ///
+    /// Findings produced:
+    /// - `timing_order_violation`: TTFB, DOMContentLoaded and load are out of order
+    /// - `instant_interaction`: first interaction faster than a human can react
+    /// - `timing_exceeds_page_interval`: the reported page load took longer
+    ///   than the gateway observed between this and the previous page load
+    /// - `timing_outlier`: a metric is far outside the site's baseline
+    pub fn analyze(
+        timing: &TimingMetrics,
+        observed: Option<&GatewayTiming>,
+        baseline: Option<&SiteBaseline>,
+    ) -> Vec<Finding> {
+        let mut findings = Self::check_plausibility(timing, observed);
+        findings.extend(baseline.and_then(|baseline| Self::check_baseline(baseline, timing)));
+        findings
+    }
+
+    /// Whether a session's timings may be added to its site's baseline.
+    ///
+    /// Sessions flagged by the timing checks, including baseline outliers,
+    /// or by another detector at Medium severity or above are excluded, so
+    /// automated traffic cannot drag the baseline towards itself.
+    pub fn should_learn(evidence: &BotEvidence, findings: &[Finding]) -> bool {
+        findings.is_empty()
+            && evidence
+                .findings
+                .iter()
+                .all(|f| f.severity < Severity::Medium)
+    }
+
+    /// Site a session's baseline is kept for: the host of the `Referer`
+    /// header the gateway observed.
+    ///
+    /// Returns `None` without an observed `Referer`.
+    pub fn site(network: &NetworkSignals) -> Option<String> {
+        let referer = network.observed_header("referer")?;
+        let authority = referer
+            .split_once("://")
+            .map_or(referer, |(_, rest)| rest)
+            .split(['/', '?', '#'])
+            .next()?;
+        let host = authority.rsplit('@').next()?;
+        let host = match host.strip_prefix('[') {
+            // IPv6 literal, keep the brackets
+            Some(_) => host.split_inclusive(']').next()?,
+            None => host.split(':').next()?,
+        };
+        (!host.is_empty()).then(|| host.to_ascii_lowercase())
+    }
+
+    /// Check invariants that hold for every real browser.
+    fn check_plausibility(
+        timing: &TimingMetrics,
+        observed: Option<&GatewayTiming>,
+    ) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        let ordered = [
+            timing.time_to_first_byte_ms,
+            timing.dom_content_loaded_ms,
+            timing.load_time_ms,
+        ];
+        let present: Vec<u64> = ordered.iter().flatten().copied().collect();
+        if present.windows(2).any(|w| w[0] > w[1]) {
+            findings.push(Finding::new(
+                "timing_order_violation",
+                Severity::High,
+                format!(
+                    "expected ttfb <= dcl <= load, got ttfb={:?} dcl={:?} load={:?}",
+                    timing.time_to_first_byte_ms, timing.dom_content_loaded_ms, timing.load_time_ms
+                ),
+            ));
+        }
+
+        if let Some(interaction) = timing.time_to_first_interaction_ms {
+            if interaction < MIN_HUMAN_INTERACTION_MS {
+                findings.push(Finding::new(
+                    "instant_interaction",
+                    Severity::High,
+                    format!(
+                        "first interaction after {}ms (minimum human: {}ms)",
+                        interaction, MIN_HUMAN_INTERACTION_MS
+                    ),
+                ));
+            }
+        }
+
+        // The page was requested after the previous page load reached the
+        // gateway, so none of its timings can exceed the interval between them
+        let longest = present.iter().max();
+        if let (Some(longest), Some(observed)) = (longest, observed) {
+            if *longest
+                > observed
+                    .page_interval_ms
+                    .saturating_add(PAGE_INTERVAL_SLACK_MS)
+            {
+                findings.push(Finding::new(
+                    "timing_exceeds_page_interval",
+                    Severity::Medium,
+                    format!(
+                        "reported page timing {}ms exceeds the {}ms observed since the previous page load",
+                        longest, observed.page_interval_ms
+                    ),
+                ));
+            }
+        }
+
+        findings
+    }
+
+    /// Score each metric against the site's learned baseline.
+    fn check_baseline(baseline: &SiteBaseline, timing: &TimingMetrics) -> Option<Finding> {
+        let outliers: Vec<String> = baseline
+            .metrics(timing)
+            .iter()
+            .filter_map(|(name, stats, value)| {
+                let z = stats.z_score((*value)? as f64)?;
+                (z.abs() > OUTLIER_Z_SCORE).then(|| format!("{} (z={:.1})", name, z))
+            })
+            .collect();
+
+        if outliers.is_empty() {
+            return None;
+        }
+
+        let severity = if outliers.len() > 1 {
+            Severity::Medium
+        } else {
+            Severity::Low
+        };
+
+        Some(Finding::new(
+            "timing_outlier",
+            severity,
+            format!("outside site baseline: {}", outliers.join(", ")),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+    use scrybe_core::types::Header;
+
+    fn timing(ttfb: u64, dcl: u64, load: u64, interaction: u64) -> TimingMetrics {
+        TimingMetrics {
+            time_to_first_byte_ms: Some(ttfb),
+            dom_content_loaded_ms: Some(dcl),
+            load_time_ms: Some(load),
+            time_to_first_interaction_ms: Some(interaction),
+        }
+    }
+
+    fn names(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    fn analyze(timing: &TimingMetrics) -> Vec<Finding> {
+        TimingAnalyzer::analyze(timing, None, None)
+    }
+
+    /// Baseline learned from 50 plausible page loads.
+    fn learned_baseline() -> SiteBaseline {
+        let mut baseline = SiteBaseline::default();
+        for i in 0..50 {
+            let timing = timing(100 + i % 10, 800 + i % 40, 1500 + i % 60, 3000);
+            for (name, value) in SiteBaseline::samples(&timing) {
+                let stats = match name {
+                    "time_to_first_byte" => &mut baseline.time_to_first_byte,
+                    "dom_content_loaded" => &mut baseline.dom_content_loaded,
+                    "load_time" => &mut baseline.load_time,
+                    _ => &mut baseline.time_to_first_interaction,
+                };
+                stats.update(value);
+            }
+        }
+        baseline
+    }
+
+    fn network(headers: Vec<Header>) -> NetworkSignals {
+        NetworkSignals {
+            headers,
+            ..test_util::network_signals()
+        }
+    }
+
+    #[test]
+    fn test_plausible_timing_has_no_findings() {
+        assert!(analyze(&timing(120, 800, 1500, 2400)).is_empty());
+    }
+
+    #[test]
+    fn test_order_violation_detected() {
+        let findings = analyze(&timing(900, 800, 1500, 2400));
+        assert_eq!(names(&findings), vec!["timing_order_violation"]);
+    }
+
+    #[test]
+    fn test_missing_metrics_are_skipped_in_ordering() {
+        let timing = TimingMetrics {
+            time_to_first_byte_ms: Some(100),
+            dom_content_loaded_ms: None,
+            load_time_ms: Some(1200),
+            time_to_first_interaction_ms: None,
+        };
+        assert!(analyze(&timing).is_empty());
+    }
+
+    #[test]
+    fn test_instant_interaction_detected() {
+        let findings = analyze(&timing(20, 40, 60, 70));
+        assert_eq!(names(&findings), vec!["instant_interaction"]);
+    }
+
+    #[test]
+    fn test_timing_exceeding_page_interval_detected() {
+        let timing = timing(100, 800, 4_500, 5_000);
+
+        let observed = GatewayTiming {
+            page_interval_ms: 2_000,
+        };
+        let findings = TimingAnalyzer::analyze(&timing, Some(&observed), None);
+        assert_eq!(names(&findings), vec!["timing_exceeds_page_interval"]);
+
+        // Within the allowance for network delay
+        let observed = GatewayTiming {
+            page_interval_ms: 4_000,
+        };
+        assert!(TimingAnalyzer::analyze(&timing, Some(&observed), None).is_empty());
+    }
+
+    #[test]
+    fn test_oversized_timing_does_not_overflow() {
+        let observed = GatewayTiming {
+            page_interval_ms: u64::MAX,
+        };
+        let timing = TimingMetrics {
+            time_to_first_byte_ms: Some(u64::MAX),
+            dom_content_loaded_ms: None,
+            load_time_ms: None,
+            time_to_first_interaction_ms: None,
+        };
+        assert!(TimingAnalyzer::analyze(&timing, Some(&observed), None).is_empty());
+    }
+
+    #[test]
+    fn test_baseline_outlier_detected() {
+        let baseline = learned_baseline();
+
+        let findings =
+            TimingAnalyzer::analyze(&timing(100, 800, 60_000, 3000), None, Some(&baseline));
+        assert_eq!(names(&findings), vec!["timing_outlier"]);
+
+        let findings =
+            TimingAnalyzer::analyze(&timing(105, 820, 1530, 3000), None, Some(&baseline));
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_small_baseline_is_not_used() {
+        let mut baseline = SiteBaseline::default();
+        for load in [1_000.0, 1_100.0, 1_200.0] {
+            baseline.load_time.update(load);
+        }
+        let findings =
+            TimingAnalyzer::analyze(&timing(100, 800, 60_000, 3000), None, Some(&baseline));
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn test_flagged_sessions_do_not_train_baseline() {
+        let mut evidence = BotEvidence::default();
+        assert!(TimingAnalyzer::should_learn(&evidence, &[]));
+
+        // Timing findings, outliers included
+        let outlier = Finding::new("timing_outlier", Severity::Low, "load_time (z=5.0)");
+        assert!(!TimingAnalyzer::should_learn(&evidence, &[outlier]));
+
+        // Weak findings from other detectors are common in real traffic
+        evidence.record(Finding::new("ip_vpn", Severity::Low, "vpn"));
+        assert!(TimingAnalyzer::should_learn(&evidence, &[]));
+
+        evidence.record(Finding::new("teleport_click", Severity::High, "click"));
+        assert!(!TimingAnalyzer::should_learn(&evidence, &[]));
+    }
+
+    #[test]
+    fn test_baseline_round_trips_through_summaries() {
+        let baseline = learned_baseline();
+        let summaries: HashMap<String, MetricSummary> = [
+            ("time_to_first_byte", baseline.time_to_first_byte),
+            ("dom_content_loaded", baseline.dom_content_loaded),
+            ("load_time", baseline.load_time),
+        ]
+        .into_iter()
+        .map(|(name, stats)| {
+            let summary = MetricSummary {
+                count: stats.count,
+                mean: stats.mean,
+                m2: stats.m2,
+            };
+            (name.to_string(), summary)
+        })
+        .collect();
+
+        let restored = SiteBaseline::from_summaries(&summaries);
+        assert_eq!(restored.load_time, baseline.load_time);
+        assert_eq!(restored.time_to_first_interaction, MetricStats::default());
+    }
+
+    #[test]
+    fn test_samples_skip_missing_metrics() {
+        let timing = TimingMetrics {
+            time_to_first_byte_ms: Some(100),
+            load_time_ms: Some(1200),
+            ..TimingMetrics::default()
+        };
+        assert_eq!(
+            SiteBaseline::samples(&timing),
+            vec![("time_to_first_byte", 100.0), ("load_time", 1200.0)]
+        );
+    }
+
+    #[test]
+    fn test_site_from_observed_referer() {
+        let site = |referer: &str| {
+            TimingAnalyzer::site(&network(vec![Header::observed("referer", referer)]))
+        };
+        assert_eq!(
+            site("https://Shop.Example.com:8443/cart?id=1").as_deref(),
+            Some("shop.example.com")
+        );
+        assert_eq!(
+            site("https://user@example.com").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            site("http://[2001:db8::1]:8080/").as_deref(),
+            Some("[2001:db8::1]")
+        );
+        assert_eq!(site("https:///"), None);
+
+        // Client-reported referers are not trusted
+        let spoofed = network(vec![Header::new("referer", "https://example.com/")]);
+        assert_eq!(TimingAnalyzer::site(&spoofed), None);
+    }
+
+    #[test]
+    fn test_metric_stats_welford() {
+        let mut stats = MetricStats::default();
+        for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            stats.update(value);
+        }
+        assert_eq!(stats.mean(), 5.0);
+        assert!((stats.std_dev() - 2.138).abs() < 0.001);
+    }
+}
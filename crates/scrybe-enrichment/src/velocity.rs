@@ -0,0 +1,114 @@
+//! Velocity rules over sliding-window counters.
+//!
+//! A single browser opens a handful of sessions per hour and a home or
+//! office network hosts a handful of devices. A fingerprint opening dozens
+//! of sessions within minutes, or a network cycling through dozens of
+//! fingerprints within an hour, is a farm or an anti-detect browser
+//! rotating its identity. Counts come from `scrybe_cache::VelocityCounters`.
+
+use scrybe_core::types::{Finding, Severity};
+use std::time::Duration;
+
+/// Counter of distinct sessions per fingerprint.
+pub const SESSIONS: &str = "sessions";
+
+/// Counter of distinct fingerprints per network or ASN.
+pub const FINGERPRINTS: &str = "fingerprints";
+
+/// Window of the sessions-per-fingerprint rule.
+pub const FINGERPRINT_SESSIONS_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Window of the fingerprints-per-network rule.
+pub const SUBNET_FINGERPRINTS_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Most sessions one fingerprint plausibly opens in five minutes.
+const MAX_FINGERPRINT_SESSIONS: u64 = 20;
+
+/// Most fingerprints one /24 (or /48) plausibly hosts in an hour.
+const MAX_SUBNET_FINGERPRINTS: u64 = 50;
+
+/// Windowed counts for the entities of one session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VelocityCounts {
+    /// Distinct sessions with the fingerprint in the last five minutes
+    pub fingerprint_sessions: u64,
+    /// Distinct fingerprints from the client network in the last hour
+    pub subnet_fingerprints: u64,
+}
+
+/// Detects entities active faster than a single visitor can be.
+pub struct VelocityDetector;
+
+impl VelocityDetector {
+    /// Check windowed counts against the velocity limits.
+    ///
+    /// # Findings
+    ///
+    /// - `fingerprint_session_velocity` (Medium): more than 20 sessions with
+    ///   the fingerprint in five minutes
+    /// - `subnet_fingerprint_churn` (Medium): more than 50 fingerprints from
+    ///   the client network in an hour
+    pub fn detect(counts: &VelocityCounts) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        if counts.fingerprint_sessions > MAX_FINGERPRINT_SESSIONS {
+            findings.push(Finding::new(
+                "fingerprint_session_velocity",
+                Severity::Medium,
+                format!(
+                    "{} sessions with this fingerprint in 5 minutes (limit {})",
+                    counts.fingerprint_sessions, MAX_FINGERPRINT_SESSIONS
+                ),
+            ));
+        }
+
+        if counts.subnet_fingerprints > MAX_SUBNET_FINGERPRINTS {
+            findings.push(Finding::new(
+                "subnet_fingerprint_churn",
+                Severity::Medium,
+                format!(
+                    "{} fingerprints from this network in 1 hour (limit {})",
+                    counts.subnet_fingerprints, MAX_SUBNET_FINGERPRINTS
+                ),
+            ));
+        }
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_counts_have_no_findings() {
+        let counts = VelocityCounts {
+            fingerprint_sessions: 3,
+            subnet_fingerprints: 12,
+        };
+        assert!(VelocityDetector::detect(&counts).is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_session_velocity() {
+        let counts = VelocityCounts {
+            fingerprint_sessions: 40,
+            ..Default::default()
+        };
+        let findings = VelocityDetector::detect(&counts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].name, "fingerprint_session_velocity");
+    }
+
+    #[test]
+    fn test_subnet_fingerprint_churn() {
+        let counts = VelocityCounts {
+            subnet_fingerprints: 51,
+            ..Default::default()
+        };
+        let findings = VelocityDetector::detect(&counts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].name, "subnet_fingerprint_churn");
+    }
+}
@@ -0,0 +1,316 @@
+//! WebGL renderer consistency checks.
+//!
+//! The unmasked renderer string reveals the graphics API and GPU behind the
+//! browser. Direct3D only exists on Windows, Metal only on Apple platforms,
+//! and Adreno/Mali GPUs only in mobile and ARM devices, so a renderer that
+//! cannot exist on the operating system claimed by the user agent means one
+//! of the two has been spoofed.
+
+use scrybe_core::types::{BrowserSignals, Finding, Severity, WebGlInfo};
+
+/// Software WebGL renderers used when no GPU is available.
+const SOFTWARE_RENDERERS: &[&str] = &["swiftshader", "llvmpipe", "softpipe"];
+
+/// Smallest `MAX_TEXTURE_SIZE` reported by real hardware.
+const MIN_MAX_TEXTURE_SIZE: u32 = 1024;
+
+/// Largest `MAX_TEXTURE_SIZE` reported by real hardware.
+const MAX_MAX_TEXTURE_SIZE: u32 = 65536;
+
+/// Operating system family claimed by a user agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClaimedOs {
+    Windows,
+    MacOs,
+    Ios,
+    Android,
+    ChromeOs,
+    Linux,
+}
+
+/// Renderer substrings and the operating systems they can appear on.
+const RENDERER_PLATFORMS: &[(&str, &[ClaimedOs])] = &[
+    ("direct3d", &[ClaimedOs::Windows]),
+    ("d3d11", &[ClaimedOs::Windows]),
+    ("d3d9", &[ClaimedOs::Windows]),
+    ("metal", &[ClaimedOs::MacOs, ClaimedOs::Ios]),
+    ("apple m", &[ClaimedOs::MacOs, ClaimedOs::Ios]),
+    ("apple gpu", &[ClaimedOs::MacOs, ClaimedOs::Ios]),
+    (
+        "adreno",
+        &[ClaimedOs::Android, ClaimedOs::ChromeOs, ClaimedOs::Windows],
+    ),
+    (
+        "mali",
+        &[ClaimedOs::Android, ClaimedOs::ChromeOs, ClaimedOs::Linux],
+    ),
+    ("xclipse", &[ClaimedOs::Android]),
+    (
+        "powervr",
+        &[ClaimedOs::Android, ClaimedOs::Ios, ClaimedOs::ChromeOs],
+    ),
+];
+
+/// GPU vendor names and the substrings identifying them.
+const GPU_BRANDS: &[(&str, &[&str])] = &[
+    ("nvidia", &["nvidia", "geforce", "quadro"]),
+    ("amd", &["amd", "radeon", "ati technologies"]),
+    ("intel", &["intel"]),
+    ("apple", &["apple"]),
+    ("qualcomm", &["qualcomm", "adreno"]),
+    ("arm", &["(arm", "mali"]),
+];
+
+/// Detects WebGL parameters inconsistent with the claimed platform.
+pub struct WebGlDetector;
+
+impl WebGlDetector {
+    /// Check WebGL parameters against the user agent.
+    ///
+    /// # Findings
+    ///
+    /// - `webgl_os_mismatch` (High): renderer cannot exist on the claimed OS
+    /// - `software_webgl_renderer` (Medium): SwiftShader/llvmpipe rendering
+    /// - `webgl_vendor_renderer_mismatch` (Medium): vendor and renderer name
+    ///   different GPU makers
+    /// - `implausible_max_texture_size` (Medium): not a power of two or out
+    ///   of the range real GPUs report
+    pub fn detect(browser: &BrowserSignals) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let Some(webgl) = &browser.webgl else {
+            return findings;
+        };
+
+        if let Some(renderer) = &webgl.unmasked_renderer {
+            Self::check_renderer(renderer, &browser.user_agent, &mut findings);
+        }
+        Self::check_vendor(webgl, &mut findings);
+
+        if let Some(size) = webgl.max_texture_size {
+            if !size.is_power_of_two()
+                || !(MIN_MAX_TEXTURE_SIZE..=MAX_MAX_TEXTURE_SIZE).contains(&size)
+            {
+                findings.push(Finding::new(
+                    "implausible_max_texture_size",
+                    Severity::Medium,
+                    format!("MAX_TEXTURE_SIZE {} is not reported by real GPUs", size),
+                ));
+            }
+        }
+
+        findings
+    }
+
+    /// Check the renderer against the user agent's operating system.
+    fn check_renderer(renderer: &str, user_agent: &str, findings: &mut Vec<Finding>) {
+        if is_software_renderer(renderer) {
+            findings.push(Finding::new(
+                "software_webgl_renderer",
+                Severity::Medium,
+                format!("WebGL renders in software ({})", renderer),
+            ));
+        }
+
+        let Some(os) = claimed_os(user_agent) else {
+            return;
+        };
+        let lower = renderer.to_ascii_lowercase();
+        let conflict = RENDERER_PLATFORMS
+            .iter()
+            .find(|(hint, platforms)| lower.contains(hint) && !platforms.contains(&os));
+        if let Some((hint, _)) = conflict {
+            findings.push(Finding::new(
+                "webgl_os_mismatch",
+                Severity::High,
+                format!(
+                    "renderer '{}' ({}) cannot run on claimed OS {:?}",
+                    renderer, hint, os
+                ),
+            ));
+        }
+    }
+
+    /// Check that vendor and renderer name the same GPU maker.
+    fn check_vendor(webgl: &WebGlInfo, findings: &mut Vec<Finding>) {
+        let (Some(vendor), Some(renderer)) = (&webgl.unmasked_vendor, &webgl.unmasked_renderer)
+        else {
+            return;
+        };
+        if let (Some(vendor_brand), Some(renderer_brand)) = (gpu_brand(vendor), gpu_brand(renderer))
+        {
+            if vendor_brand != renderer_brand {
+                findings.push(Finding::new(
+                    "webgl_vendor_renderer_mismatch",
+                    Severity::Medium,
+                    format!(
+                        "vendor '{}' ({}) does not match renderer '{}' ({})",
+                        vendor, vendor_brand, renderer, renderer_brand
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+/// Check whether a renderer string names a software rasterizer.
+pub(crate) fn is_software_renderer(renderer: &str) -> bool {
+    let lower = renderer.to_ascii_lowercase();
+    SOFTWARE_RENDERERS.iter().any(|r| lower.contains(r))
+}
+
+/// Determine the operating system family claimed by a user agent.
+fn claimed_os(user_agent: &str) -> Option<ClaimedOs> {
+    // Order matters: iOS UAs contain "Mac OS X", Android UAs contain "Linux".
+    if user_agent.contains("iPhone") || user_agent.contains("iPad") {
+        Some(ClaimedOs::Ios)
+    } else if user_agent.contains("Android") {
+        Some(ClaimedOs::Android)
+    } else if user_agent.contains("CrOS") {
+        Some(ClaimedOs::ChromeOs)
+    } else if user_agent.contains("Windows") {
+        Some(ClaimedOs::Windows)
+    } else if user_agent.contains("Macintosh") || user_agent.contains("Mac OS X") {
+        Some(ClaimedOs::MacOs)
+    } else if user_agent.contains("Linux") {
+        Some(ClaimedOs::Linux)
+    } else {
+        None
+    }
+}
+
+/// Identify the GPU maker named in a vendor or renderer string.
+///
+/// ANGLE renderers embed the vendor (e.g., "ANGLE (NVIDIA, ...)"), so the
+/// first maker found wins.
+fn gpu_brand(text: &str) -> Option<&'static str> {
+    let lower = text.to_ascii_lowercase();
+    GPU_BRANDS
+        .iter()
+        .filter_map(|(brand, needles)| {
+            needles
+                .iter()
+                .filter_map(|n| lower.find(n))
+                .min()
+                .map(|pos| (pos, *brand))
+        })
+        .min()
+        .map(|(_, brand)| brand)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scrybe_core::test_util;
+
+    const WINDOWS_UA: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+                              (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+    const MAC_UA: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 \
+                          (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+    fn browser(user_agent: &str, vendor: &str, renderer: &str) -> BrowserSignals {
+        BrowserSignals {
+            webgl: Some(WebGlInfo {
+                unmasked_vendor: Some(vendor.to_string()),
+                unmasked_renderer: Some(renderer.to_string()),
+                max_texture_size: Some(16384),
+                extensions: vec![],
+                shader_precisions: vec![],
+            }),
+            user_agent: user_agent.to_string(),
+            ..test_util::browser_signals()
+        }
+    }
+
+    fn names(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_consistent_windows_renderer() {
+        let signals = browser(
+            WINDOWS_UA,
+            "Google Inc. (NVIDIA)",
+            "ANGLE (NVIDIA, NVIDIA GeForce RTX 3060 Direct3D11 vs_5_0 ps_5_0, D3D11)",
+        );
+        assert!(WebGlDetector::detect(&signals).is_empty());
+    }
+
+    #[test]
+    fn test_direct3d_on_mac_is_mismatch() {
+        let signals = browser(
+            MAC_UA,
+            "Google Inc. (NVIDIA)",
+            "ANGLE (NVIDIA, NVIDIA GeForce RTX 3060 Direct3D11 vs_5_0 ps_5_0, D3D11)",
+        );
+        assert_eq!(
+            names(&WebGlDetector::detect(&signals)),
+            vec!["webgl_os_mismatch"]
+        );
+    }
+
+    #[test]
+    fn test_apple_gpu_on_windows_is_mismatch() {
+        let signals = browser(
+            WINDOWS_UA,
+            "Google Inc. (Apple)",
+            "ANGLE (Apple, ANGLE Metal Renderer: Apple M2, Unspecified Version)",
+        );
+        assert_eq!(
+            names(&WebGlDetector::detect(&signals)),
+            vec!["webgl_os_mismatch"]
+        );
+    }
+
+    #[test]
+    fn test_software_renderer() {
+        let signals = browser(
+            WINDOWS_UA,
+            "Google Inc. (Google)",
+            "ANGLE (Google, Vulkan 1.3.0 (SwiftShader Device (Subzero)), SwiftShader driver)",
+        );
+        assert_eq!(
+            names(&WebGlDetector::detect(&signals)),
+            vec!["software_webgl_renderer"]
+        );
+    }
+
+    #[test]
+    fn test_vendor_renderer_mismatch() {
+        let signals = browser(
+            WINDOWS_UA,
+            "Google Inc. (Intel)",
+            "ANGLE (NVIDIA, NVIDIA GeForce GTX 1080 Direct3D11 vs_5_0 ps_5_0, D3D11)",
+        );
+        assert_eq!(
+            names(&WebGlDetector::detect(&signals)),
+            vec!["webgl_vendor_renderer_mismatch"]
+        );
+    }
+
+    #[test]
+    fn test_implausible_max_texture_size() {
+        let mut signals = browser(WINDOWS_UA, "Google Inc. (Intel)", "ANGLE (Intel, D3D11)");
+        if let Some(webgl) = signals.webgl.as_mut() {
+            webgl.max_texture_size = Some(10000);
+        }
+        assert_eq!(
+            names(&WebGlDetector::detect(&signals)),
+            vec!["implausible_max_texture_size"]
+        );
+    }
+
+    #[test]
+    fn test_claimed_os() {
+        assert_eq!(claimed_os(WINDOWS_UA), Some(ClaimedOs::Windows));
+        assert_eq!(claimed_os(MAC_UA), Some(ClaimedOs::MacOs));
+        assert_eq!(
+            claimed_os("Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X)"),
+            Some(ClaimedOs::Ios)
+        );
+        assert_eq!(
+            claimed_os("Mozilla/5.0 (Linux; Android 14; Pixel 8)"),
+            Some(ClaimedOs::Android)
+        );
+        assert_eq!(claimed_os("curl/8.0"), None);
+    }
+}
@@ -0,0 +1,177 @@
+#!/usr/bin/env python3
+"""Generate the tiny MaxMind DB fixtures used by the GeoIP tests.
+
+The fixtures are checked in; rerun this script only when the test data
+below changes:
+
+    python3 crates/scrybe-enrichment/tests/fixtures/geo/generate.py
+
+Only the subset of the MMDB v2 format needed by the fixtures is
+implemented (24-bit records, IPv6 tree, maps/strings/uints/doubles).
+"""
+
+import ipaddress
+import os
+import struct
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+METADATA_MARKER = b"\xab\xcd\xefMaxMind.com"
+BUILD_EPOCH = 1735689600  # 2025-01-01, fixed for reproducible output
+
+CITY = {
+    "81.2.69.0/24": {
+        "city": {"names": {"en": "London"}},
+        "country": {"iso_code": "GB", "names": {"en": "United Kingdom"}},
+        "location": {
+            "latitude": 51.5142,
+            "longitude": -0.0931,
+            "time_zone": "Europe/London",
+        },
+    },
+    "89.160.20.112/28": {
+        "city": {"names": {"en": "Linköping"}},
+        "country": {"iso_code": "SE", "names": {"en": "Sweden"}},
+        "location": {
+            "latitude": 58.4167,
+            "longitude": 15.6167,
+            "time_zone": "Europe/Stockholm",
+        },
+    },
+    "2001:480::/32": {
+        "city": {"names": {"en": "San Diego"}},
+        "country": {"iso_code": "US", "names": {"en": "United States"}},
+        "location": {
+            "latitude": 32.7203,
+            "longitude": -117.1552,
+            "time_zone": "America/Los_Angeles",
+        },
+    },
+}
+
+ASN = {
+    "1.128.0.0/11": {
+        "autonomous_system_number": 1221,
+        "autonomous_system_organization": "Telstra Pty Ltd",
+    },
+    "81.2.69.0/24": {
+        "autonomous_system_number": 20712,
+        "autonomous_system_organization": "Andrews & Arnold Ltd",
+    },
+    "2001:480::/32": {
+        "autonomous_system_number": 6939,
+        "autonomous_system_organization": "Hurricane Electric LLC",
+    },
+}
+
+
+def encode_size(type_bits, size):
+    if size < 29:
+        return bytes([type_bits | size])
+    if size < 285:
+        return bytes([type_bits | 29, size - 29])
+    if size < 65821:
+        return bytes([type_bits | 30]) + struct.pack(">H", size - 285)
+    return bytes([type_bits | 31]) + struct.pack(">I", size - 65821)[1:]
+
+
+def encode_control(type_id, size):
+    if type_id <= 7:
+        return encode_size(type_id << 5, size)
+    head = encode_size(0, size)
+    return head[:1] + bytes([type_id - 7]) + head[1:]
+
+
+def encode(value):
+    if isinstance(value, str):
+        raw = value.encode("utf-8")
+        return encode_control(2, len(raw)) + raw
+    if isinstance(value, float):
+        return encode_control(3, 8) + struct.pack(">d", value)
+    if isinstance(value, int):
+        raw = value.to_bytes(8, "big").lstrip(b"\x00")
+        type_id = 6 if value < 2**32 else 9
+        return encode_control(type_id, len(raw)) + raw
+    if isinstance(value, dict):
+        out = encode_control(7, len(value))
+        for key, item in value.items():
+            out += encode(key) + encode(item)
+        return out
+    if isinstance(value, list):
+        out = encode_control(11, len(value))
+        for item in value:
+            out += encode(item)
+        return out
+    raise TypeError(type(value))
+
+
+def uint16(value):
+    return encode_control(5, 2) + struct.pack(">H", value)
+
+
+def network_bits(cidr):
+    network = ipaddress.ip_network(cidr)
+    if network.version == 4:
+        address = int(network.network_address)
+        return [(address >> (31 - i)) & 1 for i in range(network.prefixlen)], 96
+    address = int(network.network_address)
+    return [(address >> (127 - i)) & 1 for i in range(network.prefixlen)], 0
+
+
+def build(records, database_type):
+    data = b""
+    nodes = [[None, None]]
+    for cidr, record in records.items():
+        offset = len(data)
+        data += encode(record)
+        bits, zero_prefix = network_bits(cidr)
+        path = [0] * zero_prefix + bits
+        node = 0
+        for bit in path[:-1]:
+            child = nodes[node][bit]
+            if not isinstance(child, int):
+                nodes.append([None, None])
+                child = len(nodes) - 1
+                nodes[node][bit] = child
+            node = child
+        nodes[node][path[-1]] = ("data", offset)
+
+    node_count = len(nodes)
+
+    def record_value(entry):
+        if entry is None:
+            return node_count
+        if isinstance(entry, int):
+            return entry
+        return node_count + 16 + entry[1]
+
+    tree = b""
+    for left, right in nodes:
+        tree += record_value(left).to_bytes(3, "big")
+        tree += record_value(right).to_bytes(3, "big")
+
+    metadata = encode_control(7, 9)
+    metadata += encode("binary_format_major_version") + uint16(2)
+    metadata += encode("binary_format_minor_version") + uint16(0)
+    metadata += encode("build_epoch") + encode(BUILD_EPOCH)
+    metadata += encode("database_type") + encode(database_type)
+    metadata += encode("description") + encode({"en": "Scrybe test fixture"})
+    metadata += encode("ip_version") + uint16(6)
+    metadata += encode("languages") + encode(["en"])
+    metadata += encode("node_count") + encode(node_count)
+    metadata += encode("record_size") + uint16(24)
+
+    return tree + b"\x00" * 16 + data + METADATA_MARKER + metadata
+
+
+def main():
+    outputs = {
+        "GeoLite2-City-Test.mmdb": build(CITY, "GeoLite2-City"),
+        "GeoLite2-ASN-Test.mmdb": build(ASN, "GeoLite2-ASN"),
+    }
+    for name, content in outputs.items():
+        with open(os.path.join(HERE, name), "wb") as handle:
+            handle.write(content)
+
+
+if __name__ == "__main__":
+    main()
@@ -29,7 +29,7 @@ pub fn extract_headers(headers: &HeaderMap) -> Vec<Header> {
     for header_name in &capture_headers {
         if let Some(value) = headers.get(*header_name) {
             if let Ok(value_str) = value.to_str() {
-                result.push(Header::new(*header_name, value_str));
+                result.push(Header::observed(*header_name, value_str));
             } else {
                 warn!("Failed to parse header: {}", header_name);
             }
@@ -64,6 +64,7 @@ mod tests {
         let extracted = extract_headers(&headers);
 
         assert_eq!(extracted.len(), 2);
+        assert!(extracted.iter().all(|h| h.observed));
         assert!(extracted.iter().any(|h| h.name == "user-agent"));
         assert!(extracted.iter().any(|h| h.name == "accept-language"));
     }
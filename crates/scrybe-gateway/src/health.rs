@@ -1,6 +1,9 @@
 //! Health check endpoints for liveness and readiness probes.
 
-use axum::http::StatusCode;
+use crate::routes::ingest::AppState;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use std::sync::Arc;
+use tracing::warn;
 
 /// Liveness probe - always returns OK if the process is running.
 ///
@@ -12,23 +15,40 @@ pub async fn health_check() -> StatusCode {
 
 /// Readiness probe - checks if the service is ready to accept traffic.
 ///
-/// This would typically check database connections, cache availability,
-/// etc. For now, it returns OK immediately.
+/// Pings Redis when it is configured and reports its connection pool
+/// usage (`in_use`, `idle`, `waiters`) under `redis_pool`.
 ///
 /// # Returns
 ///
 /// - `200 OK`: Service is ready
 /// - `503 Service Unavailable`: Service is not ready
-pub async fn readiness_check() -> StatusCode {
-    // TODO: Add actual readiness checks
-    // - Redis connectivity
-    // - ClickHouse connectivity
-    StatusCode::OK
+pub async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    // TODO: Add ClickHouse connectivity once the gateway writes to it
+    let Some(redis) = &state.redis else {
+        return (
+            StatusCode::OK,
+            Json(serde_json::json!({ "redis_pool": null })),
+        );
+    };
+
+    let status = match redis.health_check().await {
+        Ok(()) => StatusCode::OK,
+        Err(e) => {
+            warn!("Readiness check failed: {}", e);
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    };
+
+    (
+        status,
+        Json(serde_json::json!({ "redis_pool": redis.pool_metrics() })),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::session_token::SessionSigner;
 
     #[tokio::test]
     async fn test_health_check_returns_ok() {
@@ -38,7 +58,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_readiness_check_returns_ok() {
-        let status = readiness_check().await;
-        assert_eq!(status, StatusCode::OK);
+        let state = Arc::new(AppState::new(SessionSigner::new(b"test-key").unwrap()));
+        let response = readiness_check(State(state)).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
     }
 }
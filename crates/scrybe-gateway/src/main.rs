@@ -7,6 +7,9 @@
 //! - HMAC-SHA256 authentication
 //! - Rate limiting
 //! - Health check endpoints
+//! - Enrichment queueing via Redis Streams
+//! - Session stitching across page loads (signed session tokens)
+//! - Live anomaly feed over Server-Sent Events
 //! - Graceful shutdown
 //!
 //! ## TigerStyle Compliance
@@ -23,16 +26,23 @@ mod extraction;
 mod health;
 mod middleware;
 mod routes;
+mod session_token;
 mod shutdown;
 mod state;
 
 use axum::{routing::get, Router};
-use routes::ingest::AppState;
-use scrybe_core::{Config, ScrybeError};
+use routes::{
+    anomalies::{StreamAccess, StreamShutdown},
+    ingest::AppState,
+};
+use scrybe_cache::{KeySpace, RedisClient, RedisPoolConfig, RedisTopology};
+use scrybe_core::{Config, ScrybeError, SecretConfig};
+use session_token::SessionSigner;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use tokio::sync::watch;
 use tower_http::{cors::CorsLayer, trace::TraceLayer};
-use tracing::info;
+use tracing::{info, warn};
 
 #[tokio::main]
 async fn main() -> Result<(), ScrybeError> {
@@ -53,22 +63,60 @@ async fn main() -> Result<(), ScrybeError> {
     info!("Gateway listening on {}", addr);
 
     // Create application state
-    let state = Arc::new(AppState::new());
-
-    // Configure CORS
-    let cors = CorsLayer::permissive(); // Allow all origins in dev mode
+    let signer = SessionSigner::from_env()?;
+    let state = Arc::new(match RedisTopology::from_env()? {
+        Some(topology) => {
+            let pool_config = RedisPoolConfig::from_env()?;
+            let key_space = KeySpace::from_env()?;
+            info!(
+                "Redis key space: environment {}, tenant {}",
+                key_space.environment(),
+                key_space.tenant()
+            );
+            let redis_client = RedisClient::connect(&topology, pool_config)
+                .await?
+                .with_key_space(key_space);
+            let ip_salt = SecretConfig::ip_hash_salt()?;
+            info!("Session cache, enrichment queue, velocity counters and anomaly feed enabled");
+            AppState::with_redis(signer, redis_client, ip_salt.into_inner().into_bytes())
+        }
+        None => {
+            warn!("Redis not configured, sessions will not be stitched or enqueued");
+            AppState::new(signer)
+        }
+    });
+
+    // SDK routes accept any origin in dev mode
+    let sdk_routes = Router::new()
+        .merge(routes::ingest_route())
+        .merge(routes::events_route())
+        .layer(CorsLayer::permissive());
 
     // Build router with all routes and middleware
-    let app = Router::new()
+    let mut app = Router::new()
         // Health check routes (no authentication required)
         .route("/health", get(health::health_check))
         .route("/health/ready", get(health::readiness_check))
-        // API routes (with authentication and rate limiting)
-        .merge(routes::ingest_route())
+        .merge(sdk_routes);
+
+    // Open streams never finish on their own, so they end on shutdown
+    let (stopping, stopping_rx) = watch::channel(false);
+
+    // The anomaly feed is only served with a dashboard token
+    match StreamAccess::from_env()? {
+        Some(access) => {
+            app = app.merge(routes::anomalies_route(
+                access,
+                StreamShutdown(stopping_rx.clone()),
+            ))
+        }
+        None => warn!("SCRYBE_DASHBOARD_TOKEN not set, anomaly stream disabled"),
+    }
+
+    let app = app
         // Global middleware
-        .layer(cors)
         .layer(axum::middleware::from_fn(middleware::security_headers))
-        .layer(TraceLayer::new_for_http())
+        .layer(TraceLayer::new_for_http().make_span_with(middleware::request_span))
         .with_state(state);
 
     // Create server with graceful shutdown
@@ -80,15 +128,35 @@ async fn main() -> Result<(), ScrybeError> {
     info!("  GET  /health - Liveness probe");
     info!("  GET  /health/ready - Readiness probe");
     info!("  POST /api/v1/ingest - Ingest browser telemetry");
+    info!("  POST /api/v1/sessions/:id/events - Append behavioral events");
+    info!("  GET  /api/v1/anomalies/stream - Live anomaly feed (SSE)");
 
     info!("Gateway ready to accept connections");
     info!("Security: HMAC-SHA256 authentication enabled");
     info!("Rate limit: 100 requests/minute per IP");
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown::shutdown_signal())
-        .await
-        .map_err(|e| ScrybeError::io_error("serve", e.to_string()))?;
+    let server = axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown::shutdown_signal().await;
+        let _ = stopping.send(true);
+    });
+
+    // Graceful shutdown waits for every connection; give up on the ones
+    // still open after the timeout
+    let mut stopped = stopping_rx;
+    tokio::select! {
+        result = server => result.map_err(|e| ScrybeError::io_error("serve", e.to_string()))?,
+        _ = async {
+            let _ = stopped.wait_for(|stopping| *stopping).await;
+            tokio::time::sleep(shutdown::SHUTDOWN_TIMEOUT).await;
+        } => warn!(
+            "Connections still open after {:?}, shutting down",
+            shutdown::SHUTDOWN_TIMEOUT
+        ),
+    }
 
     info!("Gateway shutdown complete");
 
@@ -6,6 +6,7 @@
 pub mod auth;
 pub mod rate_limit;
 pub mod security;
+pub mod trace;
 
 // Ready for integration (allow unused until wired up)
 #[allow(unused_imports)]
@@ -13,3 +14,4 @@ pub use auth::hmac_auth;
 #[allow(unused_imports)]
 pub use rate_limit::rate_limit_layer;
 pub use security::security_headers;
+pub use trace::request_span;
@@ -0,0 +1,62 @@
+//! Request tracing.
+
+use axum::http::Request;
+use tracing::Span;
+
+/// Create the span of a request for `TraceLayer`.
+///
+/// Records the path instead of the full URI: the query string may carry
+/// the dashboard token, which must not end up in logs.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    tracing::debug_span!(
+        "request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        version = ?request.version(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    /// Log sink shared with the test.
+    #[derive(Clone, Default)]
+    struct Logs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_span_omits_query_string() {
+        let logs = Logs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        let request = Request::get("/api/v1/anomalies/stream?token=secret-dashboard-token")
+            .body(())
+            .unwrap();
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = request_span(&request).entered();
+            tracing::info!("started processing request");
+        });
+
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("path=/api/v1/anomalies/stream"));
+        assert!(!logs.contains("secret-dashboard-token"));
+    }
+}
@@ -0,0 +1,494 @@
+//! Live anomaly feed streamed as Server-Sent Events.
+//!
+//! The feed reveals which detection rules caught which sessions, so it is
+//! only served to holders of the dashboard token, only to the dashboard's
+//! origin, and to a bounded number of concurrent streams.
+
+use super::ingest::{AppError, AppState};
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, Method},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream};
+use scrybe_cache::{Anomaly, AnomalyFeed};
+use scrybe_core::{types::SessionId, ScrybeError};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::convert::Infallible;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+use tower_http::cors::CorsLayer;
+use tracing::warn;
+
+/// How often the feed is polled for new anomalies.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Recent anomalies sent when a client connects.
+const BACKLOG_SIZE: usize = 50;
+
+/// Maximum anomalies read per poll (DoS protection).
+const MAX_POLL_SIZE: usize = 500;
+
+/// Default number of concurrent streams (DoS protection: every stream polls
+/// Redis once per [`POLL_INTERVAL`]).
+pub const DEFAULT_MAX_STREAMS: usize = 16;
+
+/// Shortest accepted dashboard token.
+pub const MIN_TOKEN_LENGTH: usize = 32;
+
+/// Who may open the anomaly stream, and how many at once.
+#[derive(Clone)]
+pub struct StreamAccess {
+    token_digest: [u8; 32],
+    origin: Option<HeaderValue>,
+    max_streams: usize,
+    streams: Arc<Semaphore>,
+}
+
+impl StreamAccess {
+    /// Create access rules for the stream.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - Dashboard token, at least [`MIN_TOKEN_LENGTH`] characters
+    /// * `origin` - Dashboard origin allowed by CORS (default: same-origin only)
+    /// * `max_streams` - Concurrent streams (default: [`DEFAULT_MAX_STREAMS`])
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the token is too short, the
+    /// origin is not a valid header value, or `max_streams` is zero.
+    pub fn new(
+        token: &str,
+        origin: Option<&str>,
+        max_streams: Option<usize>,
+    ) -> Result<Self, ScrybeError> {
+        if token.len() < MIN_TOKEN_LENGTH {
+            return Err(ScrybeError::config_error(format!(
+                "Dashboard token must be at least {} characters",
+                MIN_TOKEN_LENGTH
+            )));
+        }
+        let origin = origin
+            .map(|origin| {
+                HeaderValue::from_str(origin).map_err(|e| {
+                    ScrybeError::config_error(format!("Invalid dashboard origin: {}", e))
+                })
+            })
+            .transpose()?;
+        let max_streams = max_streams.unwrap_or(DEFAULT_MAX_STREAMS);
+        if max_streams == 0 {
+            return Err(ScrybeError::config_error(
+                "Maximum anomaly streams must be at least 1",
+            ));
+        }
+
+        Ok(Self {
+            token_digest: Sha256::digest(token.as_bytes()).into(),
+            origin,
+            max_streams,
+            streams: Arc::new(Semaphore::new(max_streams)),
+        })
+    }
+
+    /// Load access rules from `SCRYBE_DASHBOARD_TOKEN`,
+    /// `SCRYBE_DASHBOARD_ORIGIN` and `SCRYBE_DASHBOARD_MAX_STREAMS`.
+    ///
+    /// Returns `None` if no token is set, in which case the stream is not
+    /// served.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a setting is invalid.
+    pub fn from_env() -> Result<Option<Self>, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load access rules from a variable lookup.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, ScrybeError> {
+        let Some(token) = var("SCRYBE_DASHBOARD_TOKEN") else {
+            return Ok(None);
+        };
+        let max_streams = var("SCRYBE_DASHBOARD_MAX_STREAMS")
+            .map(|value| {
+                value.parse().map_err(|e| {
+                    ScrybeError::config_error(format!(
+                        "Invalid SCRYBE_DASHBOARD_MAX_STREAMS: {}",
+                        e
+                    ))
+                })
+            })
+            .transpose()?;
+
+        Self::new(
+            &token,
+            var("SCRYBE_DASHBOARD_ORIGIN").as_deref(),
+            max_streams,
+        )
+        .map(Some)
+    }
+
+    /// Check the token from `Authorization: Bearer`, or from the `token`
+    /// query parameter for `EventSource` clients, which cannot set headers.
+    fn authorize(&self, headers: &HeaderMap, query_token: Option<&str>) -> Result<(), ScrybeError> {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let Some(token) = bearer.or(query_token) else {
+            return Err(ScrybeError::authentication_error("Missing dashboard token"));
+        };
+
+        // Compare digests so the comparison time does not depend on length
+        let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        if bool::from(digest.ct_eq(&self.token_digest)) {
+            Ok(())
+        } else {
+            Err(ScrybeError::authentication_error("Invalid dashboard token"))
+        }
+    }
+
+    /// Reserve one of the stream slots, held until the stream is dropped.
+    fn acquire(&self) -> Result<OwnedSemaphorePermit, ScrybeError> {
+        Arc::clone(&self.streams)
+            .try_acquire_owned()
+            .map_err(|_| ScrybeError::rate_limit(self.max_streams as u32, "concurrent streams"))
+    }
+
+    /// CORS rules admitting only the dashboard origin.
+    fn cors(&self) -> CorsLayer {
+        let cors = CorsLayer::new()
+            .allow_methods([Method::GET])
+            .allow_headers([header::AUTHORIZATION]);
+        match &self.origin {
+            Some(origin) => cors.allow_origin(origin.clone()),
+            None => cors,
+        }
+    }
+}
+
+/// Query parameters of the stream.
+#[derive(Debug, Default, Deserialize)]
+pub struct StreamParams {
+    /// Dashboard token, for clients that cannot send `Authorization`
+    token: Option<String>,
+}
+
+/// Position in the feed: the latest detection time delivered, and the
+/// sessions already delivered at exactly that time.
+#[derive(Debug)]
+struct FeedCursor {
+    at: DateTime<Utc>,
+    delivered: HashSet<SessionId>,
+}
+
+impl FeedCursor {
+    /// Start at `at` with nothing delivered.
+    fn new(at: DateTime<Utc>) -> Self {
+        Self {
+            at,
+            delivered: HashSet::new(),
+        }
+    }
+
+    /// Filter anomalies (oldest first, detected at or after the cursor)
+    /// down to those not yet delivered, and advance past them.
+    fn advance(&mut self, anomalies: Vec<Anomaly>) -> Vec<Anomaly> {
+        let mut fresh = Vec::with_capacity(anomalies.len());
+        for anomaly in anomalies {
+            if anomaly.detected_at < self.at
+                || (anomaly.detected_at == self.at && self.delivered.contains(&anomaly.session_id))
+            {
+                continue;
+            }
+
+            if anomaly.detected_at > self.at {
+                self.at = anomaly.detected_at;
+                self.delivered.clear();
+            }
+            self.delivered.insert(anomaly.session_id);
+            fresh.push(anomaly);
+        }
+        fresh
+    }
+}
+
+/// Set to `true` when the gateway shuts down, ending every open stream.
+#[derive(Clone)]
+pub struct StreamShutdown(pub watch::Receiver<bool>);
+
+impl StreamShutdown {
+    /// Wait one poll interval.
+    ///
+    /// Returns `false` instead if the gateway starts shutting down first.
+    async fn wait_poll(&mut self) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => true,
+            // A dropped sender means the server is gone as well
+            _ = self.0.wait_for(|stopping| *stopping) => false,
+        }
+    }
+}
+
+/// State carried between items of the event stream.
+struct FeedStream {
+    feed: AnomalyFeed,
+    cursor: FeedCursor,
+    pending: VecDeque<Anomaly>,
+    shutdown: StreamShutdown,
+    /// Stream slot, released when the client disconnects
+    _permit: OwnedSemaphorePermit,
+}
+
+/// GET /api/v1/anomalies/stream - Stream anomalies as Server-Sent Events.
+///
+/// Sends the most recent anomalies on connect, then every new anomaly as
+/// an `anomaly` event whose data is the JSON-encoded anomaly. The stream
+/// ends when the gateway shuts down.
+///
+/// # Errors
+///
+/// - `401 Unauthorized`: Missing or invalid dashboard token
+/// - `429 Too Many Requests`: All stream slots in use
+/// - `503 Service Unavailable`: Anomaly feed unavailable
+pub async fn anomaly_stream_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(access): Extension<StreamAccess>,
+    Extension(shutdown): Extension<StreamShutdown>,
+    headers: HeaderMap,
+    Query(params): Query<StreamParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    access.authorize(&headers, params.token.as_deref())?;
+
+    let Some(feed) = state.anomalies.clone() else {
+        return Err(ScrybeError::cache_error("anomaly_feed", "Anomaly feed not configured").into());
+    };
+    let permit = access.acquire()?;
+
+    let mut backlog = feed.recent(BACKLOG_SIZE).await?;
+    backlog.reverse();
+
+    let mut cursor = FeedCursor::new(Utc::now());
+    if let Some(oldest) = backlog.first() {
+        cursor.at = oldest.detected_at;
+    }
+    let pending = cursor.advance(backlog).into();
+
+    let state = FeedStream {
+        feed,
+        cursor,
+        pending,
+        shutdown,
+        _permit: permit,
+    };
+
+    let stream = stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(anomaly) = state.pending.pop_front() {
+                return Some((Ok(to_event(&anomaly)), state));
+            }
+
+            if !state.shutdown.wait_poll().await {
+                return None;
+            }
+            match state.feed.since(state.cursor.at, MAX_POLL_SIZE).await {
+                Ok(anomalies) => state.pending.extend(state.cursor.advance(anomalies)),
+                Err(e) => warn!("Anomaly feed poll failed: {}", e),
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Encode an anomaly as an SSE event.
+fn to_event(anomaly: &Anomaly) -> Event {
+    let event = Event::default()
+        .event("anomaly")
+        .id(anomaly.detected_at.timestamp_millis().to_string());
+    match event.clone().json_data(anomaly) {
+        Ok(event) => event,
+        Err(e) => {
+            warn!("Failed to encode anomaly {}: {}", anomaly.session_id, e);
+            event.comment("encoding failed")
+        }
+    }
+}
+
+/// Create the anomaly feed route, restricted by `access`, whose streams end
+/// on `shutdown`.
+pub fn anomalies_route(
+    access: StreamAccess,
+    shutdown: StreamShutdown,
+) -> axum::Router<Arc<AppState>> {
+    use axum::routing::get;
+
+    let cors = access.cors();
+    axum::Router::new()
+        .route("/api/v1/anomalies/stream", get(anomaly_stream_handler))
+        .layer(Extension(access))
+        .layer(Extension(shutdown))
+        .layer(cors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anomaly(detected_at: DateTime<Utc>) -> Anomaly {
+        Anomaly {
+            session_id: SessionId::new(),
+            fingerprint_hash: None,
+            score: 0.9,
+            rules: vec![],
+            detected_at,
+        }
+    }
+
+    #[test]
+    fn test_cursor_skips_delivered_anomalies() {
+        let now = Utc::now();
+        let first = anomaly(now);
+        let mut cursor = FeedCursor::new(now);
+
+        assert_eq!(cursor.advance(vec![first.clone()]).len(), 1);
+
+        // Polls include the cursor time, so the first anomaly comes back
+        let second = anomaly(now);
+        let fresh = cursor.advance(vec![first, second.clone()]);
+        assert_eq!(fresh, vec![second]);
+    }
+
+    #[test]
+    fn test_cursor_advances_to_latest() {
+        let now = Utc::now();
+        let later = anomaly(now + chrono::Duration::milliseconds(5));
+        let mut cursor = FeedCursor::new(now);
+
+        assert_eq!(cursor.advance(vec![anomaly(now), later.clone()]).len(), 2);
+        assert_eq!(cursor.at, later.detected_at);
+        assert!(cursor.advance(vec![later]).is_empty());
+    }
+
+    const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+
+    async fn status(
+        state: Arc<AppState>,
+        access: &StreamAccess,
+        headers: HeaderMap,
+        token: Option<&str>,
+    ) -> axum::http::StatusCode {
+        use axum::response::IntoResponse;
+
+        let params = StreamParams {
+            token: token.map(str::to_string),
+        };
+        match anomaly_stream_handler(
+            State(state),
+            Extension(access.clone()),
+            Extension(StreamShutdown(watch::channel(false).1)),
+            headers,
+            Query(params),
+        )
+        .await
+        {
+            Ok(response) => response.into_response().status(),
+            Err(e) => e.into_response().status(),
+        }
+    }
+
+    fn state() -> Arc<AppState> {
+        use crate::session_token::SessionSigner;
+
+        Arc::new(AppState::new(SessionSigner::new(b"test-key").unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_requires_dashboard_token() {
+        use axum::http::StatusCode;
+
+        let access = StreamAccess::new(TOKEN, None, None).unwrap();
+
+        let missing = status(state(), &access, HeaderMap::new(), None).await;
+        assert_eq!(missing, StatusCode::UNAUTHORIZED);
+
+        let wrong = status(
+            state(),
+            &access,
+            HeaderMap::new(),
+            Some("x".repeat(32).as_str()),
+        )
+        .await;
+        assert_eq!(wrong, StatusCode::UNAUTHORIZED);
+
+        // Authorized, but this state has no feed
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", TOKEN)).unwrap(),
+        );
+        let bearer = status(state(), &access, headers, None).await;
+        assert_eq!(bearer, StatusCode::SERVICE_UNAVAILABLE);
+
+        let query = status(state(), &access, HeaderMap::new(), Some(TOKEN)).await;
+        assert_eq!(query, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_poll_wait_ends_on_shutdown() {
+        let (stop, receiver) = watch::channel(false);
+        let mut shutdown = StreamShutdown(receiver);
+        assert!(shutdown.wait_poll().await);
+
+        stop.send(true).unwrap();
+        assert!(!shutdown.wait_poll().await);
+    }
+
+    #[test]
+    fn test_stream_slots_are_bounded() {
+        let access = StreamAccess::new(TOKEN, None, Some(2)).unwrap();
+
+        let first = access.acquire().unwrap();
+        let _second = access.acquire().unwrap();
+        assert!(matches!(
+            access.acquire(),
+            Err(ScrybeError::RateLimit { .. })
+        ));
+
+        // Closing a stream frees its slot
+        drop(first);
+        assert!(access.acquire().is_ok());
+    }
+
+    #[test]
+    fn test_access_from_vars() {
+        assert!(StreamAccess::from_vars(|_| None).unwrap().is_none());
+
+        let short = StreamAccess::from_vars(|key| match key {
+            "SCRYBE_DASHBOARD_TOKEN" => Some("short".to_string()),
+            _ => None,
+        });
+        assert!(matches!(short, Err(ScrybeError::ConfigError(_))));
+
+        let access = StreamAccess::from_vars(|key| match key {
+            "SCRYBE_DASHBOARD_TOKEN" => Some(TOKEN.to_string()),
+            "SCRYBE_DASHBOARD_ORIGIN" => Some("https://soc.example.com".to_string()),
+            "SCRYBE_DASHBOARD_MAX_STREAMS" => Some("4".to_string()),
+            _ => None,
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(access.max_streams, 4);
+        assert_eq!(
+            access.origin,
+            Some(HeaderValue::from_static("https://soc.example.com"))
+        );
+    }
+}
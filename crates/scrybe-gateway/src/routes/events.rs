@@ -0,0 +1,164 @@
+//! Incremental behavioral event batches for an existing session.
+
+use super::ingest::{AppError, AppState};
+use axum::{
+    extract::{Json, Path, State},
+    response::IntoResponse,
+};
+use scrybe_core::{
+    types::{EventBatch, SessionId},
+    ScrybeError,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::debug;
+
+/// Request payload for the events endpoint.
+#[derive(Debug, Deserialize)]
+pub struct EventsRequest {
+    /// Token issued for the session by the ingest endpoint
+    pub session_token: String,
+    /// Events to append, timestamped from the start of the current page
+    #[serde(flatten)]
+    pub events: EventBatch,
+}
+
+/// Response from the events endpoint.
+#[derive(Debug, Serialize)]
+pub struct EventsResponse {
+    /// Session the events were appended to
+    pub session_id: String,
+    /// Mouse events now held for the session
+    pub mouse_events: usize,
+    /// Scroll events now held for the session
+    pub scroll_events: usize,
+    /// Click events now held for the session
+    pub click_events: usize,
+}
+
+/// POST /api/v1/sessions/{id}/events - Append behavioral events.
+///
+/// Appends mouse, scroll and click events to a cached session and marks
+/// it dirty so the worker re-scores it. Collections that reach their
+/// `MAX_*` limit are down-sampled.
+///
+/// # Errors
+///
+/// - `400 Bad Request`: Invalid session ID, empty batch, or session not
+///   cached (expired or unknown)
+/// - `401 Unauthorized`: Session token missing, forged, or for another
+///   session
+/// - `503 Service Unavailable`: Session cache unavailable
+pub async fn events_handler(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(payload): Json<EventsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let session_id: SessionId = id
+        .parse()
+        .map_err(|_| ScrybeError::validation_error("session_id", "UUID", id.clone()))?;
+
+    if state.signer.verify(&payload.session_token) != Some(session_id) {
+        return Err(ScrybeError::authentication_error("Invalid session token").into());
+    }
+
+    if payload.events.is_empty() {
+        return Err(ScrybeError::validation_error("events", "at least one event", "0").into());
+    }
+
+    let Some(sessions) = &state.sessions else {
+        return Err(
+            ScrybeError::cache_error("session_cache", "Session cache not configured").into(),
+        );
+    };
+
+    let events = sessions
+        .append_events(&session_id, payload.events)
+        .await?
+        .ok_or_else(|| {
+            ScrybeError::invalid_session("session_id", "Session not found or expired")
+        })?;
+
+    debug!("Appended events to session {}", session_id);
+
+    Ok(Json(EventsResponse {
+        session_id: session_id.to_string(),
+        mouse_events: events.mouse_events.len(),
+        scroll_events: events.scroll_events.len(),
+        click_events: events.click_events.len(),
+    }))
+}
+
+/// Create the events route.
+pub fn events_route() -> axum::Router<Arc<AppState>> {
+    use axum::routing::post;
+
+    axum::Router::new().route("/api/v1/sessions/:id/events", post(events_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_token::SessionSigner;
+    use axum::http::StatusCode;
+    use scrybe_core::types::{ClickEvent, MouseButton};
+
+    fn create_test_state() -> Arc<AppState> {
+        Arc::new(AppState::new(SessionSigner::new(b"test-key").unwrap()))
+    }
+
+    fn create_test_request(session_token: String) -> EventsRequest {
+        EventsRequest {
+            session_token,
+            events: EventBatch {
+                click_events: vec![ClickEvent {
+                    timestamp_ms: 10,
+                    x: 5,
+                    y: 5,
+                    button: MouseButton::Left,
+                }],
+                ..Default::default()
+            },
+        }
+    }
+
+    async fn status(state: Arc<AppState>, id: SessionId, request: EventsRequest) -> StatusCode {
+        match events_handler(State(state), Path(id.to_string()), Json(request)).await {
+            Ok(response) => response.into_response().status(),
+            Err(e) => e.into_response().status(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_for_other_session_rejected() {
+        let state = create_test_state();
+        let token = state.signer.sign(&SessionId::new());
+
+        let status = status(state, SessionId::new(), create_test_request(token)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_without_cache_is_service_unavailable() {
+        let state = create_test_state();
+        let id = SessionId::new();
+        let token = state.signer.sign(&id);
+
+        let status = status(state, id, create_test_request(token)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_request_accepts_partial_batches() {
+        let request: EventsRequest = serde_json::from_value(serde_json::json!({
+            "session_token": "token",
+            "scroll_events": [
+                { "timestamp_ms": 5, "x": 0, "y": 10, "delta_x": 0, "delta_y": 10 }
+            ],
+        }))
+        .unwrap();
+
+        assert_eq!(request.events.scroll_events.len(), 1);
+        assert!(request.events.mouse_events.is_empty());
+    }
+}
@@ -1,13 +1,20 @@
 //! Ingestion endpoint for browser session data.
 
-use crate::extraction::{extract_headers, extract_http_version, extract_ip_info};
+use crate::extraction::{extract_headers, extract_http_version, extract_ip_info, ip::hash_ip};
+use crate::session_token::SessionSigner;
 use axum::{
     extract::{ConnectInfo, Json, State},
     http::{HeaderMap, StatusCode, Version},
     response::IntoResponse,
 };
+use chrono::{DateTime, Utc};
+use scrybe_cache::{
+    AnomalyFeed, Entity, EntityKind, RedisClient, SessionCache, SessionQueue, VelocityCounters,
+    VelocityUpdate,
+};
 use scrybe_core::{
-    types::{BehavioralSignals, BrowserSignals, NetworkSignals, SessionId},
+    privacy,
+    types::{BehavioralSignals, BrowserSignals, GatewayTiming, NetworkSignals, Session},
     ScrybeError,
 };
 use serde::{Deserialize, Serialize};
@@ -18,13 +25,50 @@ use tracing::{info, warn};
 /// Application state shared across handlers.
 #[derive(Clone)]
 pub struct AppState {
-    // TODO: Add Redis, ClickHouse clients
+    /// Redis client behind the cache, queue, counters and feed
+    pub(crate) redis: Option<RedisClient>,
+    /// Enrichment queue (sessions are not enqueued if absent)
+    pub(crate) queue: Option<SessionQueue>,
+    /// Cache of live sessions (page loads are not stitched if absent)
+    pub(crate) sessions: Option<SessionCache>,
+    /// Anomaly feed (not streamed if absent)
+    pub(crate) anomalies: Option<AnomalyFeed>,
+    /// Velocity counters (not updated if absent)
+    pub(crate) velocity: Option<VelocityCounters>,
+    /// Salt for hashing IPs before they are counted
+    pub(crate) ip_salt: Vec<u8>,
+    /// Signs and verifies session tokens
+    pub(crate) signer: SessionSigner,
 }
 
 impl AppState {
-    /// Create new application state.
-    pub fn new() -> Self {
-        Self {}
+    /// Create new application state without Redis.
+    pub fn new(signer: SessionSigner) -> Self {
+        Self {
+            redis: None,
+            queue: None,
+            sessions: None,
+            anomalies: None,
+            velocity: None,
+            ip_salt: Vec::new(),
+            signer,
+        }
+    }
+
+    /// Create application state that caches sessions, enqueues them for
+    /// enrichment, updates velocity counters, and streams the anomaly feed.
+    ///
+    /// IPs are hashed with `ip_salt` before they are counted.
+    pub fn with_redis(signer: SessionSigner, client: RedisClient, ip_salt: Vec<u8>) -> Self {
+        Self {
+            queue: Some(SessionQueue::new(client.clone(), None, None, None)),
+            sessions: Some(SessionCache::new(client.clone(), None, None)),
+            anomalies: Some(AnomalyFeed::new(client.clone(), None, None, None)),
+            velocity: Some(VelocityCounters::new(client.clone(), None, None)),
+            redis: Some(client),
+            ip_salt,
+            signer,
+        }
     }
 }
 
@@ -33,12 +77,13 @@ impl AppState {
 pub struct IngestRequest {
     /// Network signals from client
     pub network: NetworkSignals,
-    /// Browser signals from client (not yet persisted)
-    #[allow(dead_code)]
+    /// Browser signals from client
     pub browser: BrowserSignals,
-    /// Behavioral signals from client (not yet persisted)
-    #[allow(dead_code)]
+    /// Behavioral signals from client
     pub behavioral: BehavioralSignals,
+    /// Token from an earlier response, continuing that session
+    #[serde(default)]
+    pub session_token: Option<String>,
 }
 
 /// Response from ingestion endpoint.
@@ -48,14 +93,23 @@ pub struct IngestResponse {
     pub session_id: String,
     /// Whether this is a new session
     pub is_new: bool,
+    /// Token to send with later page loads of this session
+    pub session_token: String,
     /// Server timestamp
     pub timestamp: String,
 }
 
 /// POST /api/v1/ingest - Ingest browser telemetry data.
 ///
-/// This endpoint receives browser session data, validates it,
-/// enriches it with server-side signals, and stores it.
+/// This endpoint receives browser session data, merges it with
+/// server-side signals, and enqueues it for enrichment and storage.
+///
+/// # Session Stitching
+///
+/// A request carrying a valid `session_token` whose session is still
+/// cached is appended to that session as a further page load and
+/// `is_new` is `false`. Otherwise a new session is started. Tokens with
+/// an invalid signature are ignored.
 ///
 /// # Authentication
 ///
@@ -76,14 +130,18 @@ pub struct IngestResponse {
 /// - `429 Too Many Requests`: Rate limit exceeded
 /// - `503 Service Unavailable`: Backend unavailable
 pub async fn ingest_handler(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
     version: Version,
-    Json(payload): Json<IngestRequest>,
+    Json(mut payload): Json<IngestRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     info!("Received ingest request from {}", addr.ip());
 
+    payload.browser.validate()?;
+    // Bounded before the session is cached, queued or stored
+    payload.behavioral.bound_events();
+
     // Extract server-side signals
     let client_ip = extract_ip_info(&ConnectInfo(addr));
     let server_headers = extract_headers(&headers);
@@ -100,23 +158,133 @@ pub async fn ingest_handler(
     let mut network_signals = payload.network;
     network_signals.ip = client_ip;
     network_signals.http_version = http_version;
+    // Measured below for stitched page loads; never taken from the client
+    network_signals.gateway_timing = None;
+    // Only headers seen here count as observed; clients cannot claim it
+    for header in &mut network_signals.headers {
+        header.observed = false;
+    }
     // Append server-extracted headers (client can't spoof these)
     network_signals.headers.extend(server_headers);
 
-    // TODO: Validate payload
-    // TODO: Store in Redis
-    // TODO: Enqueue for enrichment
+    let stitch_id = match (&state.sessions, payload.session_token.as_deref()) {
+        (Some(_), Some(token)) => {
+            let session_id = state.signer.verify(token);
+            if session_id.is_none() {
+                warn!("Ignoring session token with invalid signature");
+            }
+            session_id
+        }
+        _ => None,
+    };
+
+    // Applied by the cache to the session cached at the time of the write,
+    // so concurrent page loads of one session each count
+    let stitched = match (&state.sessions, stitch_id) {
+        (Some(sessions), Some(session_id)) => {
+            sessions
+                .store_page_load(&session_id, |session| {
+                    let loaded_at = chrono::Utc::now();
+                    let mut network = network_signals.clone();
+                    network.gateway_timing = Some(page_timing(session, loaded_at));
+                    // Appended by the cache to the events cached now
+                    let mut behavioral = payload.behavioral.clone();
+                    let page_events = behavioral.take_events();
+                    session.append_page_load(
+                        loaded_at,
+                        network,
+                        payload.browser.clone(),
+                        behavioral,
+                    );
+                    page_events
+                })
+                .await?
+        }
+        _ => None,
+    };
+    let is_new = stitched.is_none();
+
+    // Raw session only; derived data is added by the worker
+    let session = match stitched {
+        Some(session) => session,
+        None => {
+            let session = Session::new(network_signals, payload.browser, payload.behavioral);
+            if let Some(sessions) = &state.sessions {
+                sessions.store(&session).await?;
+            }
+            session
+        }
+    };
+
+    if let Some(velocity) = &state.velocity {
+        let updates = velocity_updates(&session, &state.ip_salt);
+        // Counters only feed detection; never fail ingestion over them
+        if let Err(e) = velocity.record(&updates, chrono::Utc::now()).await {
+            warn!("Failed to update velocity counters: {}", e);
+        }
+    }
 
-    // Create session
-    let session_id = SessionId::new();
+    if let Some(queue) = &state.queue {
+        let entry_id = queue.enqueue(&session).await?;
+        info!(
+            "Enqueued session {} for enrichment ({})",
+            session.id, entry_id
+        );
+    }
 
     Ok(Json(IngestResponse {
-        session_id: session_id.to_string(),
-        is_new: true,
-        timestamp: chrono::Utc::now().to_rfc3339(),
+        session_id: session.id.to_string(),
+        is_new,
+        session_token: state.signer.sign(&session.id),
+        timestamp: session.timestamp.to_rfc3339(),
     }))
 }
 
+/// Timing observed for a page load stitched into `session` at `loaded_at`.
+fn page_timing(session: &Session, loaded_at: DateTime<Utc>) -> GatewayTiming {
+    let page_offset = i64::try_from(session.page_offset_ms).unwrap_or(0);
+    let previous = session.timestamp + chrono::Duration::milliseconds(page_offset);
+    GatewayTiming {
+        page_interval_ms: u64::try_from((loaded_at - previous).num_milliseconds()).unwrap_or(0),
+    }
+}
+
+/// Velocity counter updates for one ingested page load.
+///
+/// Counts the request per IP, per network and per JA4, and the session per
+/// IP. IPs and networks are hashed with `ip_salt`.
+fn velocity_updates(session: &Session, ip_salt: &[u8]) -> Vec<VelocityUpdate> {
+    let ip = &session.network.ip;
+    let ip_hash = Entity::new(EntityKind::IpHash, hash_ip(ip, ip_salt));
+    let subnet_hash = Entity::new(
+        EntityKind::SubnetHash,
+        privacy::hash_ip(&privacy::subnet(ip), ip_salt),
+    );
+
+    let mut updates = vec![
+        VelocityUpdate::Count {
+            counter: "requests",
+            entity: ip_hash.clone(),
+        },
+        VelocityUpdate::Count {
+            counter: "requests",
+            entity: subnet_hash,
+        },
+        VelocityUpdate::Distinct {
+            counter: "sessions",
+            entity: ip_hash,
+            member: session.id.to_string(),
+        },
+    ];
+    if let Some(ja4) = &session.network.ja4 {
+        updates.push(VelocityUpdate::Count {
+            counter: "requests",
+            entity: Entity::new(EntityKind::Ja4, ja4.clone()),
+        });
+    }
+    updates
+}
+
 /// Error wrapper for Axum responses.
 #[derive(Debug)]
 pub struct AppError(ScrybeError);
@@ -137,6 +305,10 @@ impl IntoResponse for AppError {
                 "Authentication failed".to_string(),
             ),
             ScrybeError::RateLimit { .. } => (StatusCode::TOO_MANY_REQUESTS, self.0.to_string()),
+            ScrybeError::CacheError { .. } | ScrybeError::PoolExhausted { .. } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Service unavailable".to_string(),
+            ),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
@@ -167,43 +339,35 @@ pub fn ingest_route() -> axum::Router<Arc<AppState>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::state::AppState as GatewayAppState;
     use axum::http::StatusCode;
-    use scrybe_core::{types::*, ScrybeError};
+    use scrybe_core::test_util;
+    use scrybe_core::types::*;
     use std::net::Ipv4Addr;
 
     fn create_test_request() -> IngestRequest {
+        let mut network = test_util::network_signals();
+        network.headers = vec![Header::new("User-Agent", "Test/1.0")];
+
+        let mut browser = test_util::browser_signals();
+        browser.canvas_hash = Some("test_hash".to_string());
+        browser.fonts = vec!["Arial".to_string()];
+        browser.user_agent = "Test/1.0".to_string();
+
         IngestRequest {
-            network: NetworkSignals {
-                ip: std::net::IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)),
-                ja3: None,
-                ja4: None,
-                headers: vec![Header::new("User-Agent", "Test/1.0")],
-                http_version: HttpVersion::Http2,
-            },
-            browser: BrowserSignals {
-                canvas_hash: Some("test_hash".to_string()),
-                webgl_hash: None,
-                audio_hash: None,
-                fonts: vec!["Arial".to_string()],
-                plugins: vec![],
-                timezone: "UTC".to_string(),
-                language: "en-US".to_string(),
-                screen: ScreenInfo::default(),
-                user_agent: "Test/1.0".to_string(),
-            },
-            behavioral: BehavioralSignals {
-                mouse_events: vec![],
-                scroll_events: vec![],
-                click_events: vec![],
-                timing: TimingMetrics::default(),
-            },
+            network,
+            browser,
+            behavioral: test_util::behavioral_signals(),
+            session_token: None,
         }
     }
 
+    fn create_test_state() -> Arc<AppState> {
+        Arc::new(AppState::new(SessionSigner::new(b"test-key").unwrap()))
+    }
+
     #[tokio::test]
     async fn test_ingest_handler_returns_session_id() {
-        let state = Arc::new(AppState::new());
+        let state = create_test_state();
         let request = create_test_request();
         let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
         let headers = axum::http::HeaderMap::new();
@@ -222,4 +386,126 @@ mod tests {
         let response = result.unwrap().into_response();
         assert_eq!(response.status(), StatusCode::OK);
     }
+
+    #[tokio::test]
+    async fn test_ingest_without_cache_starts_new_session() {
+        let state = create_test_state();
+        let mut request = create_test_request();
+        request.session_token = Some(state.signer.sign(&SessionId::new()));
+        let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
+
+        let response = ingest_handler(
+            State(state.clone()),
+            ConnectInfo(addr),
+            axum::http::HeaderMap::new(),
+            axum::http::Version::HTTP_11,
+            Json(request),
+        )
+        .await
+        .unwrap()
+        .into_response();
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["is_new"], true);
+
+        let token = body["session_token"].as_str().unwrap();
+        let session_id = state.signer.verify(token).unwrap();
+        assert_eq!(body["session_id"], session_id.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_ingest_rejects_oversized_lists() {
+        let mut request = create_test_request();
+        request.browser.webgl = Some(WebGlInfo {
+            extensions: vec!["OES_texture_float".to_string(); MAX_WEBGL_EXTENSIONS + 1],
+            ..WebGlInfo::default()
+        });
+
+        let result = ingest_handler(
+            State(create_test_state()),
+            ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 8080))),
+            axum::http::HeaderMap::new(),
+            axum::http::Version::HTTP_11,
+            Json(request),
+        )
+        .await;
+        let response = result.err().unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_page_timing_measures_from_previous_page_load() {
+        let request = create_test_request();
+        let mut session = Session::new(request.network, request.browser, request.behavioral);
+        let second_page = session.timestamp + chrono::Duration::seconds(10);
+        session.page_offset_ms = 10_000;
+
+        let timing = page_timing(
+            &session,
+            second_page + chrono::Duration::milliseconds(2_500),
+        );
+        assert_eq!(timing.page_interval_ms, 2_500);
+
+        // Clock steps backwards do not wrap around
+        assert_eq!(page_timing(&session, session.timestamp).page_interval_ms, 0);
+    }
+
+    #[test]
+    fn test_velocity_updates_share_network_across_ips() {
+        let request = create_test_request();
+        let mut session = Session::new(request.network, request.browser, request.behavioral);
+        let first = velocity_updates(&session, b"salt");
+
+        session.network.ip = std::net::IpAddr::V4(Ipv4Addr::new(203, 0, 113, 8));
+        session.network.ja4 = Some("t13d1516h2_8daaf6152771_b186095e22b6".to_string());
+        let second = velocity_updates(&session, b"salt");
+
+        // Same /24, different IP; JA4 counted only when present
+        assert_eq!(first.len(), 3);
+        assert_eq!(second.len(), 4);
+        assert_ne!(first[0], second[0]);
+        assert_eq!(first[1], second[1]);
+    }
+
+    #[test]
+    fn test_cache_error_is_service_unavailable() {
+        let error = AppError(ScrybeError::cache_error("redis", "XADD failed"));
+        assert_eq!(
+            error.into_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test]
+    fn test_pool_exhausted_is_service_unavailable() {
+        let error = AppError(ScrybeError::pool_exhausted("redis", 500));
+        assert_eq!(
+            error.into_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[test]
+    fn test_ingest_request_accepts_repeated_renders() {
+        let mut value = serde_json::json!({
+            "network": create_test_request().network,
+            "browser": create_test_request().browser,
+            "behavioral": create_test_request().behavioral,
+        });
+        value["browser"]["canvas_hash_repeat"] = "test_hash_2".into();
+        value["browser"]["audio_hash_repeat"] = "audio_2".into();
+
+        let request: IngestRequest = serde_json::from_value(value).unwrap();
+        assert_eq!(
+            request.browser.canvas_hash_repeat.as_deref(),
+            Some("test_hash_2")
+        );
+        assert_eq!(
+            request.browser.audio_hash_repeat.as_deref(),
+            Some("audio_2")
+        );
+    }
 }
@@ -1,5 +1,9 @@
 //! API route definitions.
 
+pub mod anomalies;
+pub mod events;
 pub mod ingest;
 
+pub use anomalies::anomalies_route;
+pub use events::events_route;
 pub use ingest::ingest_route;
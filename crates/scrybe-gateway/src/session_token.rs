@@ -0,0 +1,156 @@
+//! Signed session tokens for stitching page loads into one session.
+//!
+//! The gateway hands each client a token of the form
+//! `{session_id}.{issued_at}.{hmac}` where `issued_at` is in Unix seconds
+//! and `hmac` is HMAC-SHA256 of `{session_id}.{issued_at}` under a
+//! server-side key. Clients send it back on later page loads; a token
+//! whose signature does not verify is ignored, so clients cannot attach
+//! signals to sessions they were not issued.
+//!
+//! Tokens expire with the cached session: every page load issues a fresh
+//! one, and a token older than the session TTL is rejected, so a leaked
+//! token cannot be replayed indefinitely.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use scrybe_cache::session::DEFAULT_TTL_SECONDS;
+use scrybe_core::{types::SessionId, ScrybeError, SecretConfig};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Clock difference tolerated between gateways, in seconds.
+const MAX_CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// Signs and verifies session tokens.
+#[derive(Clone)]
+pub struct SessionSigner {
+    mac: HmacSha256,
+    max_age_seconds: i64,
+}
+
+impl SessionSigner {
+    /// Create a signer with the given key.
+    ///
+    /// Tokens are valid for the default session TTL (1 hour).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the key is rejected.
+    pub fn new(key: &[u8]) -> Result<Self, ScrybeError> {
+        let mac = HmacSha256::new_from_slice(key)
+            .map_err(|e| ScrybeError::config_error(format!("Invalid session key: {}", e)))?;
+        Ok(Self {
+            mac,
+            max_age_seconds: DEFAULT_TTL_SECONDS as i64,
+        })
+    }
+
+    /// Create a signer from the hex-encoded `SCRYBE_SESSION_KEY`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the key is missing, not valid
+    /// hex or too short.
+    pub fn from_env() -> Result<Self, ScrybeError> {
+        Self::new(SecretConfig::session_key()?.expose())
+    }
+
+    /// Issue a token for a session.
+    pub fn sign(&self, session_id: &SessionId) -> String {
+        self.sign_at(session_id, Utc::now())
+    }
+
+    /// Return the session ID of a token if its signature is valid and it
+    /// has not expired.
+    pub fn verify(&self, token: &str) -> Option<SessionId> {
+        self.verify_at(token, Utc::now())
+    }
+
+    /// Issue a token as of `issued_at`.
+    fn sign_at(&self, session_id: &SessionId, issued_at: DateTime<Utc>) -> String {
+        let payload = format!("{}.{}", session_id, issued_at.timestamp());
+        format!("{}.{}", payload, hex::encode(self.signature(&payload)))
+    }
+
+    /// Verify a token as of `now`.
+    fn verify_at(&self, token: &str, now: DateTime<Utc>) -> Option<SessionId> {
+        let (payload, signature) = token.rsplit_once('.')?;
+        let (id, issued_at) = payload.split_once('.')?;
+        let session_id: SessionId = id.parse().ok()?;
+        let issued_at: i64 = issued_at.parse().ok()?;
+        let signature = hex::decode(signature).ok()?;
+
+        let mut mac = self.mac.clone();
+        mac.update(payload.as_bytes());
+        mac.verify_slice(&signature).ok()?;
+
+        let age = now.timestamp().saturating_sub(issued_at);
+        (-MAX_CLOCK_SKEW_SECONDS..=self.max_age_seconds)
+            .contains(&age)
+            .then_some(session_id)
+    }
+
+    /// HMAC of a token payload.
+    fn signature(&self, payload: &str) -> Vec<u8> {
+        let mut mac = self.mac.clone();
+        mac.update(payload.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = SessionSigner::new(b"test-key").unwrap();
+        let id = SessionId::new();
+
+        let token = signer.sign(&id);
+        assert_eq!(signer.verify(&token), Some(id));
+    }
+
+    #[test]
+    fn test_forged_token_rejected() {
+        let signer = SessionSigner::new(b"test-key").unwrap();
+        let token = signer.sign(&SessionId::new());
+        let (id, rest) = token.split_once('.').unwrap();
+        let (issued_at, signature) = rest.split_once('.').unwrap();
+
+        let forged = format!("{}.{}.{}", SessionId::new(), issued_at, signature);
+        assert_eq!(signer.verify(&forged), None);
+
+        // Extending the lifetime breaks the signature too
+        let issued_at: i64 = issued_at.parse().unwrap();
+        let extended = format!("{}.{}.{}", id, issued_at + 3_600, signature);
+        assert_eq!(signer.verify(&extended), None);
+    }
+
+    #[test]
+    fn test_expired_token_rejected() {
+        let signer = SessionSigner::new(b"test-key").unwrap();
+        let id = SessionId::new();
+        let issued_at = Utc::now();
+        let token = signer.sign_at(&id, issued_at);
+        let later = |seconds: i64| issued_at + chrono::Duration::seconds(seconds);
+
+        assert_eq!(signer.verify_at(&token, later(3_600)), Some(id));
+        assert_eq!(signer.verify_at(&token, later(3_601)), None);
+
+        // Issued by a gateway whose clock runs slightly ahead
+        assert_eq!(signer.verify_at(&token, later(-60)), Some(id));
+        assert_eq!(signer.verify_at(&token, later(-61)), None);
+    }
+
+    #[test]
+    fn test_other_key_rejected() {
+        let signer = SessionSigner::new(b"test-key").unwrap();
+        let other = SessionSigner::new(b"other-key").unwrap();
+
+        let token = other.sign(&SessionId::new());
+        assert_eq!(signer.verify(&token), None);
+        assert_eq!(signer.verify("not-a-token"), None);
+    }
+}
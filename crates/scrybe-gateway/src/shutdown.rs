@@ -1,8 +1,12 @@
 //! Graceful shutdown handling.
 
+use std::time::Duration;
 use tokio::signal;
 use tracing::info;
 
+/// How long open connections may take to finish after the shutdown signal.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Wait for shutdown signal (SIGTERM or Ctrl-C).
 ///
 /// This function will block until one of the following signals is received:
@@ -0,0 +1,30 @@
+-- Enriched sessions, one row per write by the worker.
+--
+-- A session re-scored later is written again with a newer scored_at.
+-- Merges keep only the latest row of each session; read with FINAL where
+-- duplicates not merged yet would skew results.
+--
+-- Signals are stored as JSON next to the columns most queries filter on.
+
+CREATE TABLE IF NOT EXISTS sessions (
+    session_id UUID,
+    timestamp DateTime64(3, 'UTC'),
+    fingerprint_hash String,
+    ip String,
+    user_agent String,
+    network_signals String,
+    browser_signals String,
+    behavioral_signals String,
+    bot_probability Float32,
+    confidence_score Float32,
+    scored_at DateTime64(3, 'UTC'),
+    INDEX idx_fingerprint fingerprint_hash TYPE bloom_filter GRANULARITY 1,
+    INDEX idx_ip ip TYPE tokenbf_v1(32768, 3, 0) GRANULARITY 1
+) ENGINE = ReplacingMergeTree(scored_at)
+PARTITION BY toYYYYMM(timestamp)
+ORDER BY (timestamp, session_id)
+TTL timestamp + INTERVAL 90 DAY
+SETTINGS index_granularity = 8192;
+
+ALTER TABLE sessions
+    ADD INDEX IF NOT EXISTS idx_bot_probability bot_probability TYPE minmax GRANULARITY 1;
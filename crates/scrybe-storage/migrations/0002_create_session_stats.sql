@@ -0,0 +1,19 @@
+-- Hourly session counts per fingerprint.
+--
+-- A plain view rather than a materialized one: a re-scored session has a
+-- row per write until merges collapse them, and only FINAL counts it once
+-- with its latest score. Averages are stored as sums so hours can be added
+-- up; divide by session_count when querying. Filter on hour to bound the
+-- scan.
+
+CREATE VIEW IF NOT EXISTS session_stats_hourly
+AS SELECT
+    toStartOfHour(timestamp) AS hour,
+    fingerprint_hash,
+    count() AS session_count,
+    countIf(bot_probability > 0.7) AS bot_count,
+    countIf(bot_probability < 0.3) AS human_count,
+    sum(toFloat64(bot_probability)) AS bot_probability_sum,
+    sum(toFloat64(confidence_score)) AS confidence_sum
+FROM sessions FINAL
+GROUP BY hour, fingerprint_hash;
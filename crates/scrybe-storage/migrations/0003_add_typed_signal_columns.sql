@@ -0,0 +1,73 @@
+-- Native columns for the signals analysts filter on.
+--
+-- The JSON columns stay, and remain the complete record for fields without
+-- a column of their own. Each DEFAULT extracts the value from that JSON, so
+-- rows written before this migration, or by workers that do not write the
+-- columns yet, read the same values without rewriting any part. Run
+-- `ALTER TABLE sessions MATERIALIZE COLUMN <name>` to store them for old
+-- parts if extraction at query time becomes a cost.
+
+ALTER TABLE sessions
+    ADD COLUMN IF NOT EXISTS client_ip IPv6
+        DEFAULT toIPv6OrDefault(ip),
+    ADD COLUMN IF NOT EXISTS tls_ja3 LowCardinality(String)
+        DEFAULT JSONExtractString(network_signals, 'ja3'),
+    ADD COLUMN IF NOT EXISTS tls_ja4 LowCardinality(String)
+        DEFAULT JSONExtractString(network_signals, 'ja4'),
+    ADD COLUMN IF NOT EXISTS http_version LowCardinality(String)
+        DEFAULT transform(
+            JSONExtractString(network_signals, 'http_version'),
+            ['Http10', 'Http11', 'Http2', 'Http3'],
+            ['HTTP/1.0', 'HTTP/1.1', 'HTTP/2', 'HTTP/3'],
+            ''
+        ),
+    ADD COLUMN IF NOT EXISTS http_headers Map(LowCardinality(String), String)
+        DEFAULT mapFromArrays(
+            arrayMap(h -> JSONExtractString(h, 'name'), JSONExtractArrayRaw(network_signals, 'headers')),
+            arrayMap(h -> JSONExtractString(h, 'value'), JSONExtractArrayRaw(network_signals, 'headers'))
+        ),
+    ADD COLUMN IF NOT EXISTS http_header_order Array(LowCardinality(String))
+        DEFAULT arrayMap(h -> JSONExtractString(h, 'name'), JSONExtractArrayRaw(network_signals, 'headers')),
+    ADD COLUMN IF NOT EXISTS timezone LowCardinality(String)
+        DEFAULT JSONExtractString(browser_signals, 'timezone'),
+    ADD COLUMN IF NOT EXISTS language LowCardinality(String)
+        DEFAULT JSONExtractString(browser_signals, 'language'),
+    ADD COLUMN IF NOT EXISTS fonts Array(LowCardinality(String))
+        DEFAULT JSONExtract(browser_signals, 'fonts', 'Array(String)'),
+    ADD COLUMN IF NOT EXISTS plugins Array(LowCardinality(String))
+        DEFAULT JSONExtract(browser_signals, 'plugins', 'Array(String)'),
+    ADD COLUMN IF NOT EXISTS screen_width UInt32
+        DEFAULT JSONExtractUInt(browser_signals, 'screen', 'width'),
+    ADD COLUMN IF NOT EXISTS screen_height UInt32
+        DEFAULT JSONExtractUInt(browser_signals, 'screen', 'height'),
+    ADD COLUMN IF NOT EXISTS color_depth UInt8
+        DEFAULT JSONExtractUInt(browser_signals, 'screen', 'color_depth'),
+    ADD COLUMN IF NOT EXISTS pixel_ratio Float32
+        DEFAULT JSONExtractFloat(browser_signals, 'screen', 'pixel_ratio'),
+    ADD COLUMN IF NOT EXISTS canvas_hash String
+        DEFAULT JSONExtractString(browser_signals, 'canvas_hash'),
+    ADD COLUMN IF NOT EXISTS webgl_hash String
+        DEFAULT JSONExtractString(browser_signals, 'webgl_hash'),
+    ADD COLUMN IF NOT EXISTS audio_hash String
+        DEFAULT JSONExtractString(browser_signals, 'audio_hash'),
+    ADD COLUMN IF NOT EXISTS webgl_vendor LowCardinality(String)
+        DEFAULT JSONExtractString(browser_signals, 'webgl', 'unmasked_vendor'),
+    ADD COLUMN IF NOT EXISTS webgl_renderer LowCardinality(String)
+        DEFAULT JSONExtractString(browser_signals, 'webgl', 'unmasked_renderer'),
+    ADD COLUMN IF NOT EXISTS mouse_event_count UInt32
+        DEFAULT length(JSONExtractArrayRaw(behavioral_signals, 'mouse_events')),
+    ADD COLUMN IF NOT EXISTS scroll_event_count UInt32
+        DEFAULT length(JSONExtractArrayRaw(behavioral_signals, 'scroll_events')),
+    ADD COLUMN IF NOT EXISTS click_event_count UInt32
+        DEFAULT length(JSONExtractArrayRaw(behavioral_signals, 'click_events')),
+    ADD COLUMN IF NOT EXISTS time_to_first_byte_ms Nullable(UInt32)
+        DEFAULT JSONExtract(behavioral_signals, 'timing', 'time_to_first_byte_ms', 'Nullable(UInt32)'),
+    ADD COLUMN IF NOT EXISTS dom_content_loaded_ms Nullable(UInt32)
+        DEFAULT JSONExtract(behavioral_signals, 'timing', 'dom_content_loaded_ms', 'Nullable(UInt32)'),
+    ADD COLUMN IF NOT EXISTS load_time_ms Nullable(UInt32)
+        DEFAULT JSONExtract(behavioral_signals, 'timing', 'load_time_ms', 'Nullable(UInt32)');
+
+ALTER TABLE sessions
+    ADD INDEX IF NOT EXISTS idx_tls_ja4 tls_ja4 TYPE bloom_filter GRANULARITY 1,
+    ADD INDEX IF NOT EXISTS idx_timezone timezone TYPE set(256) GRANULARITY 1,
+    ADD INDEX IF NOT EXISTS idx_header_names mapKeys(http_headers) TYPE bloom_filter GRANULARITY 1;
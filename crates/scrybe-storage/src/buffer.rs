@@ -0,0 +1,699 @@
+//! Write-behind buffering for session inserts.
+//!
+//! ClickHouse creates a part per `INSERT` and rejects inserts with
+//! `TOO_MANY_PARTS` once merges fall behind, so inserting each session, or
+//! each small batch, on its own does not keep up with production traffic.
+//! [`BufferedSessionWriter`] queues rows in a bounded channel and a
+//! background task inserts them in large batches:
+//!
+//! - a batch is flushed once it holds `batch_size` rows, or
+//!   `flush_interval` after the previous flush, whichever comes first
+//! - transient failures are retried with exponential backoff
+//! - when the channel is full, writers wait (backpressure) instead of
+//!   growing memory without bound
+//!
+//! Each write returns a [`WriteReceipt`] that resolves once its rows are
+//! stored, so callers can defer acknowledgements until then.
+//!
+//! With a [`Spool`], rows whose insert still fails after the retries are
+//! appended to disk and count as stored. While the spool holds rows, new
+//! rows are appended behind them to keep their order, and the spool is
+//! replayed once ClickHouse passes a health check. Spool file I/O and
+//! syncs run on Tokio's blocking thread pool so they do not stall the
+//! runtime.
+
+use crate::spool::{Spool, SpoolMetrics};
+use crate::writer::{is_transient, SessionRow, SessionWriter};
+use scrybe_core::{types::EnrichedSession, ScrybeError};
+use std::env;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+
+/// Largest accepted buffer capacity in rows (DoS protection against a
+/// misconfigured writer holding unbounded memory).
+pub const MAX_BUFFER_CAPACITY: usize = 1_000_000;
+
+/// Spooled batches replayed per flush interval, so a long backlog does not
+/// hold up new rows for too long.
+const MAX_REPLAY_BATCHES: usize = 10;
+
+/// Buffering, flush and retry settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteBufferConfig {
+    /// Rows queued before writers wait for a flush
+    pub capacity: usize,
+    /// Rows per `INSERT`
+    pub batch_size: usize,
+    /// Longest time a row waits before it is flushed
+    pub flush_interval: Duration,
+    /// Retries of a failed flush before its rows are given up
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further one
+    pub retry_backoff: Duration,
+    /// Upper bound on the delay between retries
+    pub max_retry_backoff: Duration,
+}
+
+impl Default for WriteBufferConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 50_000,
+            batch_size: 10_000,
+            flush_interval: Duration::from_secs(5),
+            max_retries: 5,
+            retry_backoff: Duration::from_millis(500),
+            max_retry_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl WriteBufferConfig {
+    /// Load buffer settings from environment variables, falling back to
+    /// the defaults:
+    ///
+    /// - `SCRYBE_WRITER_BUFFER_CAPACITY`
+    /// - `SCRYBE_WRITER_BATCH_SIZE`
+    /// - `SCRYBE_WRITER_FLUSH_INTERVAL_MS`
+    /// - `SCRYBE_WRITER_MAX_RETRIES`
+    /// - `SCRYBE_WRITER_RETRY_BACKOFF_MS`
+    /// - `SCRYBE_WRITER_MAX_RETRY_BACKOFF_MS`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a variable is not a number or
+    /// the settings are out of range.
+    pub fn from_env() -> Result<Self, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load buffer settings from a variable lookup.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a variable is not a number or
+    /// the settings are out of range.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ScrybeError> {
+        let defaults = Self::default();
+        let millis = |key: &str, default: Duration| {
+            parse(&var, key, default.as_millis() as u64).map(Duration::from_millis)
+        };
+
+        let config = Self {
+            capacity: parse(&var, "SCRYBE_WRITER_BUFFER_CAPACITY", defaults.capacity)?,
+            batch_size: parse(&var, "SCRYBE_WRITER_BATCH_SIZE", defaults.batch_size)?,
+            flush_interval: millis("SCRYBE_WRITER_FLUSH_INTERVAL_MS", defaults.flush_interval)?,
+            max_retries: parse(&var, "SCRYBE_WRITER_MAX_RETRIES", defaults.max_retries)?,
+            retry_backoff: millis("SCRYBE_WRITER_RETRY_BACKOFF_MS", defaults.retry_backoff)?,
+            max_retry_backoff: millis(
+                "SCRYBE_WRITER_MAX_RETRY_BACKOFF_MS",
+                defaults.max_retry_backoff,
+            )?,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the settings are usable.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the capacity is zero or above
+    /// [`MAX_BUFFER_CAPACITY`], the batch size is zero or above the
+    /// capacity, or the flush interval is zero.
+    pub fn validate(&self) -> Result<(), ScrybeError> {
+        if self.capacity == 0 || self.capacity > MAX_BUFFER_CAPACITY {
+            return Err(ScrybeError::config_error(format!(
+                "Writer buffer capacity must be between 1 and {}",
+                MAX_BUFFER_CAPACITY
+            )));
+        }
+        if self.batch_size == 0 || self.batch_size > self.capacity {
+            return Err(ScrybeError::config_error(
+                "Writer batch size must be between 1 and the buffer capacity",
+            ));
+        }
+        if self.flush_interval.is_zero() {
+            return Err(ScrybeError::config_error(
+                "Writer flush interval must be positive",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Delay before retry number `attempt` (starting at 1).
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.retry_backoff
+            .saturating_mul(factor)
+            .min(self.max_retry_backoff)
+    }
+}
+
+/// Parse an optional variable, falling back to a default.
+fn parse<T>(var: impl Fn(&str) -> Option<String>, key: &str, default: T) -> Result<T, ScrybeError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match var(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|e| ScrybeError::config_error(format!("Invalid {}: {}", key, e))),
+        None => Ok(default),
+    }
+}
+
+/// Resolves once the rows of one write are stored, or have been given up.
+pub struct WriteReceipt(oneshot::Receiver<Result<(), String>>);
+
+impl WriteReceipt {
+    /// Receipt for a write with no rows.
+    fn completed() -> Self {
+        let (sender, receiver) = oneshot::channel();
+        let _ = sender.send(Ok(()));
+        Self(receiver)
+    }
+
+    /// The outcome if the rows have been flushed, `None` while pending.
+    pub fn try_result(&mut self) -> Option<Result<(), ScrybeError>> {
+        match self.0.try_recv() {
+            Ok(result) => Some(result.map_err(flush_failed)),
+            Err(oneshot::error::TryRecvError::Empty) => None,
+            Err(oneshot::error::TryRecvError::Closed) => Some(Err(writer_stopped())),
+        }
+    }
+
+    /// Wait until the rows have been flushed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the rows could not be
+    /// stored.
+    pub async fn wait(self) -> Result<(), ScrybeError> {
+        match self.0.await {
+            Ok(result) => result.map_err(flush_failed),
+            Err(_) => Err(writer_stopped()),
+        }
+    }
+}
+
+fn flush_failed(reason: String) -> ScrybeError {
+    ScrybeError::storage_error("clickhouse", format!("Buffered write failed: {}", reason))
+}
+
+fn writer_stopped() -> ScrybeError {
+    ScrybeError::storage_error("clickhouse", "Buffered writer stopped")
+}
+
+/// Rows of one write still waiting to be flushed.
+struct Completion {
+    remaining: AtomicUsize,
+    sender: Mutex<Option<oneshot::Sender<Result<(), String>>>>,
+}
+
+impl Completion {
+    /// Record that one row was flushed with `result`.
+    ///
+    /// The receipt resolves with the first failure, or with success once
+    /// every row is stored.
+    fn row_done(&self, result: &Result<(), String>) {
+        let last = self.remaining.fetch_sub(1, Ordering::AcqRel) == 1;
+        if result.is_err() || last {
+            let sender = self
+                .sender
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .take();
+            if let Some(sender) = sender {
+                let _ = sender.send(result.clone());
+            }
+        }
+    }
+}
+
+enum Message {
+    Row(Box<SessionRow>, Arc<Completion>),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Session writer that buffers rows and inserts them in batches.
+///
+/// Rows are written by a background task; call
+/// [`close`](Self::close) on shutdown so buffered rows are flushed
+/// before the process exits.
+pub struct BufferedSessionWriter {
+    sender: mpsc::Sender<Message>,
+    task: JoinHandle<()>,
+    spool_metrics: Option<Arc<Mutex<SpoolMetrics>>>,
+}
+
+impl BufferedSessionWriter {
+    /// Start a buffered writer on the current Tokio runtime, spooling
+    /// rows to `spool` when ClickHouse is unavailable.
+    ///
+    /// Without a spool, rows whose insert fails after the retries are
+    /// dropped and their receipts fail.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the settings are invalid.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use scrybe_storage::{BufferedSessionWriter, ClickHouseClient, SessionWriter, WriteBufferConfig};
+    /// # async fn example(client: ClickHouseClient) -> Result<(), scrybe_core::ScrybeError> {
+    /// let writer = BufferedSessionWriter::spawn(
+    ///     SessionWriter::new(client),
+    ///     WriteBufferConfig::default(),
+    ///     None,
+    /// )?;
+    /// // ... writer.write_batch(&sessions).await? ...
+    /// writer.close().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn(
+        writer: SessionWriter,
+        config: WriteBufferConfig,
+        spool: Option<Spool>,
+    ) -> Result<Self, ScrybeError> {
+        config.validate()?;
+
+        let spool_metrics = spool.as_ref().map(Spool::metrics_handle);
+        let (sender, receiver) = mpsc::channel(config.capacity);
+        let spool = spool.map(|spool| Arc::new(Mutex::new(spool)));
+        let task = tokio::spawn(flush_loop(writer, receiver, config, spool));
+
+        Ok(Self {
+            sender,
+            task,
+            spool_metrics,
+        })
+    }
+
+    /// Current spool usage, if a spool is configured.
+    pub fn spool_metrics(&self) -> Option<SpoolMetrics> {
+        self.spool_metrics.as_ref().map(|metrics| {
+            *metrics
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        })
+    }
+
+    /// Queue sessions for writing.
+    ///
+    /// Waits while the buffer is full. The returned receipt resolves once
+    /// the sessions are stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if a session cannot be converted
+    /// to a row (nothing is queued then) or the writer has stopped.
+    pub async fn write_batch(
+        &self,
+        sessions: &[EnrichedSession],
+    ) -> Result<WriteReceipt, ScrybeError> {
+        if sessions.is_empty() {
+            return Ok(WriteReceipt::completed());
+        }
+
+        let rows = sessions
+            .iter()
+            .map(SessionRow::from_enriched)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (sender, receiver) = oneshot::channel();
+        let completion = Arc::new(Completion {
+            remaining: AtomicUsize::new(rows.len()),
+            sender: Mutex::new(Some(sender)),
+        });
+
+        for row in rows {
+            self.sender
+                .send(Message::Row(Box::new(row), Arc::clone(&completion)))
+                .await
+                .map_err(|_| writer_stopped())?;
+        }
+
+        Ok(WriteReceipt(receiver))
+    }
+
+    /// Flush every row queued so far and wait until it is written or given
+    /// up.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the writer has stopped.
+    pub async fn flush(&self) -> Result<(), ScrybeError> {
+        let (sender, receiver) = oneshot::channel();
+        self.sender
+            .send(Message::Flush(sender))
+            .await
+            .map_err(|_| writer_stopped())?;
+        receiver.await.map_err(|_| writer_stopped())
+    }
+
+    /// Flush the remaining rows and stop the background task.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the background task failed.
+    pub async fn close(self) -> Result<(), ScrybeError> {
+        drop(self.sender);
+        self.task.await.map_err(|e| {
+            ScrybeError::storage_error("clickhouse", format!("Writer task failed: {}", e))
+        })
+    }
+}
+
+/// Collect rows and flush them on size, time, request, or shutdown, and
+/// replay the spool on every tick.
+async fn flush_loop(
+    writer: SessionWriter,
+    mut receiver: mpsc::Receiver<Message>,
+    config: WriteBufferConfig,
+    spool: Option<SharedSpool>,
+) {
+    let mut buffer = Vec::with_capacity(config.batch_size);
+    let mut ticker = tokio::time::interval(config.flush_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            message = receiver.recv() => match message {
+                Some(Message::Row(row, completion)) => {
+                    buffer.push((*row, completion));
+                    if buffer.len() >= config.batch_size {
+                        flush(&writer, &mut buffer, &config, spool.as_ref()).await;
+                        ticker.reset();
+                    }
+                }
+                Some(Message::Flush(done)) => {
+                    flush(&writer, &mut buffer, &config, spool.as_ref()).await;
+                    let _ = done.send(());
+                }
+                None => {
+                    // Every sender is gone: write what is left and stop
+                    flush(&writer, &mut buffer, &config, spool.as_ref()).await;
+                    break;
+                }
+            },
+            _ = ticker.tick() => {
+                flush(&writer, &mut buffer, &config, spool.as_ref()).await;
+                if let Some(spool) = &spool {
+                    replay(&writer, spool, &config).await;
+                }
+            }
+        }
+    }
+
+    if let Some(metrics) = spool.as_ref().map(|spool| lock(spool).metrics()) {
+        if metrics.pending_rows > 0 {
+            info!(
+                "{} rows stay spooled until the next start",
+                metrics.pending_rows
+            );
+        }
+    }
+}
+
+/// Insert the buffered rows, retrying transient failures and spooling
+/// them if the retries run out, and resolve their receipts.
+///
+/// While a flush retries no rows are received, so the channel fills and
+/// writers wait.
+async fn flush(
+    writer: &SessionWriter,
+    buffer: &mut Vec<(SessionRow, Arc<Completion>)>,
+    config: &WriteBufferConfig,
+    spool: Option<&SharedSpool>,
+) {
+    if buffer.is_empty() {
+        return;
+    }
+
+    let (rows, completions): (Vec<_>, Vec<_>) = std::mem::take(buffer).into_iter().unzip();
+    let count = rows.len();
+
+    let result = match spool {
+        // Queue behind the spooled rows so rows are stored in order
+        Some(spool) if !lock(spool).is_empty() => spool_rows(spool, rows).await,
+        _ => match (insert_with_retries(writer, &rows, config).await, spool) {
+            (Ok(()), _) => {
+                debug!("Flushed {} rows", count);
+                Ok(())
+            }
+            (Err(e), Some(spool)) if is_transient(&e) => {
+                warn!("Spooling {} rows after failed insert: {}", count, e);
+                spool_rows(spool, rows).await
+            }
+            (Err(e), _) => Err(e.to_string()),
+        },
+    };
+
+    if let Err(e) = &result {
+        error!("Dropping {} rows: {}", count, e);
+    }
+    for completion in &completions {
+        completion.row_done(&result);
+    }
+    buffer.reserve(config.batch_size);
+}
+
+/// Insert rows, retrying transient failures with backoff.
+async fn insert_with_retries(
+    writer: &SessionWriter,
+    rows: &[SessionRow],
+    config: &WriteBufferConfig,
+) -> Result<(), clickhouse::error::Error> {
+    let mut attempt = 0;
+    loop {
+        match writer.insert_rows(rows).await {
+            Err(e) if is_transient(&e) && attempt < config.max_retries => {
+                attempt += 1;
+                let delay = config.backoff(attempt);
+                warn!(
+                    "Insert of {} rows failed ({}), retry {} of {} in {:?}",
+                    rows.len(),
+                    e,
+                    attempt,
+                    config.max_retries,
+                    delay
+                );
+                tokio::time::sleep(delay).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Spool shared with the blocking tasks doing its file I/O.
+type SharedSpool = Arc<Mutex<Spool>>;
+
+fn lock(spool: &SharedSpool) -> std::sync::MutexGuard<'_, Spool> {
+    spool
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Run a spool operation on the blocking thread pool.
+///
+/// The flush loop awaits each operation before starting the next, so the
+/// lock is never contended.
+async fn with_spool<T, F>(spool: &SharedSpool, operation: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Spool) -> Result<T, ScrybeError> + Send + 'static,
+{
+    let spool = Arc::clone(spool);
+    tokio::task::spawn_blocking(move || operation(&mut lock(&spool)))
+        .await
+        .map_err(|e| format!("Spool task failed: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+async fn spool_rows(spool: &SharedSpool, rows: Vec<SessionRow>) -> Result<(), String> {
+    with_spool(spool, move |spool| spool.append(&rows)).await
+}
+
+/// Replay spooled rows if ClickHouse is healthy again.
+async fn replay(writer: &SessionWriter, spool: &SharedSpool, config: &WriteBufferConfig) {
+    if lock(spool).is_empty() {
+        return;
+    }
+    if let Err(e) = writer.health_check().await {
+        debug!(
+            "Not replaying {} spooled rows: {}",
+            lock(spool).metrics().pending_rows,
+            e
+        );
+        return;
+    }
+
+    for _ in 0..MAX_REPLAY_BATCHES {
+        match replay_batch(writer, spool, config.batch_size).await {
+            Ok(true) => {}
+            Ok(false) => {
+                info!("Spool replayed");
+                break;
+            }
+            Err(e) => {
+                warn!("Spool replay paused: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+/// Insert the next spooled batch, returning `false` once the spool is
+/// empty.
+///
+/// A transient failure leaves the batch in place, marked in flight, so it
+/// is checked for rows already stored before it is retried.
+async fn replay_batch(
+    writer: &SessionWriter,
+    spool: &SharedSpool,
+    batch_size: usize,
+) -> Result<bool, String> {
+    let mut batch = with_spool(spool, move |spool| spool.peek(batch_size)).await?;
+    if batch.lines == 0 {
+        return Ok(false);
+    }
+
+    if batch.in_flight > 0 {
+        let existing = writer
+            .existing_rows(&batch.rows[..batch.in_flight])
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let (in_flight, spooled) = (batch.in_flight, batch.rows.len());
+        let mut index = 0;
+        batch.rows.retain(|row| {
+            let keep = index >= in_flight || !existing.contains(&(row.session_id, row.scored_at));
+            index += 1;
+            keep
+        });
+        debug!(
+            "Skipping {} spooled rows already stored",
+            spooled - batch.rows.len()
+        );
+    }
+
+    let (lines, bytes) = (batch.lines, batch.bytes);
+    with_spool(spool, move |spool| spool.begin(lines)).await?;
+    match writer.insert_rows(&batch.rows).await {
+        Ok(()) => debug!("Replayed {} spooled rows", batch.rows.len()),
+        Err(e) if is_transient(&e) => return Err(e.to_string()),
+        // Retrying a rejected batch would fail forever
+        Err(e) => error!(
+            "Dropping {} spooled rows rejected by ClickHouse: {}",
+            batch.rows.len(),
+            e
+        ),
+    }
+    with_spool(spool, move |spool| spool.commit(lines, bytes)).await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(vars: &[(&str, &str)]) -> Result<WriteBufferConfig, ScrybeError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        WriteBufferConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        assert_eq!(load(&[]).unwrap(), WriteBufferConfig::default());
+
+        let config = load(&[
+            ("SCRYBE_WRITER_BATCH_SIZE", "500"),
+            ("SCRYBE_WRITER_FLUSH_INTERVAL_MS", "250"),
+        ])
+        .unwrap();
+        assert_eq!(config.batch_size, 500);
+        assert_eq!(config.flush_interval, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_config_rejects_invalid() {
+        for vars in [
+            [("SCRYBE_WRITER_BUFFER_CAPACITY", "0")],
+            [("SCRYBE_WRITER_BUFFER_CAPACITY", "1000001")],
+            [("SCRYBE_WRITER_BATCH_SIZE", "0")],
+            [("SCRYBE_WRITER_BATCH_SIZE", "60000")],
+            [("SCRYBE_WRITER_FLUSH_INTERVAL_MS", "0")],
+            [("SCRYBE_WRITER_MAX_RETRIES", "many")],
+        ] {
+            assert!(
+                matches!(load(&vars), Err(ScrybeError::ConfigError(_))),
+                "{:?} should be rejected",
+                vars
+            );
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let config = WriteBufferConfig::default();
+        assert_eq!(config.backoff(1), Duration::from_millis(500));
+        assert_eq!(config.backoff(2), Duration::from_secs(1));
+        assert_eq!(config.backoff(3), Duration::from_secs(2));
+        assert_eq!(config.backoff(10), Duration::from_secs(10));
+        assert_eq!(config.backoff(u32::MAX), Duration::from_secs(10));
+    }
+
+    fn completion(rows: usize) -> (Arc<Completion>, WriteReceipt) {
+        let (sender, receiver) = oneshot::channel();
+        let completion = Arc::new(Completion {
+            remaining: AtomicUsize::new(rows),
+            sender: Mutex::new(Some(sender)),
+        });
+        (completion, WriteReceipt(receiver))
+    }
+
+    #[test]
+    fn test_receipt_resolves_when_all_rows_flushed() {
+        let (completion, mut receipt) = completion(2);
+        assert!(receipt.try_result().is_none());
+
+        completion.row_done(&Ok(()));
+        assert!(receipt.try_result().is_none());
+
+        completion.row_done(&Ok(()));
+        assert!(matches!(receipt.try_result(), Some(Ok(()))));
+    }
+
+    #[test]
+    fn test_receipt_fails_on_first_failed_row() {
+        let (completion, mut receipt) = completion(3);
+
+        completion.row_done(&Err("TOO_MANY_PARTS".to_string()));
+        assert!(matches!(
+            receipt.try_result(),
+            Some(Err(ScrybeError::StorageError { .. }))
+        ));
+
+        // Later rows of the same write no longer change the outcome
+        completion.row_done(&Ok(()));
+        completion.row_done(&Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_empty_write_completes_immediately() {
+        assert!(WriteReceipt::completed().wait().await.is_ok());
+    }
+}
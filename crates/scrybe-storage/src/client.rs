@@ -1,7 +1,53 @@
 //! ClickHouse client with connection pooling.
 
+use crate::migrations::Migrator;
 use clickhouse::Client;
 use scrybe_core::ScrybeError;
+use std::env;
+
+/// ClickHouse connection settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClickHouseConfig {
+    /// Server URL
+    pub url: String,
+    /// Database
+    pub database: String,
+    /// Username
+    pub username: String,
+    /// Password
+    pub password: String,
+}
+
+impl ClickHouseConfig {
+    /// Load connection settings from environment variables.
+    ///
+    /// `SCRYBE_CLICKHOUSE_URL` is required; `SCRYBE_CLICKHOUSE_DATABASE`
+    /// (default: "scrybe"), `SCRYBE_CLICKHOUSE_USERNAME` (default:
+    /// "default") and `SCRYBE_CLICKHOUSE_PASSWORD` (default: empty) are
+    /// optional.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the URL is missing.
+    pub fn from_env() -> Result<Self, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load connection settings from a variable lookup.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the URL is missing.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ScrybeError> {
+        Ok(Self {
+            url: var("SCRYBE_CLICKHOUSE_URL")
+                .ok_or_else(|| ScrybeError::config_error("Missing SCRYBE_CLICKHOUSE_URL"))?,
+            database: var("SCRYBE_CLICKHOUSE_DATABASE").unwrap_or_else(|| "scrybe".to_string()),
+            username: var("SCRYBE_CLICKHOUSE_USERNAME").unwrap_or_else(|| "default".to_string()),
+            password: var("SCRYBE_CLICKHOUSE_PASSWORD").unwrap_or_default(),
+        })
+    }
+}
 
 /// ClickHouse client for session storage.
 ///
@@ -59,6 +105,21 @@ impl ClickHouseClient {
         Ok(Self { client })
     }
 
+    /// Connect with loaded settings.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if connection fails.
+    pub async fn connect(config: &ClickHouseConfig) -> Result<Self, ScrybeError> {
+        Self::new(
+            &config.url,
+            &config.database,
+            &config.username,
+            &config.password,
+        )
+        .await
+    }
+
     /// Get the underlying ClickHouse client.
     pub fn client(&self) -> &Client {
         &self.client
@@ -79,46 +140,48 @@ impl ClickHouseClient {
 
     /// Initialize database schema.
     ///
-    /// Creates the sessions table if it doesn't exist.
+    /// Applies pending [migrations](crate::migrations) in order.
     ///
     /// # Errors
     ///
-    /// Returns `ScrybeError::StorageError` if schema creation fails.
+    /// Returns `ScrybeError::StorageError` if a migration fails or an
+    /// applied migration was modified.
     pub async fn init_schema(&self) -> Result<(), ScrybeError> {
-        let schema = r#"
-            CREATE TABLE IF NOT EXISTS sessions (
-                session_id UUID,
-                timestamp DateTime64(3, 'UTC'),
-                fingerprint_hash String,
-                ip String,
-                user_agent String,
-                network_signals String,
-                browser_signals String,
-                behavioral_signals String,
-                bot_probability Float32,
-                confidence_score Float32,
-                INDEX idx_fingerprint fingerprint_hash TYPE bloom_filter GRANULARITY 1,
-                INDEX idx_ip ip TYPE tokenbf_v1(32768, 3, 0) GRANULARITY 1
-            ) ENGINE = MergeTree()
-            PARTITION BY toYYYYMM(timestamp)
-            ORDER BY (timestamp, session_id)
-            TTL timestamp + INTERVAL 90 DAY
-            SETTINGS index_granularity = 8192;
-        "#;
-
-        self.client.query(schema).execute().await.map_err(|e| {
-            ScrybeError::storage_error("clickhouse", format!("Schema creation failed: {}", e))
-        })?;
-
+        Migrator::new(self.clone()).migrate().await?;
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_config_from_vars() {
+        let vars: HashMap<&str, &str> = [
+            ("SCRYBE_CLICKHOUSE_URL", "http://clickhouse:8123"),
+            ("SCRYBE_CLICKHOUSE_USERNAME", "scrybe"),
+        ]
+        .into();
+        let config =
+            ClickHouseConfig::from_vars(|key| vars.get(key).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(config.url, "http://clickhouse:8123");
+        assert_eq!(config.database, "scrybe");
+        assert_eq!(config.username, "scrybe");
+        assert_eq!(config.password, "");
+
+        assert!(matches!(
+            ClickHouseConfig::from_vars(|_| None),
+            Err(ScrybeError::ConfigError(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_clickhouse_client_compiles() {
         // Placeholder - requires ClickHouse for full testing
-        assert!(true);
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<super::ClickHouseClient>();
     }
 }
@@ -5,7 +5,10 @@
 //! ## Features
 //!
 //! - Batch writes for high throughput
+//! - Write-behind buffering with retries and backpressure
+//! - Disk spool for rows ClickHouse cannot take
 //! - Optimized schema for time-series data
+//! - Versioned schema migrations with drift detection
 //! - Query interface for analytics
 //!
 //! ## TigerStyle Compliance
@@ -18,9 +21,15 @@
 #![warn(rust_2018_idioms)]
 #![deny(unsafe_code)]
 
+pub mod buffer;
 pub mod client;
+pub mod migrations;
+pub mod spool;
 pub mod writer;
 
 // Re-export main types
-pub use client::ClickHouseClient;
+pub use buffer::{BufferedSessionWriter, WriteBufferConfig, WriteReceipt};
+pub use client::{ClickHouseClient, ClickHouseConfig};
+pub use migrations::{MigrationState, MigrationStatus, Migrator};
+pub use spool::{Spool, SpoolConfig, SpoolMetrics};
 pub use writer::SessionWriter;
@@ -0,0 +1,638 @@
+//! Versioned ClickHouse schema migrations.
+//!
+//! The schema lives in numbered SQL files under `migrations/`, embedded in
+//! the binary and applied in order:
+//!
+//! ```text
+//! migrations/0001_create_sessions.sql
+//! migrations/0002_create_session_stats.sql
+//! migrations/0003_add_typed_signal_columns.sql
+//! ```
+//!
+//! Each applied migration is recorded in the `schema_migrations` table with
+//! a SHA-256 checksum of its file. A recorded checksum that no longer
+//! matches means the file was edited after it ran, and is reported as
+//! drift instead of being silently ignored: add a new migration rather than
+//! changing an applied one.
+//!
+//! ClickHouse has no transactional DDL, so a migration that fails halfway
+//! is left unrecorded and runs again from its first statement. Statements
+//! must therefore be idempotent (`IF NOT EXISTS`, `IF EXISTS`), which also
+//! makes it safe for several workers to migrate at the same time. They
+//! must not contain `?` either, which the client reads as a bind
+//! placeholder.
+//!
+//! Databases set up by the `init.sql` of earlier releases already have a
+//! `sessions` table of another shape. Migrating refuses to start on them
+//! rather than skip past it; RFC-0005 describes the upgrade.
+
+use crate::client::ClickHouseClient;
+use scrybe_core::ScrybeError;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tracing::{info, warn};
+
+/// Table recording applied migrations.
+pub const MIGRATIONS_TABLE: &str = "schema_migrations";
+
+/// Columns of the `sessions` table that predate migrations lacks.
+const SESSIONS_COLUMNS: [&str; 2] = ["network_signals", "scored_at"];
+
+/// A schema change, applied once per database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    /// Position in the migration order
+    pub version: u32,
+    /// Short description, matching the file name
+    pub name: &'static str,
+    /// Semicolon-separated SQL statements
+    pub sql: &'static str,
+}
+
+/// Every migration, in order.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_sessions",
+        sql: include_str!("../migrations/0001_create_sessions.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "create_session_stats",
+        sql: include_str!("../migrations/0002_create_session_stats.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "add_typed_signal_columns",
+        sql: include_str!("../migrations/0003_add_typed_signal_columns.sql"),
+    },
+];
+
+impl Migration {
+    /// Hex SHA-256 of the SQL, ignoring line ending style.
+    pub fn checksum(&self) -> String {
+        hex::encode(Sha256::digest(self.sql.replace("\r\n", "\n").as_bytes()))
+    }
+
+    /// The SQL split into statements, since ClickHouse runs one per query.
+    ///
+    /// Splits on `;` outside quotes and comments, and skips statements that
+    /// are only comments.
+    pub fn statements(&self) -> Vec<&'static str> {
+        split_statements(self.sql)
+    }
+}
+
+/// Where a migration stands in a database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "state")]
+pub enum MigrationState {
+    /// Applied with the current file contents
+    Applied,
+    /// Not applied yet
+    Pending,
+    /// Applied, but the file has changed since
+    Modified {
+        /// Checksum recorded when the migration ran
+        applied_checksum: String,
+    },
+    /// Recorded in the database but unknown to this build, e.g. applied by
+    /// a newer release
+    Unknown,
+}
+
+/// A migration and its state.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStatus {
+    /// Position in the migration order
+    pub version: u32,
+    /// Short description
+    pub name: String,
+    /// Checksum of the migration file, or the recorded one if unknown
+    pub checksum: String,
+    /// Applied, pending or drifted
+    #[serde(flatten)]
+    pub state: MigrationState,
+}
+
+/// Row of the `schema_migrations` table.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, clickhouse::Row)]
+struct AppliedMigration {
+    version: u32,
+    name: String,
+    checksum: String,
+}
+
+/// Applies and inspects schema migrations.
+#[derive(Clone)]
+pub struct Migrator {
+    client: ClickHouseClient,
+    migrations: &'static [Migration],
+}
+
+impl Migrator {
+    /// Create a migrator for the embedded [`MIGRATIONS`].
+    pub fn new(client: ClickHouseClient) -> Self {
+        Self {
+            client,
+            migrations: MIGRATIONS,
+        }
+    }
+
+    /// State of every known and recorded migration, ordered by version.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the migrations table cannot be
+    /// created or read.
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>, ScrybeError> {
+        let applied = self.applied().await?;
+        Ok(statuses(self.migrations, &applied))
+    }
+
+    /// Apply pending migrations in order.
+    ///
+    /// Returns the versions applied, empty if the schema was up to date.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if an applied migration was
+    /// modified, or if a statement fails; earlier migrations stay applied.
+    pub async fn migrate(&self) -> Result<Vec<u32>, ScrybeError> {
+        let statuses = self.status().await?;
+        check_drift(&statuses)?;
+        if statuses
+            .iter()
+            .any(|s| s.version == 1 && s.state == MigrationState::Pending)
+        {
+            self.check_legacy_schema().await?;
+        }
+
+        let mut applied = Vec::new();
+        for status in &statuses {
+            match status.state {
+                MigrationState::Pending => {}
+                MigrationState::Unknown => {
+                    warn!(
+                        "Migration {} ({}) was applied by another release",
+                        status.version, status.name
+                    );
+                    continue;
+                }
+                _ => continue,
+            }
+
+            let Some(migration) = self.migrations.iter().find(|m| m.version == status.version)
+            else {
+                continue;
+            };
+
+            self.apply(migration).await?;
+            info!(
+                "Applied migration {} ({})",
+                migration.version, migration.name
+            );
+            applied.push(migration.version);
+        }
+
+        Ok(applied)
+    }
+
+    /// Check the schema is current without changing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if a migration is pending or was
+    /// modified after it was applied.
+    pub async fn verify(&self) -> Result<(), ScrybeError> {
+        let statuses = self.status().await?;
+        check_drift(&statuses)?;
+
+        let pending: Vec<String> = statuses
+            .iter()
+            .filter(|s| s.state == MigrationState::Pending)
+            .map(|s| format!("{} ({})", s.version, s.name))
+            .collect();
+        if !pending.is_empty() {
+            return Err(ScrybeError::storage_error(
+                "migrations",
+                format!("Pending migrations: {}", pending.join(", ")),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Fail if a `sessions` table created before migrations exists, which
+    /// `IF NOT EXISTS` would silently keep.
+    async fn check_legacy_schema(&self) -> Result<(), ScrybeError> {
+        let read_error = |e: clickhouse::error::Error| {
+            ScrybeError::storage_error("migrations", format!("Reading the schema failed: {}", e))
+        };
+
+        let engine: Option<String> = self
+            .client
+            .client()
+            .query(
+                "SELECT engine FROM system.tables \
+                 WHERE database = currentDatabase() AND name = 'sessions'",
+            )
+            .fetch_optional()
+            .await
+            .map_err(read_error)?;
+        let Some(engine) = engine else {
+            return Ok(());
+        };
+
+        let columns: Vec<String> = self
+            .client
+            .client()
+            .query(
+                "SELECT name FROM system.columns \
+                 WHERE database = currentDatabase() AND table = 'sessions'",
+            )
+            .fetch_all()
+            .await
+            .map_err(read_error)?;
+
+        match legacy_sessions(&engine, &columns) {
+            Some(reason) => Err(ScrybeError::storage_error(
+                "migrations",
+                format!(
+                    "Table sessions predates schema migrations ({}). Rename it and drop \
+                     session_stats_mv, then migrate again; see \"Upgrading from init.sql\" \
+                     in RFC-0005",
+                    reason
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Run every statement of a migration, then record it.
+    async fn apply(&self, migration: &Migration) -> Result<(), ScrybeError> {
+        for (index, statement) in migration.statements().iter().enumerate() {
+            self.client
+                .client()
+                .query(statement)
+                .execute()
+                .await
+                .map_err(|e| {
+                    ScrybeError::storage_error(
+                        "migrations",
+                        format!(
+                            "Migration {} ({}) failed at statement {}: {}",
+                            migration.version,
+                            migration.name,
+                            index + 1,
+                            e
+                        ),
+                    )
+                })?;
+        }
+
+        self.client
+            .client()
+            .query("INSERT INTO schema_migrations (version, name, checksum) VALUES (?, ?, ?)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .execute()
+            .await
+            .map_err(|e| {
+                ScrybeError::storage_error(
+                    "migrations",
+                    format!("Recording migration {} failed: {}", migration.version, e),
+                )
+            })
+    }
+
+    /// Create the migrations table if needed and read it.
+    async fn applied(&self) -> Result<Vec<AppliedMigration>, ScrybeError> {
+        // Concurrent migrators may record a version twice; the replacing
+        // engine and FINAL collapse the duplicates
+        let create = r#"
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version UInt32,
+                name String,
+                checksum String,
+                applied_at DateTime64(3, 'UTC') DEFAULT now64(3)
+            ) ENGINE = ReplacingMergeTree(applied_at)
+            ORDER BY version
+        "#;
+
+        self.client
+            .client()
+            .query(create)
+            .execute()
+            .await
+            .map_err(|e| {
+                ScrybeError::storage_error(
+                    "migrations",
+                    format!("Creating {} failed: {}", MIGRATIONS_TABLE, e),
+                )
+            })?;
+
+        self.client
+            .client()
+            .query("SELECT version, name, checksum FROM schema_migrations FINAL ORDER BY version")
+            .fetch_all()
+            .await
+            .map_err(|e| {
+                ScrybeError::storage_error(
+                    "migrations",
+                    format!("Reading {} failed: {}", MIGRATIONS_TABLE, e),
+                )
+            })
+    }
+}
+
+/// Compare the known migrations with the recorded ones.
+fn statuses(migrations: &[Migration], applied: &[AppliedMigration]) -> Vec<MigrationStatus> {
+    let recorded: HashMap<u32, &AppliedMigration> =
+        applied.iter().map(|a| (a.version, a)).collect();
+
+    let mut statuses: Vec<MigrationStatus> = migrations
+        .iter()
+        .map(|migration| {
+            let checksum = migration.checksum();
+            let state = match recorded.get(&migration.version) {
+                None => MigrationState::Pending,
+                Some(applied) if applied.checksum == checksum => MigrationState::Applied,
+                Some(applied) => MigrationState::Modified {
+                    applied_checksum: applied.checksum.clone(),
+                },
+            };
+            MigrationStatus {
+                version: migration.version,
+                name: migration.name.to_string(),
+                checksum,
+                state,
+            }
+        })
+        .collect();
+
+    for applied in applied {
+        if !migrations.iter().any(|m| m.version == applied.version) {
+            statuses.push(MigrationStatus {
+                version: applied.version,
+                name: applied.name.clone(),
+                checksum: applied.checksum.clone(),
+                state: MigrationState::Unknown,
+            });
+        }
+    }
+
+    statuses.sort_by_key(|s| s.version);
+    statuses
+}
+
+/// Why a `sessions` table with this engine and columns was not created by
+/// migration 1, if it was not.
+fn legacy_sessions(engine: &str, columns: &[String]) -> Option<String> {
+    let missing: Vec<&str> = SESSIONS_COLUMNS
+        .iter()
+        .copied()
+        .filter(|name| !columns.iter().any(|column| column == name))
+        .collect();
+
+    if !missing.is_empty() {
+        Some(format!("no {} column", missing.join(" or ")))
+    } else if engine != "ReplacingMergeTree" {
+        Some(format!("{} engine", engine))
+    } else {
+        None
+    }
+}
+
+/// Fail if any applied migration was modified.
+fn check_drift(statuses: &[MigrationStatus]) -> Result<(), ScrybeError> {
+    let modified: Vec<String> = statuses
+        .iter()
+        .filter(|s| matches!(s.state, MigrationState::Modified { .. }))
+        .map(|s| format!("{} ({})", s.version, s.name))
+        .collect();
+
+    if modified.is_empty() {
+        return Ok(());
+    }
+
+    Err(ScrybeError::storage_error(
+        "migrations",
+        format!(
+            "Migrations changed after they were applied: {}",
+            modified.join(", ")
+        ),
+    ))
+}
+
+/// Split SQL on `;` outside string literals, quoted identifiers and
+/// comments.
+fn split_statements(sql: &str) -> Vec<&str> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut i = 0;
+
+    let mut push = |from: usize, to: usize, has_code: bool| {
+        let statement = sql[from..to].trim();
+        if has_code && !statement.is_empty() {
+            statements.push(statement);
+        }
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < bytes.len() && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/')) {
+                    i += 1;
+                }
+                i += 2;
+            }
+            quote @ (b'\'' | b'"' | b'`') => {
+                has_code = true;
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    // Backslash escapes; doubled quotes are two literals
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+            }
+            b';' => {
+                push(start, i, has_code);
+                has_code = false;
+                start = i + 1;
+                i += 1;
+            }
+            byte => {
+                if !byte.is_ascii_whitespace() {
+                    has_code = true;
+                }
+                i += 1;
+            }
+        }
+    }
+    push(start, sql.len(), has_code);
+
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(version: u32, checksum: &str) -> AppliedMigration {
+        AppliedMigration {
+            version,
+            name: format!("migration_{}", version),
+            checksum: checksum.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_migration_files_are_registered_in_order() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        let mut files: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".sql"))
+            .collect();
+        files.sort();
+
+        let registered: Vec<String> = MIGRATIONS
+            .iter()
+            .map(|m| format!("{:04}_{}.sql", m.version, m.name))
+            .collect();
+        assert_eq!(files, registered);
+
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, index + 1);
+            assert!(!migration.statements().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_checksum_ignores_line_endings() {
+        let unix = Migration {
+            version: 1,
+            name: "m",
+            sql: "CREATE TABLE a (x UInt8);\n",
+        };
+        let windows = Migration {
+            sql: "CREATE TABLE a (x UInt8);\r\n",
+            ..unix
+        };
+        let changed = Migration {
+            sql: "CREATE TABLE a (x UInt16);\n",
+            ..unix
+        };
+
+        assert_eq!(unix.checksum().len(), 64);
+        assert_eq!(unix.checksum(), windows.checksum());
+        assert_ne!(unix.checksum(), changed.checksum());
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let sql = "-- leading comment; not a statement\n\
+                   CREATE TABLE a (s String DEFAULT 'x;y');\n\
+                   /* block; comment */ ALTER TABLE a COMMENT 'it\\'s; fine';\n\
+                   SELECT `odd;name` FROM a\n\
+                   -- trailing comment;\n";
+
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with("DEFAULT 'x;y')"));
+        assert!(statements[1].ends_with("'it\\'s; fine'"));
+        assert!(statements[2].starts_with("SELECT `odd;name`"));
+    }
+
+    #[test]
+    fn test_statuses() {
+        let migrations = [
+            Migration {
+                version: 1,
+                name: "one",
+                sql: "SELECT 1",
+            },
+            Migration {
+                version: 2,
+                name: "two",
+                sql: "SELECT 2",
+            },
+            Migration {
+                version: 3,
+                name: "three",
+                sql: "SELECT 3",
+            },
+        ];
+        let recorded = [
+            applied(1, &migrations[0].checksum()),
+            applied(2, "stale"),
+            applied(7, "future"),
+        ];
+
+        let statuses = statuses(&migrations, &recorded);
+        let states: Vec<(u32, &MigrationState)> =
+            statuses.iter().map(|s| (s.version, &s.state)).collect();
+        assert_eq!(
+            states,
+            vec![
+                (1, &MigrationState::Applied),
+                (
+                    2,
+                    &MigrationState::Modified {
+                        applied_checksum: "stale".to_string()
+                    }
+                ),
+                (3, &MigrationState::Pending),
+                (7, &MigrationState::Unknown),
+            ]
+        );
+
+        assert!(matches!(
+            check_drift(&statuses),
+            Err(ScrybeError::StorageError { .. })
+        ));
+        assert!(check_drift(&statuses[2..]).is_ok());
+    }
+
+    #[test]
+    fn test_legacy_sessions() {
+        let columns =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+        // Created by migration 1, possibly before a later statement failed
+        assert_eq!(
+            legacy_sessions(
+                "ReplacingMergeTree",
+                &columns(&["session_id", "network_signals", "scored_at"])
+            ),
+            None
+        );
+
+        // Created by the init.sql of earlier releases
+        assert_eq!(
+            legacy_sessions("MergeTree", &columns(&["session_id", "ja3", "ja4"])),
+            Some("no network_signals or scored_at column".to_string())
+        );
+        assert_eq!(
+            legacy_sessions(
+                "MergeTree",
+                &columns(&["session_id", "network_signals", "scored_at"])
+            ),
+            Some("MergeTree engine".to_string())
+        );
+    }
+}
@@ -0,0 +1,761 @@
+//! Disk spool for rows ClickHouse could not take.
+//!
+//! When inserts keep failing, the [`BufferedSessionWriter`] appends the
+//! rows to segment files in a local directory instead of dropping them,
+//! and replays them in order once ClickHouse passes a health check:
+//!
+//! ```text
+//! <dir>/00000000000000000001.jsonl    # Oldest segment, one JSON row per line
+//! <dir>/00000000000000000002.jsonl
+//! <dir>/checkpoint.json               # Replay position and in-flight rows
+//! ```
+//!
+//! Appends are synced before they are reported stored, so spooled rows
+//! survive a restart. The checkpoint records the next row to replay, with
+//! its byte offset so replay seeks to it instead of re-reading the
+//! segment, and how many rows are being inserted; if the process stops
+//! mid-insert, those rows are checked against ClickHouse by `session_id`
+//! on the next replay instead of being inserted twice.
+//!
+//! The spool is bounded by a disk budget: once exceeded, the oldest
+//! segments are deleted and counted in [`SpoolMetrics`].
+//!
+//! [`BufferedSessionWriter`]: crate::BufferedSessionWriter
+
+use crate::writer::SessionRow;
+use scrybe_core::ScrybeError;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// Extension of segment files.
+const SEGMENT_EXTENSION: &str = "jsonl";
+
+/// Name of the checkpoint file.
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+/// Smallest accepted segment size (DoS protection against a directory of
+/// millions of tiny files).
+pub const MIN_SEGMENT_BYTES: u64 = 64 * 1024;
+
+/// Spool location and size limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpoolConfig {
+    /// Directory holding the segments and checkpoint
+    pub dir: PathBuf,
+    /// Size at which a new segment is started
+    pub segment_bytes: u64,
+    /// Disk budget; the oldest segments are evicted beyond it
+    pub max_bytes: u64,
+}
+
+impl SpoolConfig {
+    /// Spool in `dir` with the default limits (64 MiB segments, 1 GiB
+    /// budget).
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_bytes: 64 * 1024 * 1024,
+            max_bytes: 1024 * 1024 * 1024,
+        }
+    }
+
+    /// Load spool settings from environment variables.
+    ///
+    /// `SCRYBE_WRITER_SPOOL_DIR` enables the spool;
+    /// `SCRYBE_WRITER_SPOOL_MAX_BYTES` and
+    /// `SCRYBE_WRITER_SPOOL_SEGMENT_BYTES` override the limits. Returns
+    /// `None` if no directory is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a limit is not a number or out
+    /// of range.
+    pub fn from_env() -> Result<Option<Self>, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load spool settings from a variable lookup.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if a limit is not a number or out
+    /// of range.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Option<Self>, ScrybeError> {
+        let Some(dir) = var("SCRYBE_WRITER_SPOOL_DIR") else {
+            return Ok(None);
+        };
+
+        let defaults = Self::new(dir);
+        let config = Self {
+            segment_bytes: parse(
+                &var,
+                "SCRYBE_WRITER_SPOOL_SEGMENT_BYTES",
+                defaults.segment_bytes,
+            )?,
+            max_bytes: parse(&var, "SCRYBE_WRITER_SPOOL_MAX_BYTES", defaults.max_bytes)?,
+            ..defaults
+        };
+        config.validate()?;
+        Ok(Some(config))
+    }
+
+    /// Check the limits are usable.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if segments are smaller than
+    /// [`MIN_SEGMENT_BYTES`] or the budget holds fewer than two segments.
+    pub fn validate(&self) -> Result<(), ScrybeError> {
+        if self.segment_bytes < MIN_SEGMENT_BYTES {
+            return Err(ScrybeError::config_error(format!(
+                "Spool segment size must be at least {} bytes",
+                MIN_SEGMENT_BYTES
+            )));
+        }
+        if self.max_bytes < self.segment_bytes.saturating_mul(2) {
+            return Err(ScrybeError::config_error(
+                "Spool budget must hold at least two segments",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Parse an optional variable, falling back to a default.
+fn parse<T>(var: impl Fn(&str) -> Option<String>, key: &str, default: T) -> Result<T, ScrybeError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match var(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|e| ScrybeError::config_error(format!("Invalid {}: {}", key, e))),
+        None => Ok(default),
+    }
+}
+
+/// Point-in-time spool usage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SpoolMetrics {
+    /// Segment files on disk
+    pub segments: usize,
+    /// Bytes of all segments
+    pub bytes: u64,
+    /// Rows waiting to be replayed
+    pub pending_rows: u64,
+    /// Rows deleted unreplayed to stay within the disk budget
+    pub evicted_rows: u64,
+    /// Bytes deleted to stay within the disk budget
+    pub evicted_bytes: u64,
+}
+
+/// Replay position, persisted across restarts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    /// Segment being replayed
+    segment: u64,
+    /// Rows of that segment already replayed
+    row: u64,
+    /// Byte offset of row `row` in that segment
+    #[serde(default)]
+    offset: u64,
+    /// Rows from `row` on that were being inserted and may already be
+    /// stored
+    in_flight: u64,
+}
+
+/// Rows read for replay.
+#[derive(Default)]
+pub(crate) struct SpooledRows {
+    /// Rows in spool order
+    pub(crate) rows: Vec<SessionRow>,
+    /// Lines consumed, including unreadable ones
+    pub(crate) lines: usize,
+    /// Bytes of the lines consumed
+    pub(crate) bytes: u64,
+    /// Leading rows that may already be stored because an insert of them
+    /// was interrupted
+    pub(crate) in_flight: usize,
+}
+
+/// One segment file.
+#[derive(Debug)]
+struct Segment {
+    id: u64,
+    bytes: u64,
+    rows: u64,
+}
+
+/// Append-only row spool on local disk.
+pub struct Spool {
+    config: SpoolConfig,
+    segments: VecDeque<Segment>,
+    /// Open handle of the newest segment while it takes appends
+    active: Option<File>,
+    checkpoint: Checkpoint,
+    next_id: u64,
+    evicted_rows: u64,
+    evicted_bytes: u64,
+    metrics: Arc<Mutex<SpoolMetrics>>,
+}
+
+impl Spool {
+    /// Open the spool, creating its directory if needed and resuming from
+    /// any segments and checkpoint left by a previous run.
+    ///
+    /// A row cut short by a crash mid-append is discarded; it was never
+    /// reported stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if the limits are invalid and
+    /// `ScrybeError::StorageError` if the directory cannot be read.
+    pub fn open(config: SpoolConfig) -> Result<Self, ScrybeError> {
+        config.validate()?;
+        Self::recover(config).map_err(|e| io_error("open", e))
+    }
+
+    fn recover(config: SpoolConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+
+        let mut ids = Vec::new();
+        for entry in fs::read_dir(&config.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SEGMENT_EXTENSION) {
+                continue;
+            }
+            if let Some(id) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse::<u64>().ok())
+            {
+                ids.push(id);
+            }
+        }
+        ids.sort_unstable();
+
+        let checkpoint = read_checkpoint(&config.dir)?;
+
+        let mut segments = VecDeque::with_capacity(ids.len());
+        for id in ids {
+            let path = segment_path(&config.dir, id);
+            // Segments before the checkpoint were replayed before a crash
+            // kept them from being deleted
+            if id < checkpoint.segment {
+                fs::remove_file(&path)?;
+                continue;
+            }
+            let (bytes, rows) = truncate_torn_tail(&path)?;
+            segments.push_back(Segment { id, bytes, rows });
+        }
+
+        let next_id = segments
+            .back()
+            .map_or(checkpoint.segment.max(1), |segment| segment.id + 1);
+
+        // The checkpoint's segment is gone if it was replayed or evicted
+        // just before the process stopped
+        let mut checkpoint = match segments.front() {
+            Some(front) if front.id != checkpoint.segment => Checkpoint {
+                segment: front.id,
+                ..Checkpoint::default()
+            },
+            _ => checkpoint,
+        };
+        // Checkpoints written before offsets were recorded
+        if checkpoint.row > 0 && checkpoint.offset == 0 {
+            checkpoint.offset = line_offset(
+                &segment_path(&config.dir, checkpoint.segment),
+                checkpoint.row,
+            )?;
+        }
+
+        let mut spool = Self {
+            config,
+            segments,
+            active: None,
+            checkpoint,
+            next_id,
+            evicted_rows: 0,
+            evicted_bytes: 0,
+            metrics: Arc::new(Mutex::new(SpoolMetrics::default())),
+        };
+        spool.drop_replayed()?;
+        spool.publish();
+        Ok(spool)
+    }
+
+    /// Shared handle to the current usage, updated after every operation.
+    pub fn metrics_handle(&self) -> Arc<Mutex<SpoolMetrics>> {
+        Arc::clone(&self.metrics)
+    }
+
+    /// Current usage.
+    pub fn metrics(&self) -> SpoolMetrics {
+        *self
+            .metrics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Whether no rows are waiting to be replayed.
+    pub fn is_empty(&self) -> bool {
+        self.pending_rows() == 0
+    }
+
+    fn pending_rows(&self) -> u64 {
+        let total: u64 = self.segments.iter().map(|segment| segment.rows).sum();
+        let replayed = match self.segments.front() {
+            Some(front) if front.id == self.checkpoint.segment => self.checkpoint.row,
+            _ => 0,
+        };
+        total.saturating_sub(replayed)
+    }
+
+    /// Append rows and sync them to disk, evicting the oldest segments if
+    /// the budget is exceeded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the rows cannot be written.
+    pub(crate) fn append(&mut self, rows: &[SessionRow]) -> Result<(), ScrybeError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let mut buf = Vec::new();
+        for row in rows {
+            serde_json::to_writer(&mut buf, row).map_err(|e| {
+                ScrybeError::storage_error("spool", format!("Serialization failed: {}", e))
+            })?;
+            buf.push(b'\n');
+        }
+
+        self.write(&buf, rows.len() as u64)
+            .map_err(|e| io_error("append", e))?;
+        self.evict().map_err(|e| io_error("evict", e))?;
+        self.publish();
+        Ok(())
+    }
+
+    fn write(&mut self, buf: &[u8], rows: u64) -> io::Result<()> {
+        let full = match self.segments.back() {
+            Some(segment) => segment.bytes >= self.config.segment_bytes,
+            None => true,
+        };
+        if self.active.is_none() || full {
+            let id = self.next_id;
+            self.next_id += 1;
+            let file = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(segment_path(&self.config.dir, id))?;
+            if self.segments.is_empty() {
+                self.checkpoint = Checkpoint {
+                    segment: id,
+                    ..Checkpoint::default()
+                };
+                self.save_checkpoint()?;
+            }
+            self.segments.push_back(Segment {
+                id,
+                bytes: 0,
+                rows: 0,
+            });
+            self.active = Some(file);
+        }
+
+        if let (Some(file), Some(segment)) = (self.active.as_mut(), self.segments.back_mut()) {
+            file.write_all(buf)?;
+            file.sync_data()?;
+            segment.bytes += buf.len() as u64;
+            segment.rows += rows;
+        }
+        Ok(())
+    }
+
+    /// Delete the oldest segments until the spool fits its budget.
+    fn evict(&mut self) -> io::Result<()> {
+        while self.bytes() > self.config.max_bytes && self.segments.len() > 1 {
+            let Some(oldest) = self.segments.pop_front() else {
+                break;
+            };
+            fs::remove_file(segment_path(&self.config.dir, oldest.id))?;
+
+            let replayed = if oldest.id == self.checkpoint.segment {
+                self.checkpoint.row
+            } else {
+                0
+            };
+            let lost = oldest.rows.saturating_sub(replayed);
+            self.evicted_rows += lost;
+            self.evicted_bytes += oldest.bytes;
+            warn!(
+                "Spool over its {} byte budget, evicted segment {} with {} unreplayed rows",
+                self.config.max_bytes, oldest.id, lost
+            );
+
+            if let Some(next) = self.segments.front() {
+                self.checkpoint = Checkpoint {
+                    segment: next.id,
+                    ..Checkpoint::default()
+                };
+                self.save_checkpoint()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The next rows to replay, at most `max`, all from one segment.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the segment cannot be read.
+    pub(crate) fn peek(&self, max: usize) -> Result<SpooledRows, ScrybeError> {
+        let mut batch = SpooledRows::default();
+        let Some(segment) = self.segments.front() else {
+            return Ok(batch);
+        };
+
+        let mut file = File::open(segment_path(&self.config.dir, segment.id))
+            .map_err(|e| io_error("read", e))?;
+        file.seek(SeekFrom::Start(self.checkpoint.offset))
+            .map_err(|e| io_error("seek", e))?;
+
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        while batch.lines < max.max(1) {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(|e| io_error("read", e))?;
+            if read == 0 {
+                break;
+            }
+            batch.bytes += read as u64;
+
+            match serde_json::from_str::<SessionRow>(&line) {
+                Ok(mut row) => {
+                    if let Err(e) = row.backfill_signal_columns() {
+                        warn!("Spooled row {} lacks signal columns: {}", row.session_id, e);
+                    }
+                    batch.rows.push(row);
+                    if (batch.lines as u64) < self.checkpoint.in_flight {
+                        batch.in_flight += 1;
+                    }
+                }
+                Err(e) => warn!("Skipping unreadable spooled row: {}", e),
+            }
+            batch.lines += 1;
+        }
+
+        Ok(batch)
+    }
+
+    /// Record that the next `lines` rows are being inserted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the checkpoint cannot be
+    /// saved.
+    pub(crate) fn begin(&mut self, lines: usize) -> Result<(), ScrybeError> {
+        self.checkpoint.in_flight = lines as u64;
+        self.save_checkpoint()
+            .map_err(|e| io_error("checkpoint", e))
+    }
+
+    /// Record that the next `lines` rows, `bytes` long, are stored,
+    /// deleting segments that are fully replayed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::StorageError` if the checkpoint cannot be
+    /// saved or a segment deleted.
+    pub(crate) fn commit(&mut self, lines: usize, bytes: u64) -> Result<(), ScrybeError> {
+        self.checkpoint.row += lines as u64;
+        self.checkpoint.offset += bytes;
+        self.checkpoint.in_flight = 0;
+        self.save_checkpoint()
+            .and_then(|()| self.drop_replayed())
+            .map_err(|e| io_error("checkpoint", e))?;
+        self.publish();
+        Ok(())
+    }
+
+    /// Delete leading segments whose rows have all been replayed.
+    fn drop_replayed(&mut self) -> io::Result<()> {
+        while let Some(front) = self.segments.front() {
+            if front.id != self.checkpoint.segment || self.checkpoint.row < front.rows {
+                break;
+            }
+            // Stop appending to the segment before deleting it
+            if self.segments.len() == 1 {
+                self.active = None;
+            }
+            fs::remove_file(segment_path(&self.config.dir, front.id))?;
+            self.segments.pop_front();
+
+            self.checkpoint = Checkpoint {
+                segment: self.segments.front().map_or(self.next_id, |next| next.id),
+                ..Checkpoint::default()
+            };
+            self.save_checkpoint()?;
+        }
+        Ok(())
+    }
+
+    fn bytes(&self) -> u64 {
+        self.segments.iter().map(|segment| segment.bytes).sum()
+    }
+
+    /// Write the checkpoint atomically.
+    fn save_checkpoint(&self) -> io::Result<()> {
+        let path = self.config.dir.join(CHECKPOINT_FILE);
+        let tmp = path.with_extension("json.tmp");
+
+        let mut file = File::create(&tmp)?;
+        serde_json::to_writer(&mut file, &self.checkpoint)?;
+        file.sync_data()?;
+        fs::rename(tmp, path)
+    }
+
+    fn publish(&self) {
+        let metrics = SpoolMetrics {
+            segments: self.segments.len(),
+            bytes: self.bytes(),
+            pending_rows: self.pending_rows(),
+            evicted_rows: self.evicted_rows,
+            evicted_bytes: self.evicted_bytes,
+        };
+        *self
+            .metrics
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = metrics;
+    }
+}
+
+fn segment_path(dir: &Path, id: u64) -> PathBuf {
+    dir.join(format!("{:020}.{}", id, SEGMENT_EXTENSION))
+}
+
+fn read_checkpoint(dir: &Path) -> io::Result<Checkpoint> {
+    match fs::read(dir.join(CHECKPOINT_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(io::Error::from),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Checkpoint::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Cut a segment back to its last complete row, returning its size and
+/// row count.
+fn truncate_torn_tail(path: &Path) -> io::Result<(u64, u64)> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+
+    let complete = contents
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |i| i + 1);
+    if complete < contents.len() {
+        warn!(
+            "Discarding {} bytes of a torn row in {}",
+            contents.len() - complete,
+            path.display()
+        );
+        file.set_len(complete as u64)?;
+        file.sync_data()?;
+    }
+
+    let rows = contents[..complete].iter().filter(|b| **b == b'\n').count();
+    Ok((complete as u64, rows as u64))
+}
+
+/// Byte offset of line `rows` of a segment.
+fn line_offset(path: &Path, rows: u64) -> io::Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut offset = 0;
+    let mut line = Vec::new();
+    for _ in 0..rows {
+        line.clear();
+        let read = reader.read_until(b'\n', &mut line)?;
+        if read == 0 {
+            break;
+        }
+        offset += read as u64;
+    }
+    Ok(offset)
+}
+
+fn io_error(operation: &str, e: impl std::fmt::Display) -> ScrybeError {
+    ScrybeError::storage_error("spool", format!("{} failed: {}", operation, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use uuid::Uuid;
+
+    fn row(id: u128) -> SessionRow {
+        SessionRow {
+            session_id: Uuid::from_u128(id),
+            ip: "127.0.0.1".to_string(),
+            user_agent: "test".to_string(),
+            network_signals: "{}".to_string(),
+            browser_signals: "{}".to_string(),
+            behavioral_signals: "{}".to_string(),
+            client_ip: Ipv4Addr::LOCALHOST.to_ipv6_mapped(),
+            ..SessionRow::default()
+        }
+    }
+
+    fn ids(rows: &[SessionRow]) -> Vec<u128> {
+        rows.iter().map(|row| row.session_id.as_u128()).collect()
+    }
+
+    fn config(dir: &Path) -> SpoolConfig {
+        SpoolConfig {
+            dir: dir.to_path_buf(),
+            segment_bytes: MIN_SEGMENT_BYTES,
+            max_bytes: MIN_SEGMENT_BYTES * 4,
+        }
+    }
+
+    #[test]
+    fn test_replays_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(config(dir.path())).unwrap();
+        assert!(spool.is_empty());
+
+        spool.append(&[row(1), row(2)]).unwrap();
+        spool.append(&[row(3)]).unwrap();
+        assert_eq!(spool.metrics().pending_rows, 3);
+
+        let batch = spool.peek(2).unwrap();
+        assert_eq!(ids(&batch.rows), [1, 2]);
+        assert_eq!(batch.in_flight, 0);
+        spool.begin(batch.lines).unwrap();
+        spool.commit(batch.lines, batch.bytes).unwrap();
+
+        let batch = spool.peek(10).unwrap();
+        assert_eq!(ids(&batch.rows), [3]);
+        spool.begin(batch.lines).unwrap();
+        spool.commit(batch.lines, batch.bytes).unwrap();
+
+        assert!(spool.is_empty());
+        assert_eq!(spool.metrics().segments, 0);
+
+        // Appending after draining starts a fresh segment
+        spool.append(&[row(4)]).unwrap();
+        assert_eq!(ids(&spool.peek(10).unwrap().rows), [4]);
+    }
+
+    #[test]
+    fn test_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut spool = Spool::open(config(dir.path())).unwrap();
+            spool.append(&[row(1), row(2), row(3)]).unwrap();
+            let batch = spool.peek(1).unwrap();
+            spool.begin(batch.lines).unwrap();
+            spool.commit(batch.lines, batch.bytes).unwrap();
+            // Stopped while inserting "b"
+            spool.begin(1).unwrap();
+        }
+
+        let spool = Spool::open(config(dir.path())).unwrap();
+        let batch = spool.peek(10).unwrap();
+        assert_eq!(ids(&batch.rows), [2, 3]);
+        assert_eq!(batch.in_flight, 1);
+    }
+
+    #[test]
+    fn test_resumes_checkpoint_without_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut spool = Spool::open(config(dir.path())).unwrap();
+            spool.append(&[row(1), row(2), row(3)]).unwrap();
+        }
+        fs::write(
+            dir.path().join(CHECKPOINT_FILE),
+            r#"{"segment":1,"row":2,"in_flight":0}"#,
+        )
+        .unwrap();
+
+        let spool = Spool::open(config(dir.path())).unwrap();
+        assert_eq!(ids(&spool.peek(10).unwrap().rows), [3]);
+    }
+
+    #[test]
+    fn test_discards_torn_row() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut spool = Spool::open(config(dir.path())).unwrap();
+            spool.append(&[row(1)]).unwrap();
+        }
+        let segment = segment_path(dir.path(), 1);
+        OpenOptions::new()
+            .append(true)
+            .open(&segment)
+            .unwrap()
+            .write_all(b"{\"session_id\":\"tor")
+            .unwrap();
+
+        let mut spool = Spool::open(config(dir.path())).unwrap();
+        assert_eq!(spool.metrics().pending_rows, 1);
+        spool.append(&[row(2)]).unwrap();
+
+        let batch = spool.peek(10).unwrap();
+        assert_eq!(ids(&batch.rows), [1]);
+        spool.commit(batch.lines, batch.bytes).unwrap();
+        assert_eq!(ids(&spool.peek(10).unwrap().rows), [2]);
+    }
+
+    #[test]
+    fn test_evicts_oldest_over_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(config(dir.path())).unwrap();
+
+        // Each append fills a segment, so every append starts a new one
+        let batch: Vec<SessionRow> = (0..150).map(row).collect();
+        for _ in 0..6 {
+            spool.append(&batch).unwrap();
+        }
+
+        let metrics = spool.metrics();
+        assert!(metrics.bytes <= MIN_SEGMENT_BYTES * 4);
+        assert!(metrics.evicted_rows > 0);
+        assert_eq!(metrics.pending_rows + metrics.evicted_rows, 900);
+        // Replay resumes at the oldest surviving segment
+        assert_eq!(spool.peek(1).unwrap().rows[0].session_id.as_u128(), 0);
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        assert!(SpoolConfig::from_vars(|_| None).unwrap().is_none());
+
+        let config = SpoolConfig::from_vars(|key| match key {
+            "SCRYBE_WRITER_SPOOL_DIR" => Some("/var/lib/scrybe/spool".to_string()),
+            _ => None,
+        })
+        .unwrap()
+        .unwrap();
+        assert_eq!(config, SpoolConfig::new("/var/lib/scrybe/spool"));
+
+        let result = SpoolConfig::from_vars(|key| match key {
+            "SCRYBE_WRITER_SPOOL_DIR" => Some("/tmp/spool".to_string()),
+            "SCRYBE_WRITER_SPOOL_MAX_BYTES" => Some("1000".to_string()),
+            _ => None,
+        });
+        assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+    }
+}
@@ -2,12 +2,15 @@
 //!
 //! These tests require Docker to be running.
 
+use scrybe_core::test_util;
 use scrybe_core::types::{
-    BehavioralSignals, BrowserSignals, Fingerprint, FingerprintComponents, NetworkSignals, Session,
-    SessionId,
+    EnrichedSession, Enrichment, Fingerprint, FingerprintComponents, Provenance,
 };
-use scrybe_storage::{ClickHouseClient, SessionWriter};
-use std::net::IpAddr;
+use scrybe_storage::{
+    migrations::MIGRATIONS, BufferedSessionWriter, ClickHouseClient, MigrationState, Migrator,
+    SessionWriter, WriteBufferConfig,
+};
+use std::time::Duration;
 use testcontainers::{clients::Cli, core::WaitFor, GenericImage};
 
 /// Create a test ClickHouse container.
@@ -18,40 +21,17 @@ fn create_clickhouse_container() -> GenericImage {
 }
 
 /// Create a test session.
-fn create_test_session() -> Session {
-    Session {
-        id: SessionId::new(),
-        timestamp: chrono::Utc::now(),
-        fingerprint: Fingerprint {
+fn create_test_session() -> EnrichedSession {
+    let mut enriched = test_util::enriched_session();
+    enriched.fingerprint = Some(Enrichment::new(
+        Fingerprint {
             hash: "test-fingerprint-hash-123".to_string(),
             components: FingerprintComponents::default(),
             confidence: 0.95,
         },
-        network: NetworkSignals {
-            ip: "127.0.0.1".parse::<IpAddr>().unwrap(),
-            ja3: None,
-            ja4: None,
-            headers: vec![],
-            http_version: scrybe_core::types::HttpVersion::Http11,
-        },
-        browser: BrowserSignals {
-            user_agent: "Mozilla/5.0 Test".to_string(),
-            screen: scrybe_core::types::ScreenInfo::default(),
-            canvas_hash: None,
-            webgl_hash: None,
-            audio_hash: None,
-            fonts: vec![],
-            plugins: vec![],
-            timezone: "UTC".to_string(),
-            language: "en-US".to_string(),
-        },
-        behavioral: BehavioralSignals {
-            mouse_events: vec![],
-            scroll_events: vec![],
-            click_events: vec![],
-            timing: scrybe_core::types::TimingMetrics::default(),
-        },
-    }
+        Provenance::new("fingerprint", "test"),
+    ));
+    enriched
 }
 
 #[tokio::test]
@@ -107,6 +87,64 @@ async fn test_schema_initialization() {
     assert!(result.is_ok(), "Should be able to query sessions table");
 }
 
+#[tokio::test]
+#[ignore] // Requires Docker - run with `cargo test -- --ignored`
+async fn test_migrations_are_recorded_and_detect_drift() {
+    let docker = Cli::default();
+    let container = docker.run(create_clickhouse_container());
+    let port = container.get_host_port_ipv4(8123);
+
+    let url = format!("http://localhost:{}", port);
+    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+    let client = ClickHouseClient::new(&url, "default", "default", "")
+        .await
+        .expect("Failed to connect");
+    let migrator = Migrator::new(client.clone());
+
+    // Everything pending on an empty database, then applied once
+    let status = migrator.status().await.unwrap();
+    assert!(status.iter().all(|s| s.state == MigrationState::Pending));
+    assert_eq!(migrator.verify().await.is_err(), !MIGRATIONS.is_empty());
+
+    let applied = migrator.migrate().await.unwrap();
+    assert_eq!(applied.len(), MIGRATIONS.len());
+    assert!(migrator.migrate().await.unwrap().is_empty());
+    migrator.verify().await.expect("Schema should be current");
+
+    // A re-scored session is counted once by the stats view
+    let writer = SessionWriter::new(client.clone());
+    let mut session = create_test_session();
+    writer.write(&session).await.unwrap();
+    session.enriched_at += chrono::Duration::seconds(1);
+    writer.write(&session).await.unwrap();
+    let hourly: u64 = client
+        .client()
+        .query("SELECT sum(session_count) FROM session_stats_hourly")
+        .fetch_one()
+        .await
+        .unwrap();
+    assert_eq!(hourly, 1);
+
+    // A newer record with another checksum stands in for an edited file
+    client
+        .client()
+        .query("INSERT INTO schema_migrations (version, name, checksum) VALUES (1, 'create_sessions', 'edited')")
+        .execute()
+        .await
+        .unwrap();
+
+    let status = migrator.status().await.unwrap();
+    assert_eq!(
+        status[0].state,
+        MigrationState::Modified {
+            applied_checksum: "edited".to_string()
+        }
+    );
+    assert!(migrator.migrate().await.is_err());
+    assert!(migrator.verify().await.is_err());
+}
+
 #[tokio::test]
 #[ignore] // Requires Docker - run with `cargo test -- --ignored`
 async fn test_write_single_session() {
@@ -159,7 +197,7 @@ async fn test_write_batch_sessions() {
     let writer = SessionWriter::new(client.clone());
 
     // Create multiple test sessions
-    let sessions: Vec<Session> = (0..10).map(|_| create_test_session()).collect();
+    let sessions: Vec<EnrichedSession> = (0..10).map(|_| create_test_session()).collect();
 
     // Batch write
     writer
@@ -196,7 +234,7 @@ async fn test_query_by_fingerprint() {
 
     let writer = SessionWriter::new(client.clone());
     let session = create_test_session();
-    let fingerprint_hash = session.fingerprint.hash.clone();
+    let fingerprint_hash = session.fingerprint_hash().unwrap_or_default().to_string();
 
     writer.write(&session).await.expect("Write should succeed");
 
@@ -211,3 +249,126 @@ async fn test_query_by_fingerprint() {
 
     assert_eq!(count, 1, "Should find session by fingerprint");
 }
+
+#[tokio::test]
+#[ignore] // Requires Docker - run with `cargo test -- --ignored`
+async fn test_typed_signal_columns() {
+    let docker = Cli::default();
+    let container = docker.run(create_clickhouse_container());
+    let port = container.get_host_port_ipv4(8123);
+
+    let url = format!("http://localhost:{}", port);
+    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+    let client = ClickHouseClient::new(&url, "default", "default", "")
+        .await
+        .expect("Failed to connect");
+
+    client.init_schema().await.expect("Schema init failed");
+
+    let session = create_test_session();
+    SessionWriter::new(client.clone())
+        .write(&session)
+        .await
+        .expect("Write should succeed");
+
+    let (ip, http_version, timezone): (String, String, String) = client
+        .client()
+        .query("SELECT toString(client_ip), http_version, timezone FROM sessions")
+        .fetch_one()
+        .await
+        .expect("Typed columns should be queryable");
+    assert_eq!(ip, "::ffff:203.0.113.7");
+    assert_eq!(http_version, "HTTP/2");
+    assert_eq!(timezone, "UTC");
+
+    // Rows written without the typed columns fall back to their JSON
+    let mut old = session.session.clone();
+    old.id = scrybe_core::types::SessionId::new();
+    old.network.headers = vec![scrybe_core::types::Header::new("accept", "*/*")];
+    old.browser.timezone = "Asia/Tokyo".to_string();
+    client
+        .client()
+        .query(
+            "INSERT INTO sessions (session_id, timestamp, ip, network_signals, browser_signals, behavioral_signals) \
+             VALUES (?, now64(3), ?, ?, ?, ?)",
+        )
+        .bind(old.id.to_string())
+        .bind(old.network.ip.to_string())
+        .bind(serde_json::to_string(&old.network).unwrap())
+        .bind(serde_json::to_string(&old.browser).unwrap())
+        .bind(serde_json::to_string(&old.behavioral).unwrap())
+        .execute()
+        .await
+        .expect("Insert without typed columns should succeed");
+
+    let (timezone, accept): (String, String) = client
+        .client()
+        .query("SELECT timezone, http_headers['accept'] FROM sessions WHERE session_id = toUUID(?)")
+        .bind(old.id.to_string())
+        .fetch_one()
+        .await
+        .expect("Defaults should be extracted from JSON");
+    assert_eq!(timezone, "Asia/Tokyo");
+    assert_eq!(accept, "*/*");
+}
+
+#[tokio::test]
+#[ignore] // Requires Docker - run with `cargo test -- --ignored`
+async fn test_buffered_writer_flushes_on_size_time_and_close() {
+    let docker = Cli::default();
+    let container = docker.run(create_clickhouse_container());
+    let port = container.get_host_port_ipv4(8123);
+
+    let url = format!("http://localhost:{}", port);
+    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+
+    let client = ClickHouseClient::new(&url, "default", "default", "")
+        .await
+        .expect("Failed to connect");
+
+    client.init_schema().await.expect("Schema init failed");
+
+    let count = || async {
+        client
+            .client()
+            .query("SELECT count() FROM sessions")
+            .fetch_one::<u64>()
+            .await
+            .expect("Query should succeed")
+    };
+
+    let writer = BufferedSessionWriter::spawn(
+        SessionWriter::new(client.clone()),
+        WriteBufferConfig {
+            capacity: 100,
+            batch_size: 10,
+            flush_interval: Duration::from_millis(500),
+            ..WriteBufferConfig::default()
+        },
+        None,
+    )
+    .expect("Valid buffer config");
+
+    // A full batch is flushed without waiting for the interval
+    let sessions: Vec<EnrichedSession> = (0..10).map(|_| create_test_session()).collect();
+    let receipt = writer.write_batch(&sessions).await.unwrap();
+    receipt.wait().await.expect("Batch should be stored");
+    assert_eq!(count().await, 10);
+
+    // A partial batch is flushed by the interval
+    let receipt = writer.write_batch(&[create_test_session()]).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), receipt.wait())
+        .await
+        .expect("Interval flush")
+        .expect("Row should be stored");
+    assert_eq!(count().await, 11);
+
+    // Closing flushes what is left
+    let _receipt = writer
+        .write_batch(&[create_test_session(), create_test_session()])
+        .await
+        .unwrap();
+    writer.close().await.expect("Close should flush");
+    assert_eq!(count().await, 13);
+}
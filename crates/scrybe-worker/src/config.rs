@@ -0,0 +1,318 @@
+//! Worker configuration.
+
+use scrybe_cache::{KeySpace, RedisPoolConfig, RedisTopology};
+use scrybe_core::ScrybeError;
+use scrybe_enrichment::reputation::{ListFile, ListFormat};
+use scrybe_storage::{ClickHouseConfig, SpoolConfig, WriteBufferConfig};
+use std::collections::HashSet;
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Configuration for the enrichment worker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkerConfig {
+    /// Redis server, Sentinel or Cluster to connect to
+    pub redis: RedisTopology,
+    /// Redis connection pool settings
+    pub redis_pool: RedisPoolConfig,
+    /// Environment and tenant prefixing every Redis key
+    pub key_space: KeySpace,
+    /// ClickHouse connection settings
+    pub clickhouse: ClickHouseConfig,
+    /// Apply pending schema migrations at startup instead of requiring
+    /// `scrybe-worker migrate`
+    pub clickhouse_migrate: bool,
+    /// Buffering, flush and retry settings of the ClickHouse writer
+    pub writer_buffer: WriteBufferConfig,
+    /// Disk spool for rows ClickHouse cannot take, if enabled
+    pub writer_spool: Option<SpoolConfig>,
+    /// Consumer name within the consumer group (unique per worker)
+    pub consumer: String,
+    /// Maximum sessions read per batch
+    pub batch_size: usize,
+    /// How long a read blocks waiting for new sessions, in milliseconds
+    pub block_ms: usize,
+    /// How long an entry must be pending before another worker reclaims
+    /// it, in milliseconds
+    pub claim_idle_ms: u64,
+    /// Default timeout of each enrichment stage, in milliseconds
+    pub pipeline_stage_timeout_ms: u64,
+    /// Time budget for enriching one session, in milliseconds
+    pub pipeline_budget_ms: u64,
+    /// GeoLite2 City database path
+    pub geoip_city_db: Option<PathBuf>,
+    /// GeoLite2 ASN database path
+    pub geoip_asn_db: Option<PathBuf>,
+    /// IP reputation CIDR and ASN list files
+    pub reputation_lists: Vec<ListFile>,
+    /// Lowercase hosts that timing baselines are kept for
+    pub timing_sites: HashSet<String>,
+}
+
+impl WorkerConfig {
+    /// Load configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::ConfigError` if required environment variables
+    /// are missing or invalid.
+    pub fn from_env() -> Result<Self, ScrybeError> {
+        Self::from_vars(|key| env::var(key).ok())
+    }
+
+    /// Load configuration from a variable lookup.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, ScrybeError> {
+        let consumer = var("SCRYBE_WORKER_CONSUMER")
+            .or_else(|| var("HOSTNAME"))
+            .unwrap_or_else(|| format!("worker-{}", std::process::id()));
+
+        let mut reputation_lists = Vec::new();
+        if let Some(value) = var("SCRYBE_REPUTATION_CIDR_LISTS") {
+            reputation_lists.extend(ListFile::parse_all(&value, ListFormat::Cidr)?);
+        }
+        if let Some(value) = var("SCRYBE_REPUTATION_ASN_LISTS") {
+            reputation_lists.extend(ListFile::parse_all(&value, ListFormat::Asn)?);
+        }
+
+        let config = Self {
+            redis: RedisTopology::from_vars(&var)?.ok_or_else(|| {
+                ScrybeError::config_error(
+                    "Missing SCRYBE_REDIS_URL, SCRYBE_REDIS_SENTINEL_URLS or \
+                     SCRYBE_REDIS_CLUSTER_URLS",
+                )
+            })?,
+            redis_pool: RedisPoolConfig::from_vars(&var)?,
+            key_space: KeySpace::from_vars(&var)?,
+            clickhouse: ClickHouseConfig::from_vars(&var)?,
+            clickhouse_migrate: parse(&var, "SCRYBE_CLICKHOUSE_MIGRATE", true)?,
+            writer_buffer: WriteBufferConfig::from_vars(&var)?,
+            writer_spool: SpoolConfig::from_vars(&var)?,
+            consumer,
+            batch_size: parse(&var, "SCRYBE_WORKER_BATCH_SIZE", 100)?,
+            block_ms: parse(&var, "SCRYBE_WORKER_BLOCK_MS", 1_000)?,
+            claim_idle_ms: parse(&var, "SCRYBE_WORKER_CLAIM_IDLE_MS", 60_000)?,
+            // Scoring runs off the request path, so stages waiting on Redis
+            // get far more room than the gateway's latency target
+            pipeline_stage_timeout_ms: parse(&var, "SCRYBE_PIPELINE_STAGE_TIMEOUT_MS", 250)?,
+            pipeline_budget_ms: parse(&var, "SCRYBE_PIPELINE_BUDGET_MS", 1_000)?,
+            geoip_city_db: var("SCRYBE_GEOIP_CITY_DB").map(PathBuf::from),
+            geoip_asn_db: var("SCRYBE_GEOIP_ASN_DB").map(PathBuf::from),
+            reputation_lists,
+            timing_sites: var("SCRYBE_TIMING_SITES")
+                .map(|value| parse_sites(&value))
+                .unwrap_or_default(),
+        };
+
+        // Buffered entries must be stored before they look abandoned, or
+        // they are reclaimed and written twice
+        if config.writer_buffer.flush_interval.as_millis() >= u128::from(config.claim_idle_ms) {
+            return Err(ScrybeError::config_error(
+                "SCRYBE_WRITER_FLUSH_INTERVAL_MS must be below SCRYBE_WORKER_CLAIM_IDLE_MS",
+            ));
+        }
+
+        if config.pipeline_stage_timeout_ms == 0
+            || config.pipeline_stage_timeout_ms > config.pipeline_budget_ms
+        {
+            return Err(ScrybeError::config_error(
+                "SCRYBE_PIPELINE_STAGE_TIMEOUT_MS must be between 1 and SCRYBE_PIPELINE_BUDGET_MS",
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+/// Parse a comma-separated list of hosts.
+fn parse_sites(value: &str) -> HashSet<String> {
+    value
+        .split(',')
+        .map(|site| site.trim().to_ascii_lowercase())
+        .filter(|site| !site.is_empty())
+        .collect()
+}
+
+/// Parse an optional variable, falling back to a default.
+fn parse<T>(var: impl Fn(&str) -> Option<String>, key: &str, default: T) -> Result<T, ScrybeError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match var(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|e| ScrybeError::config_error(format!("Invalid {}: {}", key, e))),
+        None => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(vars: &[(&str, &str)]) -> Result<WorkerConfig, ScrybeError> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        WorkerConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("HOSTNAME", "worker-a"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.consumer, "worker-a");
+        assert_eq!(config.redis_pool, RedisPoolConfig::default());
+        assert_eq!(config.key_space, KeySpace::default());
+        assert_eq!(config.clickhouse.database, "scrybe");
+        assert!(config.clickhouse_migrate);
+        assert_eq!(config.writer_buffer, WriteBufferConfig::default());
+        assert!(config.writer_spool.is_none());
+        assert_eq!(config.batch_size, 100);
+        assert_eq!(config.claim_idle_ms, 60_000);
+        assert_eq!(config.pipeline_stage_timeout_ms, 250);
+        assert_eq!(config.pipeline_budget_ms, 1_000);
+        assert!(config.geoip_city_db.is_none());
+        assert!(config.reputation_lists.is_empty());
+        assert!(config.timing_sites.is_empty());
+    }
+
+    #[test]
+    fn test_timing_sites() {
+        let config = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("SCRYBE_TIMING_SITES", "Shop.Example.com, blog.example.com,"),
+        ])
+        .unwrap();
+
+        let mut sites: Vec<_> = config.timing_sites.iter().map(String::as_str).collect();
+        sites.sort_unstable();
+        assert_eq!(sites, vec!["blog.example.com", "shop.example.com"]);
+    }
+
+    #[test]
+    fn test_reputation_lists() {
+        let config = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            (
+                "SCRYBE_REPUTATION_CIDR_LISTS",
+                "aws:datacenter:/lists/aws.txt,tor:tor:/lists/tor.txt",
+            ),
+            ("SCRYBE_REPUTATION_ASN_LISTS", "vpn:vpn:/lists/vpn-asns.txt"),
+        ])
+        .unwrap();
+
+        let names: Vec<_> = config
+            .reputation_lists
+            .iter()
+            .map(|list| (list.name.as_str(), list.format))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("aws", ListFormat::Cidr),
+                ("tor", ListFormat::Cidr),
+                ("vpn", ListFormat::Asn)
+            ]
+        );
+
+        let result = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("SCRYBE_REPUTATION_ASN_LISTS", "vpn:/lists/vpn-asns.txt"),
+        ]);
+        assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_missing_required() {
+        let result = load(&[("SCRYBE_REDIS_URL", "redis://localhost:6379")]);
+        assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_cluster_topology() {
+        let config = load(&[
+            (
+                "SCRYBE_REDIS_CLUSTER_URLS",
+                "redis://n1:7000,redis://n2:7001",
+            ),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+        ])
+        .unwrap();
+
+        assert!(matches!(config.redis, RedisTopology::Cluster { urls } if urls.len() == 2));
+    }
+
+    #[test]
+    fn test_key_space() {
+        let config = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("SCRYBE_ENVIRONMENT", "staging"),
+            ("SCRYBE_TENANT", "site-a"),
+        ])
+        .unwrap();
+        assert_eq!(config.key_space.key("nonce:n"), "staging:site-a:nonce:n");
+
+        let result = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("SCRYBE_TENANT", "site:a"),
+        ]);
+        assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_flush_interval_below_claim_idle() {
+        let result = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("SCRYBE_WRITER_FLUSH_INTERVAL_MS", "60000"),
+        ]);
+        assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_pipeline_timeouts() {
+        let config = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("SCRYBE_PIPELINE_STAGE_TIMEOUT_MS", "100"),
+            ("SCRYBE_PIPELINE_BUDGET_MS", "400"),
+        ])
+        .unwrap();
+        assert_eq!(config.pipeline_stage_timeout_ms, 100);
+        assert_eq!(config.pipeline_budget_ms, 400);
+
+        for (timeout, budget) in [("0", "400"), ("500", "400")] {
+            let result = load(&[
+                ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+                ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+                ("SCRYBE_PIPELINE_STAGE_TIMEOUT_MS", timeout),
+                ("SCRYBE_PIPELINE_BUDGET_MS", budget),
+            ]);
+            assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+        }
+    }
+
+    #[test]
+    fn test_invalid_number() {
+        let result = load(&[
+            ("SCRYBE_REDIS_URL", "redis://localhost:6379"),
+            ("SCRYBE_CLICKHOUSE_URL", "http://localhost:8123"),
+            ("SCRYBE_WORKER_BATCH_SIZE", "lots"),
+        ]);
+        assert!(matches!(result, Err(ScrybeError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,234 @@
+//! # Scrybe Enrichment Worker
+//!
+//! Consumes sessions queued by the gateway, runs the enrichment pipeline,
+//! and writes enriched sessions to ClickHouse.
+//!
+//! ## Features
+//!
+//! - Redis Streams consumer groups for horizontal scaling
+//! - Reclaims sessions abandoned by dead workers (`XAUTOCLAIM`)
+//! - Batched ClickHouse writes, acknowledged only once stored
+//! - Links sessions sharing a fingerprint into visitors
+//! - Maintains the fingerprint/IP correlation index
+//! - Re-scores sessions updated by incremental event batches
+//! - Publishes high-scoring sessions to the anomaly feed
+//! - Graceful shutdown
+//!
+//! ## Commands
+//!
+//! - `scrybe-worker` - Apply pending schema migrations and run the worker
+//! - `scrybe-worker migrate` - Apply pending schema migrations and exit
+//! - `scrybe-worker migrate status` - List schema migrations and their state
+//!
+//! ## TigerStyle Compliance
+//!
+//! - No unwrap/panic in production code
+//! - Explicit error handling
+//! - Bounded batch sizes
+
+#![warn(missing_docs)]
+#![warn(rust_2018_idioms)]
+#![deny(unsafe_code)]
+
+mod config;
+mod shutdown;
+mod worker;
+
+use config::WorkerConfig;
+use scrybe_cache::{
+    AnomalyFeed, CanvasPopulations, CorrelationIndex, RedisClient, SessionCache, SessionQueue,
+    TimingBaselines, VelocityCounters, VisitorIndex,
+};
+use scrybe_core::{ScrybeError, Secret, SecretConfig};
+use scrybe_enrichment::{
+    reload::DEFAULT_RELOAD_INTERVAL,
+    spawn_reload,
+    stages::{
+        AutomationStage, CanvasPopulationStage, ConsistencyStage, CorrelationStage,
+        FingerprintStage, GeoStage, IpReputationStage, MovementStage, RenderNoiseStage,
+        TimingStage, VelocityStage, VisitorStage, WebGlStage,
+    },
+    EnrichmentStage, GeoEnricher, Pipeline, ReputationLists,
+};
+use scrybe_storage::{
+    BufferedSessionWriter, ClickHouseClient, ClickHouseConfig, MigrationState, Migrator,
+    SessionWriter, Spool,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+use worker::Worker;
+
+#[tokio::main]
+async fn main() -> Result<(), ScrybeError> {
+    // Initialize tracing
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::from_default_env()
+                .add_directive(tracing::Level::INFO.into()),
+        )
+        .init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        [] => run().await,
+        ["migrate"] => migrate().await,
+        ["migrate", "status"] => migration_status().await,
+        _ => Err(ScrybeError::config_error(
+            "Usage: scrybe-worker [migrate [status]]",
+        )),
+    }
+}
+
+/// Run the worker until shutdown.
+async fn run() -> Result<(), ScrybeError> {
+    info!("Starting Scrybe Worker...");
+
+    let config = WorkerConfig::from_env()?;
+    let ip_salt = SecretConfig::ip_hash_salt()?;
+
+    let redis_client = RedisClient::connect(&config.redis, config.redis_pool)
+        .await?
+        .with_key_space(config.key_space.clone());
+    let queue = SessionQueue::new(redis_client.clone(), None, None, None);
+    let sessions = SessionCache::new(redis_client.clone(), None, None);
+    let anomalies = AnomalyFeed::new(redis_client.clone(), None, None, None);
+    let visitors = VisitorIndex::new(redis_client.clone(), None);
+    let correlation = CorrelationIndex::new(redis_client.clone(), None, None);
+    let velocity = VelocityCounters::new(redis_client.clone(), None, None);
+    let timing = TimingBaselines::new(redis_client.clone(), None);
+    let canvas = CanvasPopulations::new(redis_client, None);
+
+    let clickhouse = ClickHouseClient::connect(&config.clickhouse).await?;
+    let migrator = Migrator::new(clickhouse.clone());
+    if config.clickhouse_migrate {
+        let applied = migrator.migrate().await?;
+        info!("Schema up to date ({} migrations applied)", applied.len());
+    } else {
+        migrator.verify().await?;
+    }
+
+    let spool = match &config.writer_spool {
+        Some(spool_config) => {
+            let spool = Spool::open(spool_config.clone())?;
+            info!(
+                "Spooling to {} when ClickHouse is unavailable ({} rows pending)",
+                spool_config.dir.display(),
+                spool.metrics().pending_rows
+            );
+            Some(spool)
+        }
+        None => {
+            warn!(
+                "SCRYBE_WRITER_SPOOL_DIR not set, rows are dropped if ClickHouse stays unavailable"
+            );
+            None
+        }
+    };
+    let writer =
+        BufferedSessionWriter::spawn(SessionWriter::new(clickhouse), config.writer_buffer, spool)?;
+
+    let pipeline = build_pipeline(
+        &config,
+        ip_salt,
+        visitors,
+        correlation,
+        velocity,
+        timing,
+        canvas,
+    )?;
+    info!("Enrichment stages: {:?}", pipeline.stage_names());
+
+    let worker = Worker::new(queue, sessions, anomalies, writer, pipeline, &config);
+    worker.run(shutdown::shutdown_signal()).await?;
+
+    info!("Worker shutdown complete");
+
+    Ok(())
+}
+
+/// Apply pending schema migrations.
+async fn migrate() -> Result<(), ScrybeError> {
+    let clickhouse = ClickHouseClient::connect(&ClickHouseConfig::from_env()?).await?;
+    let applied = Migrator::new(clickhouse).migrate().await?;
+    info!("Schema up to date ({} migrations applied)", applied.len());
+    Ok(())
+}
+
+/// Print every schema migration and its state.
+async fn migration_status() -> Result<(), ScrybeError> {
+    let clickhouse = ClickHouseClient::connect(&ClickHouseConfig::from_env()?).await?;
+    for status in Migrator::new(clickhouse).status().await? {
+        let state = match status.state {
+            MigrationState::Applied => "applied".to_string(),
+            MigrationState::Pending => "pending".to_string(),
+            MigrationState::Modified { applied_checksum } => {
+                format!("modified (applied as {})", applied_checksum)
+            }
+            MigrationState::Unknown => "unknown to this release".to_string(),
+        };
+        println!("{:04}  {:<32}  {}", status.version, status.name, state);
+    }
+    Ok(())
+}
+
+/// Build the enrichment pipeline with every stage.
+fn build_pipeline(
+    config: &WorkerConfig,
+    ip_salt: Secret<String>,
+    visitors: VisitorIndex,
+    correlation: CorrelationIndex,
+    velocity: VelocityCounters,
+    timing: TimingBaselines,
+    canvas: CanvasPopulations,
+) -> Result<Pipeline, ScrybeError> {
+    let geo = Arc::new(GeoEnricher::open(
+        config.geoip_city_db.as_deref(),
+        config.geoip_asn_db.as_deref(),
+    )?);
+    if !geo.is_empty() {
+        spawn_reload(geo.clone(), DEFAULT_RELOAD_INTERVAL);
+    }
+
+    // Fail at startup on a missing or invalid list rather than running
+    // without it
+    let reputation = Arc::new(ReputationLists::open(config.reputation_lists.clone())?);
+    if config.reputation_lists.is_empty() {
+        warn!("No IP reputation lists configured");
+    } else {
+        info!(
+            "Loaded {} IP reputation lists",
+            config.reputation_lists.len()
+        );
+        spawn_reload(reputation.clone(), DEFAULT_RELOAD_INTERVAL);
+    }
+
+    if config.timing_sites.is_empty() {
+        warn!("SCRYBE_TIMING_SITES not set, timing baselines disabled");
+    }
+
+    let ip_salt = ip_salt.into_inner().into_bytes();
+
+    let stages: Vec<Arc<dyn EnrichmentStage>> = vec![
+        Arc::new(FingerprintStage),
+        Arc::new(VisitorStage(visitors)),
+        Arc::new(CorrelationStage::new(correlation, ip_salt.clone())),
+        Arc::new(GeoStage(geo)),
+        Arc::new(VelocityStage::new(velocity, ip_salt)),
+        Arc::new(IpReputationStage(reputation)),
+        Arc::new(ConsistencyStage),
+        Arc::new(AutomationStage),
+        Arc::new(WebGlStage),
+        Arc::new(MovementStage),
+        Arc::new(RenderNoiseStage),
+        Arc::new(TimingStage::new(timing, config.timing_sites.clone())),
+        Arc::new(CanvasPopulationStage::new(canvas)),
+    ];
+
+    Pipeline::new(
+        stages,
+        Some(Duration::from_millis(config.pipeline_stage_timeout_ms)),
+        Some(Duration::from_millis(config.pipeline_budget_ms)),
+    )
+}
@@ -0,0 +1,43 @@
+//! Graceful shutdown handling.
+
+use tokio::signal;
+use tracing::{info, warn};
+
+/// Wait for shutdown signal (SIGTERM or Ctrl-C).
+///
+/// If a signal handler cannot be installed, that signal is ignored.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = signal::ctrl_c().await {
+            warn!("Failed to install Ctrl-C handler: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to install SIGTERM handler: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {
+            info!("Received Ctrl-C signal");
+        },
+        _ = terminate => {
+            info!("Received SIGTERM signal");
+        },
+    }
+
+    info!("Finishing current batch before shutdown...");
+}
@@ -0,0 +1,331 @@
+//! Enrichment worker loop.
+
+use crate::config::WorkerConfig;
+use scrybe_cache::{Anomaly, AnomalyFeed, QueuedSession, SessionCache, SessionQueue};
+use scrybe_core::{
+    types::{EnrichedSession, SessionId},
+    ScrybeError,
+};
+use scrybe_enrichment::Pipeline;
+use scrybe_storage::{BufferedSessionWriter, WriteReceipt};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// Pause after a failed poll before retrying.
+const ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+/// A write handed to the buffered writer and what to do once it is
+/// stored.
+struct PendingWrite {
+    receipt: WriteReceipt,
+    then: AfterWrite,
+}
+
+enum AfterWrite {
+    /// Acknowledge these queue entries
+    Ack(Vec<String>),
+    /// Nothing to acknowledge; mark these sessions dirty again on failure
+    Rescored(Vec<SessionId>),
+}
+
+/// Consumes queued sessions, enriches them, and writes them to storage.
+///
+/// Sessions are handed to a buffered writer, which inserts them in large
+/// batches. Entries are acknowledged only once their rows are stored, so a
+/// worker that dies with rows still buffered leaves its entries pending
+/// for another worker to reclaim. Between batches the worker also
+/// re-scores cached sessions marked dirty by incremental event batches.
+/// Sessions that score above the anomaly threshold are published to the
+/// anomaly feed.
+pub struct Worker {
+    queue: SessionQueue,
+    sessions: SessionCache,
+    anomalies: AnomalyFeed,
+    writer: BufferedSessionWriter,
+    pipeline: Pipeline,
+    consumer: String,
+    batch_size: usize,
+    block_ms: usize,
+    claim_idle_ms: u64,
+}
+
+impl Worker {
+    /// Create a new worker.
+    ///
+    /// # Arguments
+    ///
+    /// * `queue` - Enrichment queue to consume
+    /// * `sessions` - Session cache holding sessions to re-score
+    /// * `anomalies` - Feed receiving high-scoring sessions
+    /// * `writer` - Buffered storage writer for enriched sessions
+    /// * `pipeline` - Enrichment pipeline
+    /// * `config` - Consumer name, batch size, and timing settings
+    pub fn new(
+        queue: SessionQueue,
+        sessions: SessionCache,
+        anomalies: AnomalyFeed,
+        writer: BufferedSessionWriter,
+        pipeline: Pipeline,
+        config: &WorkerConfig,
+    ) -> Self {
+        Self {
+            queue,
+            sessions,
+            anomalies,
+            writer,
+            pipeline,
+            consumer: config.consumer.clone(),
+            batch_size: config.batch_size,
+            block_ms: config.block_ms,
+            claim_idle_ms: config.claim_idle_ms,
+        }
+    }
+
+    /// Process batches until `shutdown` completes.
+    ///
+    /// Shutdown is only observed between batches. On shutdown the buffered
+    /// rows are flushed and the entries they came from acknowledged before
+    /// the writer is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScrybeError::CacheError` if the consumer group cannot be
+    /// created. Errors while processing are logged and retried.
+    pub async fn run(self, shutdown: impl Future<Output = ()>) -> Result<(), ScrybeError> {
+        self.queue.ensure_group().await?;
+        info!("Worker '{}' consuming enrichment queue", self.consumer);
+
+        let mut pending = VecDeque::new();
+        tokio::pin!(shutdown);
+        loop {
+            let batch = tokio::select! {
+                _ = &mut shutdown => break,
+                batch = self.next_batch() => batch,
+            };
+
+            let result = match batch {
+                Ok(batch) => self.process(batch).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(write) => pending.extend(write),
+                Err(e) => {
+                    error!("Enrichment batch failed: {}", e);
+                    tokio::time::sleep(ERROR_BACKOFF).await;
+                }
+            }
+
+            match self.rescore_dirty().await {
+                Ok(write) => pending.extend(write),
+                Err(e) => {
+                    error!("Re-scoring dirty sessions failed: {}", e);
+                    tokio::time::sleep(ERROR_BACKOFF).await;
+                }
+            }
+
+            self.settle(&mut pending, false).await;
+        }
+
+        info!("Flushing buffered sessions...");
+        if let Err(e) = self.writer.flush().await {
+            error!("Final flush failed: {}", e);
+        }
+        self.settle(&mut pending, true).await;
+        self.writer.close().await?;
+
+        info!("Worker '{}' stopped", self.consumer);
+        Ok(())
+    }
+
+    /// Acknowledge or retry the writes whose rows have been flushed,
+    /// waiting for every one if `wait` is set.
+    ///
+    /// Entries of a failed write stay pending and are reclaimed later;
+    /// sessions of a failed re-score are marked dirty again.
+    async fn settle(&self, pending: &mut VecDeque<PendingWrite>, wait: bool) {
+        let mut unsettled = VecDeque::with_capacity(pending.len());
+        while let Some(PendingWrite { mut receipt, then }) = pending.pop_front() {
+            let result = if wait {
+                receipt.wait().await
+            } else {
+                match receipt.try_result() {
+                    Some(result) => result,
+                    None => {
+                        unsettled.push_back(PendingWrite { receipt, then });
+                        continue;
+                    }
+                }
+            };
+
+            match (result, then) {
+                (Ok(()), AfterWrite::Ack(ids)) => match self.queue.ack(&ids).await {
+                    Ok(acked) => debug!("Acknowledged {} stored entries", acked),
+                    Err(e) => warn!("Failed to acknowledge {} entries: {}", ids.len(), e),
+                },
+                (Ok(()), AfterWrite::Rescored(_)) => {}
+                (Err(e), AfterWrite::Ack(ids)) => {
+                    error!(
+                        "Write of {} entries failed, leaving them pending: {}",
+                        ids.len(),
+                        e
+                    );
+                }
+                (Err(e), AfterWrite::Rescored(ids)) => {
+                    error!("Write of {} re-scored sessions failed: {}", ids.len(), e);
+                    if let Err(e) = self.sessions.mark_dirty(&ids).await {
+                        warn!("Failed to mark sessions dirty again: {}", e);
+                    }
+                }
+            }
+        }
+        *pending = unsettled;
+    }
+
+    /// Reclaim entries abandoned by other consumers, or read new ones.
+    async fn next_batch(&self) -> Result<Vec<QueuedSession>, ScrybeError> {
+        let reclaimed = self
+            .queue
+            .reclaim(&self.consumer, self.claim_idle_ms, self.batch_size)
+            .await?;
+        if !reclaimed.is_empty() {
+            info!("Reclaimed {} pending sessions", reclaimed.len());
+            return Ok(reclaimed);
+        }
+
+        self.queue
+            .read(&self.consumer, self.batch_size, self.block_ms)
+            .await
+    }
+
+    /// Enrich a batch and queue it for writing.
+    ///
+    /// Entries that cannot be decoded or fail a critical enrichment stage
+    /// are acknowledged with the written ones without being written;
+    /// retrying would fail again.
+    async fn process(
+        &self,
+        batch: Vec<QueuedSession>,
+    ) -> Result<Option<PendingWrite>, ScrybeError> {
+        if batch.is_empty() {
+            return Ok(None);
+        }
+
+        let mut sessions = Vec::with_capacity(batch.len());
+        let mut ids = Vec::with_capacity(batch.len());
+        for entry in batch {
+            let Some(session) = entry.session else {
+                warn!("Dropping malformed queue entry {}", entry.id);
+                ids.push(entry.id);
+                continue;
+            };
+
+            let session_id = session.id;
+            match self.pipeline.enrich(session).await {
+                Ok((enriched, report)) => {
+                    debug!("Enriched session {} in {:?}", session_id, report.total);
+                    sessions.push(enriched);
+                }
+                Err(e) => warn!("Dropping session {}: {}", session_id, e),
+            }
+            ids.push(entry.id);
+        }
+
+        let receipt = self.writer.write_batch(&sessions).await?;
+        self.record_scores(&sessions).await;
+        self.publish_anomalies(&sessions).await;
+        debug!("Queued {} sessions for writing", sessions.len());
+
+        Ok(Some(PendingWrite {
+            receipt,
+            then: AfterWrite::Ack(ids),
+        }))
+    }
+
+    /// Re-enrich and queue for writing sessions marked dirty since they
+    /// were last scored.
+    ///
+    /// If the batch cannot be re-scored, or later fails to be written, its
+    /// sessions are marked dirty again for a later attempt.
+    async fn rescore_dirty(&self) -> Result<Option<PendingWrite>, ScrybeError> {
+        let ids = self.sessions.take_dirty(self.batch_size).await?;
+        if ids.is_empty() {
+            return Ok(None);
+        }
+
+        match self.rescore(&ids).await {
+            Ok(receipt) => Ok(Some(PendingWrite {
+                receipt,
+                then: AfterWrite::Rescored(ids),
+            })),
+            Err(e) => {
+                self.sessions.mark_dirty(&ids).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Re-enrich the given cached sessions and queue them for writing.
+    async fn rescore(&self, ids: &[SessionId]) -> Result<WriteReceipt, ScrybeError> {
+        let mut sessions = Vec::with_capacity(ids.len());
+        for id in ids {
+            // Sessions that expired since being marked have nothing to re-score
+            let Some(session) = self.sessions.get(id).await? else {
+                continue;
+            };
+
+            match self.pipeline.enrich(session).await {
+                Ok((enriched, _)) => sessions.push(enriched),
+                Err(e) => warn!("Dropping dirty session {}: {}", id, e),
+            }
+        }
+
+        let receipt = self.writer.write_batch(&sessions).await?;
+        self.record_scores(&sessions).await;
+        self.publish_anomalies(&sessions).await;
+        debug!("Re-scored {} dirty sessions", sessions.len());
+
+        Ok(receipt)
+    }
+
+    /// Record fingerprint hashes and bot probabilities of enriched
+    /// sessions in the session cache.
+    ///
+    /// The sessions are already queued for storage, so failures are logged
+    /// rather than failing the batch.
+    async fn record_scores(&self, sessions: &[EnrichedSession]) {
+        for enriched in sessions {
+            let Some(hash) = enriched.fingerprint_hash() else {
+                continue;
+            };
+
+            let session_id = enriched.session.id;
+            if let Err(e) = self
+                .sessions
+                .record_score(&session_id, hash, enriched.bot_probability())
+                .await
+            {
+                warn!("Failed to record score of session {}: {}", session_id, e);
+            }
+        }
+    }
+
+    /// Publish enriched sessions that score above the threshold to the
+    /// anomaly feed.
+    ///
+    /// The sessions are already queued for storage, so failures are logged
+    /// rather than failing the batch.
+    async fn publish_anomalies(&self, sessions: &[EnrichedSession]) {
+        for enriched in sessions {
+            if enriched.bot_probability() < self.anomalies.threshold() {
+                continue;
+            }
+
+            let anomaly = Anomaly::from_enriched(enriched);
+            if let Err(e) = self.anomalies.record(&anomaly).await {
+                warn!("Failed to publish anomaly {}: {}", anomaly.session_id, e);
+            }
+        }
+    }
+}